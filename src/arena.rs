@@ -0,0 +1,691 @@
+use html5ever::{local_name, namespace_url, ns, serialize::{Serialize, Serializer, TraversalScope}, tendril::{StrTendril, TendrilSink}, Attribute, ExpandedName, QualName};
+use html5ever::interface::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use std::{borrow::Cow, cell::{self, Cell, RefCell}, collections::HashSet, iter, ptr};
+use crate::{attributes::Attributes, diagnostics::{self, ParseResult}, encoding::{self, DecodeOptions, DecodedParse}, incremental::IncrementalParser, json::{self, JsonError}, node::{self, DomNode, NodeKind}};
+
+pub type Arena<'arena> = &'arena typed_arena::Arena<Node<'arena>>;
+pub type Ref<'arena> = &'arena Node<'arena>;
+pub type Link<'arena> = Cell<Option<Ref<'arena>>>;
+
+/// Owns the `typed_arena::Arena` every node of a parsed tree is allocated in.
+///
+/// Nodes borrow from the `ArenaDom` they were parsed into, so it has to outlive every `Ref` it hands out.
+#[derive(Default)]
+pub struct ArenaDom<'arena> {
+  arena: typed_arena::Arena<Node<'arena>>,
+}
+
+impl<'arena> ArenaDom<'arena> {
+  pub fn new() -> Self {
+    ArenaDom {
+      arena: typed_arena::Arena::new(),
+    }
+  }
+
+  pub fn arena(&'arena self) -> Arena<'arena> {
+    &self.arena
+  }
+
+  pub fn sink(&'arena self) -> Sink<'arena> {
+    Sink::new(&self.arena)
+  }
+
+  pub fn parse_document(&'arena self, html: &str) -> Ref<'arena> {
+    parse_document(&self.arena, html)
+  }
+
+  pub fn parse_fragment(&'arena self, context_name: QualName, context_attrs: Vec<Attribute>, html: &str) -> Ref<'arena> {
+    parse_fragment(&self.arena, context_name, context_attrs, html)
+  }
+
+  pub fn parse_fragment_children(&'arena self, context_element: Ref<'arena>, html: &str) -> Vec<Ref<'arena>> {
+    parse_fragment_children(&self.arena, context_element, html)
+  }
+
+  pub fn parse_document_with_diagnostics(&'arena self, html: &str) -> ParseResult<Ref<'arena>> {
+    diagnostics::parse_document_with_diagnostics(self.sink(), html)
+  }
+
+  pub fn parse_fragment_with_diagnostics(&'arena self, context_name: QualName, context_attrs: Vec<Attribute>, html: &str) -> ParseResult<Ref<'arena>> {
+    diagnostics::parse_fragment_with_diagnostics(self.sink(), context_name, context_attrs, html)
+  }
+
+  /// Decodes and parses a document given as bytes; see `encoding::parse_bytes`.
+  pub fn parse_bytes(&'arena self, bytes: &[u8], options: &DecodeOptions) -> DecodedParse<Ref<'arena>> {
+    encoding::parse_bytes(|| self.sink(), bytes, options)
+  }
+
+  /// A parser to feed byte chunks into this arena as they arrive.
+  pub fn incremental_parser(&'arena self, options: &DecodeOptions) -> IncrementalParser<Sink<'arena>> {
+    IncrementalParser::with_options(self.sink(), options)
+  }
+
+  /// Rebuilds a tree exported with `json::to_json` in this arena.
+  pub fn from_json(&'arena self, value: &serde_json::Value) -> Result<Ref<'arena>, JsonError> {
+    json::from_json_arena(&self.arena, value)
+  }
+}
+
+/// Parses a whole document into `arena` and returns its `Document` node.
+pub fn parse_document<'arena>(arena: Arena<'arena>, html: &str) -> Ref<'arena> {
+  html5ever::parse_document(Sink::new(arena), Default::default()).one(html)
+}
+
+/// Parses `html` as if it were the content of a `context_name` element and returns the fragment's `Document` node.
+pub fn parse_fragment<'arena>(arena: Arena<'arena>, context_name: QualName, context_attrs: Vec<Attribute>, html: &str) -> Ref<'arena> {
+  html5ever::parse_fragment(Sink::new(arena), Default::default(), context_name, context_attrs).one(html)
+}
+
+/// Parses `html` as the content of `context_element` (`<body>` when it is not an element) and returns the parsed nodes,
+/// detached so they can be appended anywhere in the same arena.
+pub fn parse_fragment_children<'arena>(arena: Arena<'arena>, context_element: Ref<'arena>, html: &str) -> Vec<Ref<'arena>> {
+  let (name, attrs) = match context_element.data {
+    NodeData::Element { ref name, ref attrs, .. } => (name.clone(), attrs.borrow().clone()),
+    _ => (QualName::new(None, ns!(html), local_name!("body")), vec![]),
+  };
+  let document = parse_fragment(arena, name, attrs, html);
+  // The fragment parser puts everything inside an `<html>` element under the document.
+  let nodes: Vec<Ref<'arena>> = document.first_child.get().map(|root| root.children().collect()).unwrap_or_default();
+  for node in nodes.iter() {
+    node.detach();
+  }
+  nodes
+}
+
+pub struct Sink<'arena> {
+  arena: Arena<'arena>,
+  document: Ref<'arena>,
+  quirks_mode: QuirksMode,
+  errors: Vec<Cow<'static, str>>,
+}
+
+impl<'arena> Sink<'arena> {
+  pub fn new(arena: Arena<'arena>) -> Self {
+    Sink {
+      arena,
+      document: arena.alloc(Node::new(NodeData::Document)),
+      quirks_mode: QuirksMode::NoQuirks,
+      errors: vec![],
+    }
+  }
+
+  pub fn quirks_mode(&self) -> QuirksMode {
+    self.quirks_mode
+  }
+
+  /// Parse errors reported so far, like `RcDom::errors`.
+  pub fn errors(&self) -> &[Cow<'static, str>] {
+    &self.errors
+  }
+
+  fn new_node(&self, data: NodeData<'arena>) -> Ref<'arena> {
+    self.arena.alloc(Node::new(data))
+  }
+
+  fn append_common<P, A>(&self, child: NodeOrText<Ref<'arena>>, previous: P, append: A)
+  where
+    P: FnOnce() -> Option<Ref<'arena>>,
+    A: FnOnce(Ref<'arena>),
+  {
+    let new_node = match child {
+      NodeOrText::AppendText(text) => {
+        // Append to an existing Text node if we have one.
+        if let Some(&Node {
+          data: NodeData::Text { ref contents },
+          ..
+        }) = previous()
+        {
+          contents.borrow_mut().push_tendril(&text);
+          return;
+        }
+        self.new_node(NodeData::Text {
+          contents: RefCell::new(text),
+        })
+      },
+      NodeOrText::AppendNode(node) => node,
+    };
+
+    append(new_node)
+  }
+}
+
+impl<'arena> TreeSink for Sink<'arena> {
+  type Handle = Ref<'arena>;
+  type Output = Ref<'arena>;
+
+  fn finish(self) -> Ref<'arena> {
+    self.document
+  }
+
+  fn parse_error(&mut self, msg: Cow<'static, str>) {
+    self.errors.push(msg);
+  }
+
+  fn get_document(&mut self) -> Ref<'arena> {
+    self.document
+  }
+
+  fn set_quirks_mode(&mut self, mode: QuirksMode) {
+    self.quirks_mode = mode;
+  }
+
+  fn same_node(&self, x: &Ref<'arena>, y: &Ref<'arena>) -> bool {
+    ptr::eq::<Node>(*x, *y)
+  }
+
+  fn elem_name<'a>(&self, target: &'a Ref<'arena>) -> ExpandedName<'a> {
+    match target.data {
+      NodeData::Element { ref name, .. } => name.expanded(),
+      _ => panic!("not an element!"),
+    }
+  }
+
+  fn get_template_contents(&mut self, target: &Ref<'arena>) -> Ref<'arena> {
+    if let NodeData::Element {
+      template_contents: Some(contents),
+      ..
+    } = target.data
+    {
+      contents
+    } else {
+      panic!("not a template element!")
+    }
+  }
+
+  fn is_mathml_annotation_xml_integration_point(&self, target: &Ref<'arena>) -> bool {
+    if let NodeData::Element {
+      mathml_annotation_xml_integration_point,
+      ..
+    } = target.data
+    {
+      mathml_annotation_xml_integration_point
+    } else {
+      panic!("not an element!")
+    }
+  }
+
+  fn create_element(
+    &mut self,
+    name: QualName,
+    attrs: Vec<Attribute>,
+    flags: ElementFlags,
+  ) -> Ref<'arena> {
+    self.new_node(NodeData::Element {
+      name,
+      attrs: RefCell::new(attrs),
+      template_contents: if flags.template {
+        Some(self.new_node(NodeData::Document))
+      } else {
+        None
+      },
+      mathml_annotation_xml_integration_point: flags.mathml_annotation_xml_integration_point,
+    })
+  }
+
+  fn create_comment(&mut self, text: StrTendril) -> Ref<'arena> {
+    self.new_node(NodeData::Comment { contents: text })
+  }
+
+  fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> Ref<'arena> {
+    self.new_node(NodeData::ProcessingInstruction {
+      target,
+      contents: data,
+    })
+  }
+
+  fn append(&mut self, parent: &Ref<'arena>, child: NodeOrText<Ref<'arena>>) {
+    self.append_common(
+      child,
+      || parent.last_child.get(),
+      |new_node| parent.append(new_node),
+    )
+  }
+
+  fn append_before_sibling(&mut self, sibling: &Ref<'arena>, child: NodeOrText<Ref<'arena>>) {
+    self.append_common(
+      child,
+      || sibling.previous_sibling.get(),
+      |new_node| sibling.insert_before(new_node),
+    )
+  }
+
+  fn append_based_on_parent_node(
+    &mut self,
+    element: &Ref<'arena>,
+    prev_element: &Ref<'arena>,
+    child: NodeOrText<Ref<'arena>>,
+  ) {
+    if element.parent.get().is_some() {
+      self.append_before_sibling(element, child)
+    } else {
+      self.append(prev_element, child)
+    }
+  }
+
+  fn append_doctype_to_document(
+    &mut self,
+    name: StrTendril,
+    public_id: StrTendril,
+    system_id: StrTendril,
+  ) {
+    self.document.append(self.new_node(NodeData::Doctype {
+      name,
+      public_id,
+      system_id,
+    }))
+  }
+
+  fn add_attrs_if_missing(&mut self, target: &Ref<'arena>, attrs: Vec<Attribute>) {
+    let mut existing = if let NodeData::Element { ref attrs, .. } = target.data {
+      attrs.borrow_mut()
+    } else {
+      panic!("not an element")
+    };
+
+    let existing_names = existing
+      .iter()
+      .map(|e| e.name.clone())
+      .collect::<HashSet<_>>();
+    existing.extend(
+      attrs
+        .into_iter()
+        .filter(|attr| !existing_names.contains(&attr.name)),
+    );
+  }
+
+  fn remove_from_parent(&mut self, target: &Ref<'arena>) {
+    target.detach()
+  }
+
+  fn reparent_children(&mut self, node: &Ref<'arena>, new_parent: &Ref<'arena>) {
+    let mut next_child = node.first_child.get();
+    while let Some(child) = next_child {
+      debug_assert!(ptr::eq::<Node>(child.parent.get().unwrap(), *node));
+      next_child = child.next_sibling.get();
+      new_parent.append(child)
+    }
+  }
+}
+
+#[derive(Clone)]
+pub struct Node<'arena> {
+  pub parent: Link<'arena>,
+  pub next_sibling: Link<'arena>,
+  pub previous_sibling: Link<'arena>,
+  pub first_child: Link<'arena>,
+  pub last_child: Link<'arena>,
+  pub data: NodeData<'arena>,
+}
+
+impl<'arena> Node<'arena> {
+  pub fn new(data: NodeData<'arena>) -> Self {
+    Node {
+      parent: Cell::new(None),
+      previous_sibling: Cell::new(None),
+      next_sibling: Cell::new(None),
+      first_child: Cell::new(None),
+      last_child: Cell::new(None),
+      data,
+    }
+  }
+
+  pub fn detach(&self) {
+    let parent = self.parent.take();
+    let previous_sibling = self.previous_sibling.take();
+    let next_sibling = self.next_sibling.take();
+
+    if let Some(next_sibling) = next_sibling {
+      next_sibling.previous_sibling.set(previous_sibling);
+    } else if let Some(parent) = parent {
+      parent.last_child.set(previous_sibling);
+    }
+
+    if let Some(previous_sibling) = previous_sibling {
+      previous_sibling.next_sibling.set(next_sibling);
+    } else if let Some(parent) = parent {
+      parent.first_child.set(next_sibling);
+    }
+  }
+
+  pub fn append(&'arena self, new_child: &'arena Self) {
+    new_child.detach();
+    new_child.parent.set(Some(self));
+    if let Some(last_child) = self.last_child.take() {
+      new_child.previous_sibling.set(Some(last_child));
+      debug_assert!(last_child.next_sibling.get().is_none());
+      last_child.next_sibling.set(Some(new_child));
+    } else {
+      debug_assert!(self.first_child.get().is_none());
+      self.first_child.set(Some(new_child));
+    }
+    self.last_child.set(Some(new_child));
+  }
+
+  pub fn insert_before(&'arena self, new_sibling: &'arena Self) {
+    new_sibling.detach();
+    new_sibling.parent.set(self.parent.get());
+    new_sibling.next_sibling.set(Some(self));
+    if let Some(previous_sibling) = self.previous_sibling.take() {
+      new_sibling.previous_sibling.set(Some(previous_sibling));
+      debug_assert!(ptr::eq::<Node>(
+        previous_sibling.next_sibling.get().unwrap(),
+        self
+      ));
+      previous_sibling.next_sibling.set(Some(new_sibling));
+    } else if let Some(parent) = self.parent.get() {
+      debug_assert!(ptr::eq::<Node>(parent.first_child.get().unwrap(), self));
+      parent.first_child.set(Some(new_sibling));
+    }
+    self.previous_sibling.set(Some(new_sibling));
+  }
+
+  pub fn children(&self) -> Children<'arena> {
+    Children {
+      next: self.first_child.get(),
+      next_back: self.last_child.get(),
+    }
+  }
+
+  pub fn reverse_children(&self) -> ReverseChildren<'arena> {
+    ReverseChildren(self.last_child.get())
+  }
+
+  /// This node's parent, grandparent and so on up to the root. The node itself is not included.
+  pub fn ancestors(&self) -> Ancestors<'arena> {
+    Ancestors(self.parent.get())
+  }
+
+  /// Siblings before this node, nearest first.
+  pub fn preceding_siblings(&self) -> PrecedingSiblings<'arena> {
+    PrecedingSiblings(self.previous_sibling.get())
+  }
+
+  /// Siblings after this node, nearest first.
+  pub fn following_siblings(&self) -> FollowingSiblings<'arena> {
+    FollowingSiblings(self.next_sibling.get())
+  }
+
+  /// This node and all of its descendants in tree order.
+  pub fn descendants(&'arena self) -> Descendants<'arena> {
+    Descendants(self.traverse())
+  }
+
+  /// Open/close edges of this node and its descendants in tree order.
+  pub fn traverse(&'arena self) -> Traverse<'arena> {
+    Traverse {
+      root: self,
+      next: Some(NodeEdge::Start(self)),
+    }
+  }
+}
+
+pub struct Children<'arena> {
+  next: Option<Ref<'arena>>,
+  next_back: Option<Ref<'arena>>,
+}
+
+impl<'arena> Iterator for Children<'arena> {
+  type Item = Ref<'arena>;
+
+  fn next(&mut self) -> Option<Ref<'arena>> {
+    let node = self.next?;
+    if self.next_back.is_some_and(|back| ptr::eq::<Node>(back, node)) {
+      self.next = None;
+      self.next_back = None;
+    } else {
+      self.next = node.next_sibling.get();
+    }
+    Some(node)
+  }
+}
+
+impl<'arena> DoubleEndedIterator for Children<'arena> {
+  fn next_back(&mut self) -> Option<Ref<'arena>> {
+    let node = self.next_back?;
+    if self.next.is_some_and(|front| ptr::eq::<Node>(front, node)) {
+      self.next = None;
+      self.next_back = None;
+    } else {
+      self.next_back = node.previous_sibling.get();
+    }
+    Some(node)
+  }
+}
+
+macro_rules! link_iterator {
+  ($name: ident, $link: ident) => {
+    pub struct $name<'arena>(Option<Ref<'arena>>);
+
+    impl<'arena> Iterator for $name<'arena> {
+      type Item = Ref<'arena>;
+
+      fn next(&mut self) -> Option<Ref<'arena>> {
+        let node = self.0?;
+        self.0 = node.$link.get();
+        Some(node)
+      }
+    }
+  };
+}
+
+link_iterator!(ReverseChildren, previous_sibling);
+link_iterator!(Ancestors, parent);
+link_iterator!(PrecedingSiblings, previous_sibling);
+link_iterator!(FollowingSiblings, next_sibling);
+
+#[derive(Clone, Copy)]
+pub enum NodeEdge<'arena> {
+  Start(Ref<'arena>),
+  End(Ref<'arena>),
+}
+
+pub struct Traverse<'arena> {
+  root: Ref<'arena>,
+  next: Option<NodeEdge<'arena>>,
+}
+
+impl<'arena> Iterator for Traverse<'arena> {
+  type Item = NodeEdge<'arena>;
+
+  fn next(&mut self) -> Option<NodeEdge<'arena>> {
+    let edge = self.next?;
+    self.next = match edge {
+      NodeEdge::Start(node) => match node.first_child.get() {
+        Some(first_child) => Some(NodeEdge::Start(first_child)),
+        None => Some(NodeEdge::End(node)),
+      },
+      NodeEdge::End(node) => {
+        if ptr::eq::<Node>(node, self.root) {
+          None
+        } else {
+          match node.next_sibling.get() {
+            Some(next_sibling) => Some(NodeEdge::Start(next_sibling)),
+            None => node.parent.get().map(NodeEdge::End),
+          }
+        }
+      },
+    };
+    Some(edge)
+  }
+}
+
+pub struct Descendants<'arena>(Traverse<'arena>);
+
+impl<'arena> Iterator for Descendants<'arena> {
+  type Item = Ref<'arena>;
+
+  fn next(&mut self) -> Option<Ref<'arena>> {
+    for edge in self.0.by_ref() {
+      if let NodeEdge::Start(node) = edge {
+        return Some(node);
+      }
+    }
+    None
+  }
+}
+
+#[derive(Clone)]
+pub enum NodeData<'arena> {
+  Document,
+  Doctype {
+    name: StrTendril,
+    public_id: StrTendril,
+    system_id: StrTendril,
+  },
+  Text {
+    contents: RefCell<StrTendril>,
+  },
+  Comment {
+    contents: StrTendril,
+  },
+  Element {
+    name: QualName,
+    attrs: RefCell<Vec<Attribute>>,
+    template_contents: Option<Ref<'arena>>,
+    mathml_annotation_xml_integration_point: bool,
+  },
+  ProcessingInstruction {
+    target: StrTendril,
+    contents: StrTendril,
+  },
+}
+
+/// Implemented for any borrow of an arena node, not just `Ref<'arena>`, so that a `&self` in the node's own trait
+/// impls can be read through it too.
+impl<'a, 'arena> DomNode for &'a Node<'arena> {
+  fn kind(&self) -> NodeKind {
+    match self.data {
+      NodeData::Document => NodeKind::Document,
+      NodeData::Doctype { .. } => NodeKind::Doctype,
+      NodeData::Text { .. } => NodeKind::Text,
+      NodeData::Comment { .. } => NodeKind::Comment,
+      NodeData::Element { .. } => NodeKind::Element,
+      NodeData::ProcessingInstruction { .. } => NodeKind::ProcessingInstruction,
+    }
+  }
+
+  fn same_node(&self, other: &Self) -> bool {
+    ptr::eq::<Node>(*self, *other)
+  }
+
+  fn parent(&self) -> Option<Self> {
+    self.parent.get()
+  }
+
+  fn children(&self) -> Vec<Self> {
+    Node::children(self).map(|child| child as Self).collect()
+  }
+
+  fn previous_sibling(&self) -> Option<Self> {
+    self.previous_sibling.get()
+  }
+
+  fn next_sibling(&self) -> Option<Self> {
+    self.next_sibling.get()
+  }
+
+  fn index_in_parent(&self) -> Option<usize> {
+    self.parent.get()?;
+    Some(Node::preceding_siblings(self).count())
+  }
+
+  fn element_name(&self) -> Option<&QualName> {
+    match self.data {
+      NodeData::Element { ref name, .. } => Some(name),
+      _ => None,
+    }
+  }
+
+  fn attrs(&self) -> Option<cell::Ref<'_, Vec<Attribute>>> {
+    match self.data {
+      NodeData::Element { ref attrs, .. } => Some(attrs.borrow()),
+      _ => None,
+    }
+  }
+
+  fn text(&self) -> Option<cell::Ref<'_, StrTendril>> {
+    match self.data {
+      NodeData::Text { ref contents } => Some(contents.borrow()),
+      _ => None,
+    }
+  }
+
+  fn comment(&self) -> Option<&StrTendril> {
+    match self.data {
+      NodeData::Comment { ref contents } => Some(contents),
+      NodeData::ProcessingInstruction { ref contents, .. } => Some(contents),
+      _ => None,
+    }
+  }
+
+  fn processing_instruction_target(&self) -> Option<&StrTendril> {
+    match self.data {
+      NodeData::ProcessingInstruction { ref target, .. } => Some(target),
+      _ => None,
+    }
+  }
+
+  fn template_contents(&self) -> Option<Self> {
+    match self.data {
+      NodeData::Element { template_contents, .. } => template_contents,
+      _ => None,
+    }
+  }
+
+  fn doctype(&self) -> Option<(&StrTendril, &StrTendril, &StrTendril)> {
+    match self.data {
+      NodeData::Doctype {
+        ref name,
+        ref public_id,
+        ref system_id,
+      } => Some((name, public_id, system_id)),
+      _ => None,
+    }
+  }
+
+  fn ancestors(&self) -> Vec<Self> {
+    Node::ancestors(self).map(|ancestor| ancestor as Self).collect()
+  }
+
+  fn descendants(&self) -> Vec<Self> {
+    iter::once(*self).chain(Node::children(self).flat_map(Node::descendants).map(|node| node as Self)).collect()
+  }
+}
+
+impl<'arena> Attributes for Ref<'arena> {
+  fn attributes(&self) -> Option<&RefCell<Vec<Attribute>>> {
+    match self.data {
+      NodeData::Element { ref attrs, .. } => Some(attrs),
+      _ => None,
+    }
+  }
+
+  fn is_html_element(&self) -> bool {
+    matches!(self.data, NodeData::Element { ref name, .. } if name.ns == ns!(html))
+  }
+}
+
+/// Serializes byte for byte like `markup5ever_rcdom::SerializableHandle`, so `<template>` contents are dropped; wrap
+/// the node in `HtmlNode` to write them out. A document under `TraversalScope::IncludeNode`, where `SerializableHandle`
+/// panics, is written out as its children.
+impl<'arena> Serialize for Node<'arena> {
+  fn serialize<S>(&self, serializer: &mut S, traversal_scope: TraversalScope) -> std::io::Result<()>
+  where
+    S: Serializer
+  {
+    node::serialize(&self, serializer, traversal_scope, false)
+  }
+}
+
+/// Serializes like the node itself, except that `<template>` contents are written out like a browser's `innerHTML`
+/// and `outerHTML` instead of being dropped.
+pub struct HtmlNode<'arena>(pub Ref<'arena>);
+
+impl<'arena> Serialize for HtmlNode<'arena> {
+  fn serialize<S>(&self, serializer: &mut S, traversal_scope: TraversalScope) -> std::io::Result<()>
+  where
+    S: Serializer
+  {
+    node::serialize(&self.0, serializer, traversal_scope, true)
+  }
+}
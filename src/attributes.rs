@@ -0,0 +1,290 @@
+use html5ever::{namespace_url, ns, Attribute, LocalName, Namespace, Prefix, QualName};
+use std::cell::RefCell;
+
+/// `prefix:local`, or just `local` without a prefix, which is what `getAttribute` matches against.
+fn qualified_name(name: &QualName) -> String {
+  match name.prefix {
+    Some(ref prefix) => format!("{}:{}", prefix, name.local),
+    None => name.local.to_string(),
+  }
+}
+
+/// Read and write access to an element's attributes, implemented for both `markup5ever_rcdom::Handle` and the arena
+/// `Ref<'arena>`.
+///
+/// The plain methods follow `getAttribute`/`setAttribute`: they match the qualified name (`xlink:href`, `xml:lang`),
+/// lowercased first on HTML elements. The `_ns` variants match a namespace and local name instead. Every method is a
+/// no-op or returns `None`/`false` on nodes that are not elements.
+pub trait Attributes {
+  fn attributes(&self) -> Option<&RefCell<Vec<Attribute>>>;
+
+  /// Whether names should be lowercased, as they are for elements in the HTML namespace.
+  fn is_html_element(&self) -> bool;
+
+  fn normalize_name(&self, name: &str) -> String {
+    if self.is_html_element() {
+      name.to_ascii_lowercase()
+    } else {
+      name.to_string()
+    }
+  }
+
+  fn get_attr(&self, name: &str) -> Option<String> {
+    let name = self.normalize_name(name);
+    let attrs = self.attributes()?.borrow();
+    attrs.iter().find(|attr| qualified_name(&attr.name) == name).map(|attr| attr.value.to_string())
+  }
+
+  fn has_attr(&self, name: &str) -> bool {
+    self.get_attr(name).is_some()
+  }
+
+  /// Changes the first attribute with this name, or appends a new one without a namespace.
+  fn set_attr(&self, name: &str, value: &str) {
+    let name = self.normalize_name(name);
+    let mut attrs = match self.attributes() {
+      Some(attrs) => attrs.borrow_mut(),
+      None => return,
+    };
+    match attrs.iter_mut().find(|attr| qualified_name(&attr.name) == name) {
+      Some(attr) => attr.value = value.into(),
+      None => attrs.push(Attribute {
+        name: QualName::new(None, ns!(), LocalName::from(name)),
+        value: value.into(),
+      }),
+    }
+  }
+
+  /// Removes every attribute with this name, returning whether there was one.
+  fn remove_attr(&self, name: &str) -> bool {
+    let name = self.normalize_name(name);
+    let mut attrs = match self.attributes() {
+      Some(attrs) => attrs.borrow_mut(),
+      None => return false,
+    };
+    let before = attrs.len();
+    attrs.retain(|attr| qualified_name(&attr.name) != name);
+    attrs.len() != before
+  }
+
+  /// Adds the attribute with an empty value when it is missing and removes it otherwise, unless `force` says which.
+  /// Returns whether the attribute is present afterwards.
+  fn toggle_attr(&self, name: &str, force: Option<bool>) -> bool {
+    if self.attributes().is_none() {
+      return false;
+    }
+    let present = self.has_attr(name);
+    match (present, force) {
+      (true, None) | (true, Some(false)) => {
+        self.remove_attr(name);
+        false
+      },
+      (false, None) | (false, Some(true)) => {
+        self.set_attr(name, "");
+        true
+      },
+      (true, Some(true)) => true,
+      (false, Some(false)) => false,
+    }
+  }
+
+  fn get_attr_ns(&self, namespace: &Namespace, local_name: &str) -> Option<String> {
+    let attrs = self.attributes()?.borrow();
+    attrs
+      .iter()
+      .find(|attr| attr.name.ns == *namespace && &*attr.name.local == local_name)
+      .map(|attr| attr.value.to_string())
+  }
+
+  fn has_attr_ns(&self, namespace: &Namespace, local_name: &str) -> bool {
+    self.get_attr_ns(namespace, local_name).is_some()
+  }
+
+  /// Sets the attribute in `namespace`, taking the prefix (if any) from `qualified_name`, e.g. `xlink:href`.
+  fn set_attr_ns(&self, namespace: &Namespace, qualified_name: &str, value: &str) {
+    let (prefix, local) = match qualified_name.split_once(':') {
+      Some((prefix, local)) => (Some(Prefix::from(prefix)), LocalName::from(local)),
+      None => (None, LocalName::from(qualified_name)),
+    };
+    let mut attrs = match self.attributes() {
+      Some(attrs) => attrs.borrow_mut(),
+      None => return,
+    };
+    match attrs.iter_mut().find(|attr| attr.name.ns == *namespace && attr.name.local == local) {
+      Some(attr) => {
+        attr.name.prefix = prefix;
+        attr.value = value.into();
+      },
+      None => attrs.push(Attribute {
+        name: QualName::new(prefix, namespace.clone(), local),
+        value: value.into(),
+      }),
+    }
+  }
+
+  fn remove_attr_ns(&self, namespace: &Namespace, local_name: &str) -> bool {
+    let mut attrs = match self.attributes() {
+      Some(attrs) => attrs.borrow_mut(),
+      None => return false,
+    };
+    let before = attrs.len();
+    attrs.retain(|attr| !(attr.name.ns == *namespace && &*attr.name.local == local_name));
+    attrs.len() != before
+  }
+
+  fn class_list(&self) -> ClassList<'_, Self>
+  where
+    Self: Sized,
+  {
+    ClassList { node: self }
+  }
+
+  fn dataset(&self) -> Dataset<'_, Self>
+  where
+    Self: Sized,
+  {
+    Dataset { node: self }
+  }
+}
+
+/// A live view of the `class` attribute as a set of tokens, like `Element.classList`.
+pub struct ClassList<'a, N: Attributes> {
+  node: &'a N,
+}
+
+impl<'a, N: Attributes> ClassList<'a, N> {
+  pub fn values(&self) -> Vec<String> {
+    let mut classes: Vec<String> = vec![];
+    for class in self.node.get_attr("class").unwrap_or_default().split_ascii_whitespace() {
+      if !classes.iter().any(|c| c == class) {
+        classes.push(class.to_string());
+      }
+    }
+    classes
+  }
+
+  pub fn len(&self) -> usize {
+    self.values().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.values().is_empty()
+  }
+
+  pub fn contains(&self, class: &str) -> bool {
+    self.values().iter().any(|c| c == class)
+  }
+
+  fn write(&self, classes: Vec<String>) {
+    self.node.set_attr("class", &classes.join(" "));
+  }
+
+  pub fn add(&self, class: &str) {
+    let mut classes = self.values();
+    if !classes.iter().any(|c| c == class) {
+      classes.push(class.to_string());
+    }
+    self.write(classes);
+  }
+
+  pub fn remove(&self, class: &str) {
+    if self.node.has_attr("class") {
+      self.write(self.values().into_iter().filter(|c| c != class).collect());
+    }
+  }
+
+  /// Returns whether the class is present afterwards.
+  pub fn toggle(&self, class: &str) -> bool {
+    if self.contains(class) {
+      self.remove(class);
+      false
+    } else {
+      self.add(class);
+      true
+    }
+  }
+
+  pub fn replace(&self, old: &str, new: &str) -> bool {
+    let mut classes = self.values();
+    let index = match classes.iter().position(|c| c == old) {
+      Some(index) => index,
+      None => return false,
+    };
+    if classes.iter().any(|c| c == new) {
+      classes.remove(index);
+    } else {
+      classes[index] = new.to_string();
+    }
+    self.write(classes);
+    true
+  }
+}
+
+/// A live view of the `data-*` attributes keyed by their camel-cased names, like `HTMLElement.dataset`.
+pub struct Dataset<'a, N: Attributes> {
+  node: &'a N,
+}
+
+/// `fooBar` -> `data-foo-bar`.
+fn data_attr_name(key: &str) -> String {
+  let mut name = String::from("data-");
+  for c in key.chars() {
+    if c.is_ascii_uppercase() {
+      name.push('-');
+      name.push(c.to_ascii_lowercase());
+    } else {
+      name.push(c);
+    }
+  }
+  name
+}
+
+/// `data-foo-bar` -> `fooBar`, `None` for anything that is not a data attribute.
+fn dataset_key(name: &QualName) -> Option<String> {
+  if name.ns != ns!() || name.prefix.is_some() {
+    return None;
+  }
+  let rest = name.local.strip_prefix("data-")?;
+  let mut key = String::new();
+  let mut chars = rest.chars().peekable();
+  while let Some(c) = chars.next() {
+    match chars.peek() {
+      Some(next) if c == '-' && next.is_ascii_lowercase() => {
+        key.push(next.to_ascii_uppercase());
+        chars.next();
+      },
+      _ => key.push(c),
+    }
+  }
+  Some(key)
+}
+
+impl<'a, N: Attributes> Dataset<'a, N> {
+  pub fn get(&self, key: &str) -> Option<String> {
+    self.node.get_attr(&data_attr_name(key))
+  }
+
+  pub fn set(&self, key: &str, value: &str) {
+    self.node.set_attr(&data_attr_name(key), value)
+  }
+
+  pub fn remove(&self, key: &str) -> bool {
+    self.node.remove_attr(&data_attr_name(key))
+  }
+
+  pub fn contains(&self, key: &str) -> bool {
+    self.get(key).is_some()
+  }
+
+  /// `(key, value)` pairs in attribute order.
+  pub fn entries(&self) -> Vec<(String, String)> {
+    match self.node.attributes() {
+      Some(attrs) => attrs
+        .borrow()
+        .iter()
+        .filter_map(|attr| dataset_key(&attr.name).map(|key| (key, attr.value.to_string())))
+        .collect(),
+      None => vec![],
+    }
+  }
+}
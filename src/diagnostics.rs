@@ -0,0 +1,259 @@
+use html5ever::{driver::{self, ParseOpts, Parser}, tendril::{StrTendril, TendrilSink}, tokenizer::TokenizerOpts, tree_builder::TreeBuilderOpts, Attribute, ExpandedName, QualName};
+use html5ever::interface::tree_builder::{ElementFlags, NextParserState, NodeOrText, QuirksMode, TreeSink};
+use std::{borrow::Cow, fmt};
+
+/// A parse error reported by the tokenizer or tree builder, positioned at the input character that triggered it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+  pub message: String,
+  /// 1-based line.
+  pub line: usize,
+  /// 1-based column, counted in characters.
+  pub column: usize,
+}
+
+impl fmt::Display for ParseDiagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}: {}", self.line, self.column, self.message)
+  }
+}
+
+#[derive(Debug)]
+pub struct ParseResult<T> {
+  pub output: T,
+  pub diagnostics: Vec<ParseDiagnostic>,
+  /// The mode the tree builder settled on from the doctype; see `quirks::audit` for why.
+  pub quirks_mode: QuirksMode,
+}
+
+impl<T> ParseResult<T> {
+  /// Fails with the diagnostics when there are more than `max_errors` of them.
+  pub fn lint(&self, max_errors: usize) -> Result<(), &[ParseDiagnostic]> {
+    if self.diagnostics.len() > max_errors {
+      Err(&self.diagnostics)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// Wraps another `TreeSink`, forwarding everything to it while recording parse errors at the current input position.
+pub struct DiagnosticSink<S> {
+  inner: S,
+  line: usize,
+  column: usize,
+  diagnostics: Vec<ParseDiagnostic>,
+  quirks_mode: QuirksMode,
+}
+
+impl<S: TreeSink> DiagnosticSink<S> {
+  pub fn new(inner: S) -> Self {
+    DiagnosticSink {
+      inner,
+      line: 1,
+      column: 1,
+      diagnostics: vec![],
+      quirks_mode: QuirksMode::NoQuirks,
+    }
+  }
+
+  pub fn diagnostics(&self) -> &[ParseDiagnostic] {
+    &self.diagnostics
+  }
+
+  fn set_position(&mut self, line: usize, column: usize) {
+    self.line = line;
+    self.column = column;
+  }
+}
+
+impl<S: TreeSink> TreeSink for DiagnosticSink<S> {
+  type Handle = S::Handle;
+  type Output = ParseResult<S::Output>;
+
+  fn finish(self) -> Self::Output {
+    ParseResult {
+      output: self.inner.finish(),
+      diagnostics: self.diagnostics,
+      quirks_mode: self.quirks_mode,
+    }
+  }
+
+  fn parse_error(&mut self, msg: Cow<'static, str>) {
+    self.diagnostics.push(ParseDiagnostic {
+      message: msg.to_string(),
+      line: self.line,
+      column: self.column,
+    });
+    self.inner.parse_error(msg);
+  }
+
+  fn get_document(&mut self) -> S::Handle {
+    self.inner.get_document()
+  }
+
+  fn elem_name<'a>(&'a self, target: &'a S::Handle) -> ExpandedName<'a> {
+    self.inner.elem_name(target)
+  }
+
+  fn create_element(&mut self, name: QualName, attrs: Vec<Attribute>, flags: ElementFlags) -> S::Handle {
+    self.inner.create_element(name, attrs, flags)
+  }
+
+  fn create_comment(&mut self, text: StrTendril) -> S::Handle {
+    self.inner.create_comment(text)
+  }
+
+  fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> S::Handle {
+    self.inner.create_pi(target, data)
+  }
+
+  fn append(&mut self, parent: &S::Handle, child: NodeOrText<S::Handle>) {
+    self.inner.append(parent, child)
+  }
+
+  fn append_based_on_parent_node(&mut self, element: &S::Handle, prev_element: &S::Handle, child: NodeOrText<S::Handle>) {
+    self.inner.append_based_on_parent_node(element, prev_element, child)
+  }
+
+  fn append_doctype_to_document(&mut self, name: StrTendril, public_id: StrTendril, system_id: StrTendril) {
+    self.inner.append_doctype_to_document(name, public_id, system_id)
+  }
+
+  fn mark_script_already_started(&mut self, node: &S::Handle) {
+    self.inner.mark_script_already_started(node)
+  }
+
+  fn pop(&mut self, node: &S::Handle) {
+    self.inner.pop(node)
+  }
+
+  fn get_template_contents(&mut self, target: &S::Handle) -> S::Handle {
+    self.inner.get_template_contents(target)
+  }
+
+  fn same_node(&self, x: &S::Handle, y: &S::Handle) -> bool {
+    self.inner.same_node(x, y)
+  }
+
+  fn set_quirks_mode(&mut self, mode: QuirksMode) {
+    self.quirks_mode = mode;
+    self.inner.set_quirks_mode(mode)
+  }
+
+  fn append_before_sibling(&mut self, sibling: &S::Handle, new_node: NodeOrText<S::Handle>) {
+    self.inner.append_before_sibling(sibling, new_node)
+  }
+
+  fn add_attrs_if_missing(&mut self, target: &S::Handle, attrs: Vec<Attribute>) {
+    self.inner.add_attrs_if_missing(target, attrs)
+  }
+
+  fn associate_with_form(&mut self, target: &S::Handle, form: &S::Handle, nodes: (&S::Handle, Option<&S::Handle>)) {
+    self.inner.associate_with_form(target, form, nodes)
+  }
+
+  fn remove_from_parent(&mut self, target: &S::Handle) {
+    self.inner.remove_from_parent(target)
+  }
+
+  fn reparent_children(&mut self, node: &S::Handle, new_parent: &S::Handle) {
+    self.inner.reparent_children(node, new_parent)
+  }
+
+  fn is_mathml_annotation_xml_integration_point(&self, handle: &S::Handle) -> bool {
+    self.inner.is_mathml_annotation_xml_integration_point(handle)
+  }
+
+  fn set_current_line(&mut self, line_number: u64) {
+    self.inner.set_current_line(line_number)
+  }
+
+  fn complete_script(&mut self, node: &S::Handle) -> NextParserState {
+    self.inner.complete_script(node)
+  }
+}
+
+fn exact_error_opts() -> ParseOpts {
+  ParseOpts {
+    tokenizer: TokenizerOpts {
+      exact_errors: true,
+      ..Default::default()
+    },
+    tree_builder: TreeBuilderOpts {
+      exact_errors: true,
+      ..Default::default()
+    },
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PieceKind {
+  Word,
+  Blank,
+  Other,
+}
+
+fn piece_kind(c: char) -> PieceKind {
+  if c.is_alphanumeric() {
+    PieceKind::Word
+  } else if c == ' ' || c == '\t' {
+    PieceKind::Blank
+  } else {
+    PieceKind::Other
+  }
+}
+
+/// Splits a line into the pieces fed to the parser with their 0-based column: runs of letters and digits, runs of
+/// spaces and tabs, and every other character, markup and control characters included, on its own.
+fn pieces(line: &str) -> Vec<(usize, &str)> {
+  let mut pieces: Vec<(usize, &str)> = vec![];
+  let mut start = 0;
+  let mut column = 0;
+  let mut kind = None;
+  for (index, c) in line.char_indices() {
+    let next = piece_kind(c);
+    if index > start && (next == PieceKind::Other || Some(next) != kind) {
+      let text = &line[start..index];
+      pieces.push((column, text));
+      column += text.chars().count();
+      start = index;
+    }
+    kind = Some(next);
+  }
+  if start < line.len() {
+    pieces.push((column, &line[start..]));
+  }
+  pieces
+}
+
+/// Feeds `html` piece by piece, so every error is recorded at the piece that produced it. `html5ever` only tracks
+/// lines, and the tokenizer reports errors at a markup character or where a run of text starts, so the start of the
+/// piece is the column of the character, without a parser round trip per character.
+fn feed_with_positions<S: TreeSink>(mut parser: Parser<DiagnosticSink<S>>, html: &str) -> ParseResult<S::Output> {
+  let mut line = 1;
+  for text in html.split_inclusive('\n') {
+    for (column, piece) in pieces(text) {
+      parser.tokenizer.sink.sink.set_position(line, column + 1);
+      parser.process(StrTendril::from_slice(piece));
+    }
+    if text.ends_with('\n') {
+      line += 1;
+    }
+  }
+  let column = html.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+  parser.tokenizer.sink.sink.set_position(line, column);
+  parser.finish()
+}
+
+/// Parses a whole document into `sink`, collecting parse errors with their line and column.
+pub fn parse_document_with_diagnostics<S: TreeSink>(sink: S, html: &str) -> ParseResult<S::Output> {
+  let parser = driver::parse_document(DiagnosticSink::new(sink), exact_error_opts());
+  feed_with_positions(parser, html)
+}
+
+/// Parses `html` as the content of a `context_name` element, collecting parse errors with their line and column.
+pub fn parse_fragment_with_diagnostics<S: TreeSink>(sink: S, context_name: QualName, context_attrs: Vec<Attribute>, html: &str) -> ParseResult<S::Output> {
+  let parser = driver::parse_fragment(DiagnosticSink::new(sink), exact_error_opts(), context_name, context_attrs);
+  feed_with_positions(parser, html)
+}
@@ -0,0 +1,424 @@
+use html5ever::QualName;
+use std::{collections::{HashMap, HashSet}, fmt};
+use crate::{format::{attr_name, content_children, format, html_local, FormatOptions, PRESERVED_ELEMENTS}, node::{DomNode, NodeKind}};
+
+/// One structural difference between two trees.
+///
+/// Paths are XPath-like (`/html/body/div[@id="main"]/p[2]`): an element is named by its `id` when it has one and by
+/// its position among same-named siblings otherwise. `Delete` and the `from` of a `Move` point into the old tree,
+/// everything else into the new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+  /// A node only in the new tree, with its pretty-printed HTML.
+  Insert { path: String, html: String },
+  /// A node only in the old tree, with its pretty-printed HTML.
+  Delete { path: String, html: String },
+  /// An element whose `id` is found under another parent or at another position among its siblings.
+  Move { from: String, to: String },
+  /// An attribute added (`old` is `None`), removed (`new` is `None`) or changed.
+  Attribute { path: String, name: String, old: Option<String>, new: Option<String> },
+  /// Changed text, comment or processing instruction data.
+  Text { path: String, old: String, new: String },
+}
+
+/// The first line of `html`, cut short for the one-line report.
+fn preview(html: &str) -> String {
+  let first_line = html.trim().lines().next().unwrap_or_default();
+  let mut preview: String = first_line.chars().take(60).collect();
+  if preview.len() < html.trim().len() {
+    preview.push('…');
+  }
+  preview
+}
+
+impl fmt::Display for Edit {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Edit::Insert { path, html } => write!(f, "+ {} {}", path, preview(html)),
+      Edit::Delete { path, html } => write!(f, "- {} {}", path, preview(html)),
+      Edit::Move { from, to } => write!(f, "> {} (from {})", to, from),
+      Edit::Attribute { path, name, old: None, new: Some(new) } => write!(f, "@ {} {}={:?} added", path, name, new),
+      Edit::Attribute { path, name, old: Some(old), new: None } => write!(f, "@ {} {}={:?} removed", path, name, old),
+      Edit::Attribute { path, name, old, new } => {
+        write!(f, "@ {} {}: {:?} -> {:?}", path, name, old.as_deref().unwrap_or_default(), new.as_deref().unwrap_or_default())
+      },
+      Edit::Text { path, old, new } => write!(f, "~ {} {:?} -> {:?}", path, old, new),
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+  /// Skip whitespace-only text and compare text with whitespace collapsed, outside of preserved elements such as
+  /// `<pre>`, so re-indenting a template does not show up.
+  pub ignore_whitespace: bool,
+}
+
+impl Default for DiffOptions {
+  fn default() -> Self {
+    DiffOptions { ignore_whitespace: true }
+  }
+}
+
+fn element_id<N: DomNode>(node: &N) -> Option<String> {
+  node.attr("id").filter(|id| !id.is_empty())
+}
+
+/// One step of a node's path, counted among all of `siblings` so the path also works as an XPath expression.
+fn segment<N: DomNode>(siblings: &[N], node: &N) -> String {
+  let (name, same) = match node.kind() {
+    NodeKind::Element => {
+      let name = node.element_name().unwrap();
+      if let Some(id) = element_id(node) {
+        return format!("{}[@id={:?}]", name.local, id);
+      }
+      let same: Vec<&N> = siblings.iter().filter(|sibling| sibling.element_name() == Some(name)).collect();
+      (name.local.to_string(), same)
+    },
+    kind => {
+      let name = match kind {
+        NodeKind::Text => "text()",
+        NodeKind::Comment => "comment()",
+        NodeKind::ProcessingInstruction => "processing-instruction()",
+        _ => "doctype()",
+      };
+      (name.to_string(), siblings.iter().filter(|sibling| sibling.kind() == kind).collect())
+    },
+  };
+  match same.iter().position(|sibling| sibling.same_node(node)) {
+    Some(index) if same.len() > 1 => format!("{}[{}]", name, index + 1),
+    _ => name,
+  }
+}
+
+fn root_path<N: DomNode>(node: &N) -> String {
+  match node.kind() {
+    NodeKind::Document => String::new(),
+    _ => format!("/{}", segment(std::slice::from_ref(node), node)),
+  }
+}
+
+fn display_path(path: &str) -> String {
+  if path.is_empty() { "/".to_string() } else { path.to_string() }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Pairs of indices of a longest common subsequence of `old` and `new` under `same`.
+fn longest_common_subsequence<T, F>(old: &[T], new: &[T], same: F) -> Vec<(usize, usize)>
+where
+  F: Fn(&T, &T) -> bool,
+{
+  let mut lengths = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+  for i in (0..old.len()).rev() {
+    for j in (0..new.len()).rev() {
+      lengths[i][j] = if same(&old[i], &new[j]) { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+    }
+  }
+  let (mut i, mut j, mut pairs) = (0, 0, vec![]);
+  while i < old.len() && j < new.len() {
+    if same(&old[i], &new[j]) {
+      pairs.push((i, j));
+      i += 1;
+      j += 1;
+    } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+      i += 1;
+    } else {
+      j += 1;
+    }
+  }
+  pairs
+}
+
+struct Differ<'a, N> {
+  options: &'a DiffOptions,
+  /// Elements with an `id` in each tree, with their paths, to tell moves from deletes and inserts.
+  old_ids: HashMap<String, (N, String)>,
+  new_ids: HashMap<String, (N, String)>,
+  edits: Vec<Edit>,
+  /// Ids of the old elements reported as moved.
+  moved: HashSet<String>,
+  /// Deletes of old elements whose `id` is in the new tree, held back with their place in `edits` until it is known
+  /// whether a `Move` covers them.
+  pending_deletes: Vec<(usize, String, Edit)>,
+}
+
+impl<N: DomNode> Differ<'_, N> {
+  fn collect_ids(node: &N, path: &str, ids: &mut HashMap<String, (N, String)>) {
+    if let Some(id) = element_id(node) {
+      ids.entry(id).or_insert_with(|| (node.clone(), path.to_string()));
+    }
+    let children = content_children(node);
+    for child in children.iter() {
+      Self::collect_ids(child, &format!("{}/{}", path, segment(&children, child)), ids);
+    }
+  }
+
+  fn is_significant(&self, node: &N, preserve: bool) -> bool {
+    !(self.options.ignore_whitespace && !preserve && node.kind() == NodeKind::Text && node.text().unwrap().trim().is_empty())
+  }
+
+  /// Whether two siblings are taken as the same node: same kind and name, and the same `id` if either has one.
+  fn same_node(a: &N, b: &N) -> bool {
+    a.kind() == b.kind()
+      && a.element_name() == b.element_name()
+      && element_id(a) == element_id(b)
+      && a.doctype() == b.doctype()
+      && a.processing_instruction_target() == b.processing_instruction_target()
+  }
+
+  fn html(node: &N) -> String {
+    format(node, &FormatOptions::default()).trim_end().to_string()
+  }
+
+  fn text_edit(&mut self, old: &str, new: &str, path: &str, preserve: bool) {
+    let (old, new) = if self.options.ignore_whitespace && !preserve {
+      (collapse_whitespace(old), collapse_whitespace(new))
+    } else {
+      (old.to_string(), new.to_string())
+    };
+    if old != new {
+      self.edits.push(Edit::Text { path: path.to_string(), old, new });
+    }
+  }
+
+  fn diff_attributes(&mut self, old: &N, new: &N, path: &str) {
+    let old_attrs: Vec<(QualName, String)> = old.attrs().unwrap().iter().map(|attr| (attr.name.clone(), attr.value.to_string())).collect();
+    let new_attrs: Vec<(QualName, String)> = new.attrs().unwrap().iter().map(|attr| (attr.name.clone(), attr.value.to_string())).collect();
+    for (name, old_value) in old_attrs.iter() {
+      let new_value = new_attrs.iter().find(|(new_name, _)| new_name == name).map(|(_, value)| value);
+      if new_value != Some(old_value) {
+        self.edits.push(Edit::Attribute {
+          path: path.to_string(),
+          name: attr_name(name),
+          old: Some(old_value.clone()),
+          new: new_value.cloned(),
+        });
+      }
+    }
+    for (name, new_value) in new_attrs.iter().filter(|(name, _)| !old_attrs.iter().any(|(old_name, _)| old_name == name)) {
+      self.edits.push(Edit::Attribute {
+        path: path.to_string(),
+        name: attr_name(name),
+        old: None,
+        new: Some(new_value.clone()),
+      });
+    }
+  }
+
+  /// Compares two nodes already paired up, which have the same kind and name.
+  fn diff_node(&mut self, old: &N, new: &N, old_path: &str, new_path: &str, preserve: bool) {
+    match new.kind() {
+      NodeKind::Text => self.text_edit(&old.text().unwrap(), &new.text().unwrap(), new_path, preserve),
+      NodeKind::Comment | NodeKind::ProcessingInstruction => {
+        self.text_edit(old.comment().unwrap(), new.comment().unwrap(), new_path, preserve)
+      },
+      NodeKind::Element => {
+        self.diff_attributes(old, new, new_path);
+        let preserve = preserve || html_local(new).is_some_and(|local| PRESERVED_ELEMENTS.contains(&local));
+        self.diff_children(old, new, old_path, new_path, preserve);
+      },
+      NodeKind::Document => self.diff_children(old, new, old_path, new_path, preserve),
+      NodeKind::Doctype => {},
+    }
+  }
+
+  fn diff_children(&mut self, old_parent: &N, new_parent: &N, old_path: &str, new_path: &str, preserve: bool) {
+    let old_all = content_children(old_parent);
+    let new_all = content_children(new_parent);
+    let old: Vec<&N> = old_all.iter().filter(|node| self.is_significant(node, preserve)).collect();
+    let new: Vec<&N> = new_all.iter().filter(|node| self.is_significant(node, preserve)).collect();
+    let old_path_of = |node: &N| format!("{}/{}", old_path, segment(&old_all, node));
+    let new_path_of = |node: &N| format!("{}/{}", new_path, segment(&new_all, node));
+
+    let mut pairs = longest_common_subsequence(&old, &new, |a, b| Self::same_node(a, b));
+    pairs.push((old.len(), new.len()));
+    let (mut i, mut j) = (0, 0);
+    for (next_i, next_j) in pairs {
+      for node in old[i..next_i].iter() {
+        let delete = Edit::Delete { path: old_path_of(node), html: Self::html(node) };
+        match element_id(*node).filter(|id| self.new_ids.contains_key(id)) {
+          Some(id) => self.pending_deletes.push((self.edits.len(), id, delete)),
+          None => self.edits.push(delete),
+        }
+      }
+      for node in new[j..next_j].iter() {
+        let to = new_path_of(node);
+        if !self.diff_moved(node, &to, preserve) {
+          self.edits.push(Edit::Insert { path: to.clone(), html: Self::html(node) });
+          self.moves_within(node, &to, preserve);
+        }
+      }
+      if next_i < old.len() {
+        self.diff_node(old[next_i], new[next_j], &old_path_of(old[next_i]), &new_path_of(new[next_j]), preserve);
+      }
+      (i, j) = (next_i + 1, next_j + 1);
+    }
+  }
+
+  /// Reports `node` as moved, and compares it with where it came from, when the old tree has an element that is the
+  /// same node by `id`. Only the first element with an `id` in each tree takes part.
+  fn diff_moved(&mut self, node: &N, to: &str, preserve: bool) -> bool {
+    let id = match element_id(node) {
+      Some(id) => id,
+      None => return false,
+    };
+    if !self.new_ids.get(&id).is_some_and(|(first, _)| first.same_node(node)) {
+      return false;
+    }
+    let (old_node, from) = match self.old_ids.get(&id) {
+      Some((old_node, from)) if Self::same_node(old_node, node) => (old_node.clone(), from.clone()),
+      _ => return false,
+    };
+    self.moved.insert(id);
+    self.edits.push(Edit::Move { from: from.clone(), to: to.to_string() });
+    self.diff_node(&old_node, node, &from, to, preserve);
+    true
+  }
+
+  /// Reports the moves into an inserted subtree, which the `Insert` alone would hide.
+  fn moves_within(&mut self, node: &N, path: &str, preserve: bool) {
+    let children = content_children(node);
+    for child in children.iter().filter(|child| child.kind() == NodeKind::Element) {
+      let child_path = format!("{}/{}", path, segment(&children, child));
+      if !self.diff_moved(child, &child_path, preserve) {
+        self.moves_within(child, &child_path, preserve);
+      }
+    }
+  }
+
+  /// Puts back the held deletes no `Move` accounted for, where they were found.
+  fn finish(mut self) -> Vec<Edit> {
+    for (index, id, delete) in std::mem::take(&mut self.pending_deletes).into_iter().rev() {
+      if !self.moved.contains(&id) {
+        self.edits.insert(index, delete);
+      }
+    }
+    self.edits
+  }
+}
+
+/// Compares two trees structurally, in document order: children are paired up by kind, name and `id` along a longest
+/// common subsequence, elements whose `id` turns up elsewhere become moves, and the rest inserts and deletes.
+pub fn diff<N: DomNode>(old: &N, new: &N) -> Vec<Edit> {
+  diff_with_options(old, new, &DiffOptions::default())
+}
+
+pub fn diff_with_options<N: DomNode>(old: &N, new: &N, options: &DiffOptions) -> Vec<Edit> {
+  let (old_path, new_path) = (root_path(old), root_path(new));
+  let mut differ = Differ {
+    options,
+    old_ids: HashMap::new(),
+    new_ids: HashMap::new(),
+    edits: vec![],
+    moved: HashSet::new(),
+    pending_deletes: vec![],
+  };
+  Differ::collect_ids(old, &old_path, &mut differ.old_ids);
+  Differ::collect_ids(new, &new_path, &mut differ.new_ids);
+  if Differ::same_node(old, new) {
+    differ.diff_node(old, new, &old_path, &new_path, false);
+  } else {
+    differ.edits.push(Edit::Delete { path: display_path(&old_path), html: Differ::html(old) });
+    differ.edits.push(Edit::Insert { path: display_path(&new_path), html: Differ::html(new) });
+  }
+  differ.finish()
+}
+
+/// One line per edit followed by a count of each kind, or `no differences`.
+pub fn report(edits: &[Edit]) -> String {
+  if edits.is_empty() {
+    return "no differences\n".to_string();
+  }
+  let mut out = String::new();
+  let mut counts = [0; 5];
+  for edit in edits {
+    out.push_str(&edit.to_string());
+    out.push('\n');
+    counts[match edit {
+      Edit::Insert { .. } => 0,
+      Edit::Delete { .. } => 1,
+      Edit::Move { .. } => 2,
+      Edit::Attribute { .. } => 3,
+      Edit::Text { .. } => 4,
+    }] += 1;
+  }
+  let summary: Vec<String> = ["inserted", "deleted", "moved", "attributes changed", "texts changed"]
+    .iter()
+    .zip(counts)
+    .filter(|(_, count)| *count > 0)
+    .map(|(label, count)| format!("{} {}", count, label))
+    .collect();
+  out.push_str(&summary.join(", "));
+  out.push('\n');
+  out
+}
+
+/// A unified diff, with 3 lines of context, of the two trees pretty-printed by `format::format`, so that only
+/// structural changes show up and not how the sources happen to be indented.
+pub fn unified_diff<N: DomNode>(old: &N, new: &N, old_label: &str, new_label: &str) -> String {
+  const CONTEXT: usize = 3;
+  let old_text = format(old, &FormatOptions::default());
+  let new_text = format(new, &FormatOptions::default());
+  let old_lines: Vec<&str> = old_text.lines().collect();
+  let new_lines: Vec<&str> = new_text.lines().collect();
+
+  // Lines in both trees, as (old index, new index), ending with a sentinel past the last line.
+  let prefix = old_lines.iter().zip(new_lines.iter()).take_while(|(a, b)| a == b).count();
+  let suffix = old_lines[prefix..].iter().rev().zip(new_lines[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+  let mut common: Vec<(usize, usize)> = (0..prefix).map(|index| (index, index)).collect();
+  let middle = longest_common_subsequence(&old_lines[prefix..old_lines.len() - suffix], &new_lines[prefix..new_lines.len() - suffix], |a, b| a == b);
+  common.extend(middle.into_iter().map(|(i, j)| (i + prefix, j + prefix)));
+  common.extend((0..suffix).map(|index| (old_lines.len() - suffix + index, new_lines.len() - suffix + index)));
+  common.push((old_lines.len(), new_lines.len()));
+
+  // Changed runs as (old start, old end, new start, new end).
+  let mut changes = vec![];
+  let (mut i, mut j) = (0, 0);
+  for &(next_i, next_j) in common.iter() {
+    if next_i > i || next_j > j {
+      changes.push((i, next_i, j, next_j));
+    }
+    (i, j) = (next_i + 1, next_j + 1);
+  }
+  if changes.is_empty() {
+    return String::new();
+  }
+
+  let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+  let mut index = 0;
+  while index < changes.len() {
+    // Changes closer than twice the context share a hunk.
+    let mut last = index;
+    while last + 1 < changes.len() && changes[last + 1].0 - changes[last].1 <= 2 * CONTEXT {
+      last += 1;
+    }
+    let old_start = changes[index].0.saturating_sub(CONTEXT);
+    let new_start = changes[index].2.saturating_sub(CONTEXT);
+    let old_end = (changes[last].1 + CONTEXT).min(old_lines.len());
+    let new_end = (changes[last].3 + CONTEXT).min(new_lines.len());
+    out.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start + 1, old_end - old_start, new_start + 1, new_end - new_start));
+
+    let mut line = old_start;
+    for &(old_from, old_to, new_from, new_to) in changes[index..=last].iter() {
+      for context in old_lines[line..old_from].iter() {
+        out.push_str(&format!(" {}\n", context));
+      }
+      for removed in old_lines[old_from..old_to].iter() {
+        out.push_str(&format!("-{}\n", removed));
+      }
+      for added in new_lines[new_from..new_to].iter() {
+        out.push_str(&format!("+{}\n", added));
+      }
+      line = old_to;
+    }
+    for context in old_lines[line..old_end].iter() {
+      out.push_str(&format!(" {}\n", context));
+    }
+    index = last + 1;
+  }
+  out
+}
+
@@ -0,0 +1,468 @@
+use encoding_rs::{Encoding, EUC_KR, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252, X_USER_DEFINED};
+use html5ever::{local_name, namespace_url, ns, parse_document, tendril::{StrTendril, TendrilSink}, Attribute, ExpandedName, QualName};
+use html5ever::interface::tree_builder::{ElementFlags, NextParserState, NodeOrText, QuirksMode, TreeSink};
+use markup5ever_rcdom::RcDom;
+use std::borrow::Cow;
+
+/// How sure the parser is of the encoding, as in the spec: a tentative encoding is replaced by a `<meta>` that
+/// disagrees with it, a certain one is not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+  Certain,
+  Tentative,
+}
+
+/// Where the encoding came from, in the order the spec tries them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodingSource {
+  ByteOrderMark,
+  TransportHint,
+  /// A `<meta>` found by the prescan of the first 1024 bytes.
+  MetaPrescan,
+  /// The bytes are valid UTF-8, except perhaps for a character cut off at the end, with at least one non-ASCII
+  /// character.
+  Utf8Detected,
+  Fallback,
+  /// A `<meta>` beyond the prescan that disagreed with the encoding the first parse used, so the input was reparsed.
+  MetaReparse,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+  /// A charset from outside the document, either a `Content-Type` value such as `text/html; charset=euc-kr` or a bare
+  /// label.
+  pub transport_hint: Option<String>,
+  /// Used when nothing in or around the document names an encoding.
+  pub fallback: &'static Encoding,
+}
+
+impl Default for DecodeOptions {
+  /// No hint, and `EUC-KR` (decoded as its CP949 superset, like browsers do) as the fallback, which is the spec's
+  /// default for a Korean locale.
+  fn default() -> Self {
+    DecodeOptions {
+      transport_hint: None,
+      fallback: EUC_KR,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct DecodedParse<T> {
+  pub output: T,
+  pub encoding: &'static Encoding,
+  pub confidence: Confidence,
+  pub source: EncodingSource,
+  /// Whether malformed byte sequences were replaced with U+FFFD.
+  pub had_errors: bool,
+}
+
+/// The encoding decided before parsing, and the length of the byte order mark to skip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sniffed {
+  pub encoding: &'static Encoding,
+  pub confidence: Confidence,
+  pub source: EncodingSource,
+  pub bom_length: usize,
+}
+
+fn is_space(byte: u8) -> bool {
+  matches!(byte, b'\t' | b'\n' | b'\x0C' | b'\r' | b' ')
+}
+
+/// `Encoding::for_label`, plus the Windows names for EUC-KR that Korean pages use but the spec does not list.
+pub fn encoding_for_label(label: &[u8]) -> Option<&'static Encoding> {
+  Encoding::for_label(label).or_else(|| match label.trim_ascii().to_ascii_lowercase().as_slice() {
+    b"cp949" | b"ms949" | b"uhc" => Some(EUC_KR),
+    _ => None,
+  })
+}
+
+/// A `<meta>` can ask for UTF-16, but by the time it is read the document evidently is not UTF-16.
+fn meta_encoding(encoding: &'static Encoding) -> &'static Encoding {
+  if encoding == UTF_16BE || encoding == UTF_16LE {
+    UTF_8
+  } else if encoding == X_USER_DEFINED {
+    WINDOWS_1252
+  } else {
+    encoding
+  }
+}
+
+/// The spec's "extracting a character encoding from a meta element" on a `content` value such as
+/// `text/html; charset=euc-kr`.
+pub fn extract_charset(content: &str) -> Option<&'static Encoding> {
+  let bytes = content.as_bytes();
+  let mut position = 0;
+  loop {
+    let found = bytes[position..].windows(7).position(|window| window.eq_ignore_ascii_case(b"charset"))?;
+    position += found + 7;
+    while position < bytes.len() && is_space(bytes[position]) {
+      position += 1;
+    }
+    if bytes.get(position) == Some(&b'=') {
+      position += 1;
+      break;
+    }
+  }
+  while position < bytes.len() && is_space(bytes[position]) {
+    position += 1;
+  }
+  let label = match bytes.get(position)? {
+    quote @ (b'"' | b'\'') => {
+      let length = bytes[position + 1..].iter().position(|byte| byte == quote)?;
+      &bytes[position + 1..position + 1 + length]
+    },
+    _ => {
+      let length = bytes[position..].iter().position(|byte| is_space(*byte) || *byte == b';').unwrap_or(bytes.len() - position);
+      &bytes[position..position + length]
+    },
+  };
+  encoding_for_label(label)
+}
+
+struct Prescanner<'a> {
+  bytes: &'a [u8],
+  position: usize,
+}
+
+impl<'a> Prescanner<'a> {
+  fn peek(&self) -> Option<u8> {
+    self.bytes.get(self.position).copied()
+  }
+
+  fn starts_with_ignore_case(&self, prefix: &[u8]) -> bool {
+    self.bytes[self.position..].get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+  }
+
+  fn skip_while<F: Fn(u8) -> bool>(&mut self, skip: F) {
+    while self.peek().is_some_and(&skip) {
+      self.position += 1;
+    }
+  }
+
+  /// The spec's "get an attribute", with the name and value lowercased. `None` at `>` or when the bytes run out.
+  fn attribute(&mut self) -> Option<(String, String)> {
+    self.skip_while(|byte| is_space(byte) || byte == b'/');
+    if self.peek()? == b'>' {
+      return None;
+    }
+    let mut name = vec![];
+    let mut value = vec![];
+    loop {
+      match self.peek()? {
+        b'=' if !name.is_empty() => {
+          self.position += 1;
+          break;
+        },
+        byte if is_space(byte) => {
+          self.skip_while(is_space);
+          if self.peek()? != b'=' {
+            return Some((String::from_utf8_lossy(&name).into_owned(), String::new()));
+          }
+          self.position += 1;
+          break;
+        },
+        b'/' | b'>' => return Some((String::from_utf8_lossy(&name).into_owned(), String::new())),
+        byte => name.push(byte.to_ascii_lowercase()),
+      }
+      self.position += 1;
+    }
+    self.skip_while(is_space);
+    match self.peek()? {
+      quote @ (b'"' | b'\'') => loop {
+        self.position += 1;
+        match self.peek()? {
+          byte if byte == quote => {
+            self.position += 1;
+            break;
+          },
+          byte => value.push(byte.to_ascii_lowercase()),
+        }
+      },
+      b'>' => {},
+      _ => loop {
+        match self.peek()? {
+          byte if is_space(byte) || byte == b'>' => break,
+          byte => value.push(byte.to_ascii_lowercase()),
+        }
+        self.position += 1;
+      },
+    }
+    Some((String::from_utf8_lossy(&name).into_owned(), String::from_utf8_lossy(&value).into_owned()))
+  }
+
+  /// The attributes of a `<meta>` tag, deciding on an encoding once the tag ends.
+  fn meta(&mut self) -> Option<&'static Encoding> {
+    let mut seen: Vec<String> = vec![];
+    let mut got_pragma = false;
+    let mut need_pragma: Option<bool> = None;
+    let mut charset = None;
+    while let Some((name, value)) = self.attribute() {
+      if seen.contains(&name) {
+        continue;
+      }
+      match name.as_str() {
+        "http-equiv" => got_pragma |= value == "content-type",
+        "content" if charset.is_none() => {
+          if let Some(encoding) = extract_charset(&value) {
+            charset = Some(encoding);
+            need_pragma = Some(true);
+          }
+        },
+        "charset" => {
+          charset = encoding_for_label(value.as_bytes());
+          need_pragma = Some(false);
+        },
+        _ => {},
+      }
+      seen.push(name);
+    }
+    match need_pragma? {
+      true if !got_pragma => None,
+      _ => charset.map(meta_encoding),
+    }
+  }
+
+  fn scan(&mut self) -> Option<&'static Encoding> {
+    while self.position < self.bytes.len() {
+      if self.starts_with_ignore_case(b"<!--") {
+        // `-->` may share its dashes with `<!--`, as in `<!-->`.
+        let end = self.bytes[self.position + 2..].windows(3).position(|window| window == b"-->")?;
+        self.position += 2 + end + 2;
+      } else if self.starts_with_ignore_case(b"<meta") && self.bytes.get(self.position + 5).is_some_and(|byte| is_space(*byte) || *byte == b'/') {
+        self.position += 6;
+        if let Some(encoding) = self.meta() {
+          return Some(encoding);
+        }
+      } else if self.starts_with_ignore_case(b"<") && self.bytes.get(self.position + 1).is_some_and(u8::is_ascii_alphabetic)
+        || self.starts_with_ignore_case(b"</") && self.bytes.get(self.position + 2).is_some_and(u8::is_ascii_alphabetic)
+      {
+        self.skip_while(|byte| !is_space(byte) && byte != b'>');
+        while self.attribute().is_some() {}
+      } else if self.starts_with_ignore_case(b"<!") || self.starts_with_ignore_case(b"</") || self.starts_with_ignore_case(b"<?") {
+        let end = self.bytes[self.position..].iter().position(|byte| *byte == b'>')?;
+        self.position += end;
+      }
+      self.position += 1;
+    }
+    None
+  }
+}
+
+/// The spec's prescan: looks for a `<meta charset>` or `<meta http-equiv="Content-Type" content>` in the first 1024
+/// bytes, skipping comments and the attributes of other tags.
+pub fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+  let bytes = &bytes[..bytes.len().min(1024)];
+  Prescanner { bytes, position: 0 }.scan()
+}
+
+/// Whether `bytes` is valid UTF-8, allowing for a character cut off at the end: the bytes may be the first chunk of a
+/// longer input.
+pub(crate) fn is_utf8_prefix(bytes: &[u8]) -> bool {
+  match std::str::from_utf8(bytes) {
+    Ok(_) => true,
+    Err(error) => error.error_len().is_none(),
+  }
+}
+
+/// The encoding a transport hint names, either a `Content-Type` value with a charset or a bare label.
+pub(crate) fn hinted_encoding(hint: &str) -> Option<&'static Encoding> {
+  extract_charset(hint).or_else(|| encoding_for_label(hint.as_bytes()))
+}
+
+/// Decides the encoding before parsing: a byte order mark, then the transport hint, then the prescan, then whether
+/// the bytes are UTF-8, then the fallback.
+pub fn sniff(bytes: &[u8], options: &DecodeOptions) -> Sniffed {
+  let sniffed = |encoding, confidence, source| Sniffed {
+    encoding,
+    confidence,
+    source,
+    bom_length: 0,
+  };
+  if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+    return Sniffed {
+      bom_length,
+      ..sniffed(encoding, Confidence::Certain, EncodingSource::ByteOrderMark)
+    };
+  }
+  if let Some(encoding) = options.transport_hint.as_deref().and_then(hinted_encoding) {
+    return sniffed(encoding, Confidence::Certain, EncodingSource::TransportHint);
+  }
+  if let Some(encoding) = prescan(bytes) {
+    return sniffed(encoding, Confidence::Tentative, EncodingSource::MetaPrescan);
+  }
+  if !bytes.is_ascii() && is_utf8_prefix(bytes) {
+    return sniffed(UTF_8, Confidence::Tentative, EncodingSource::Utf8Detected);
+  }
+  sniffed(options.fallback, Confidence::Tentative, EncodingSource::Fallback)
+}
+
+/// Wraps another `TreeSink`, remembering the encoding named by the first `<meta>` the tree builder creates.
+struct MetaCharsetSink<S> {
+  inner: S,
+  charset: Option<&'static Encoding>,
+}
+
+impl<S: TreeSink> TreeSink for MetaCharsetSink<S> {
+  type Handle = S::Handle;
+  type Output = (S::Output, Option<&'static Encoding>);
+
+  fn finish(self) -> Self::Output {
+    (self.inner.finish(), self.charset)
+  }
+
+  fn parse_error(&mut self, msg: Cow<'static, str>) {
+    self.inner.parse_error(msg)
+  }
+
+  fn get_document(&mut self) -> S::Handle {
+    self.inner.get_document()
+  }
+
+  fn elem_name<'a>(&'a self, target: &'a S::Handle) -> ExpandedName<'a> {
+    self.inner.elem_name(target)
+  }
+
+  fn create_element(&mut self, name: QualName, attrs: Vec<Attribute>, flags: ElementFlags) -> S::Handle {
+    if self.charset.is_none() && name.ns == ns!(html) && name.local == local_name!("meta") {
+      let attr = |local: &str| attrs.iter().find(|attr| attr.name.ns == ns!() && &*attr.name.local == local).map(|attr| &attr.value);
+      self.charset = match (attr("charset"), attr("http-equiv"), attr("content")) {
+        (Some(charset), _, _) => encoding_for_label(charset.as_bytes()),
+        (None, Some(pragma), Some(content)) if pragma.eq_ignore_ascii_case("content-type") => extract_charset(content),
+        _ => None,
+      };
+    }
+    self.inner.create_element(name, attrs, flags)
+  }
+
+  fn create_comment(&mut self, text: StrTendril) -> S::Handle {
+    self.inner.create_comment(text)
+  }
+
+  fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> S::Handle {
+    self.inner.create_pi(target, data)
+  }
+
+  fn append(&mut self, parent: &S::Handle, child: NodeOrText<S::Handle>) {
+    self.inner.append(parent, child)
+  }
+
+  fn append_based_on_parent_node(&mut self, element: &S::Handle, prev_element: &S::Handle, child: NodeOrText<S::Handle>) {
+    self.inner.append_based_on_parent_node(element, prev_element, child)
+  }
+
+  fn append_doctype_to_document(&mut self, name: StrTendril, public_id: StrTendril, system_id: StrTendril) {
+    self.inner.append_doctype_to_document(name, public_id, system_id)
+  }
+
+  fn mark_script_already_started(&mut self, node: &S::Handle) {
+    self.inner.mark_script_already_started(node)
+  }
+
+  fn pop(&mut self, node: &S::Handle) {
+    self.inner.pop(node)
+  }
+
+  fn get_template_contents(&mut self, target: &S::Handle) -> S::Handle {
+    self.inner.get_template_contents(target)
+  }
+
+  fn same_node(&self, x: &S::Handle, y: &S::Handle) -> bool {
+    self.inner.same_node(x, y)
+  }
+
+  fn set_quirks_mode(&mut self, mode: QuirksMode) {
+    self.inner.set_quirks_mode(mode)
+  }
+
+  fn append_before_sibling(&mut self, sibling: &S::Handle, new_node: NodeOrText<S::Handle>) {
+    self.inner.append_before_sibling(sibling, new_node)
+  }
+
+  fn add_attrs_if_missing(&mut self, target: &S::Handle, attrs: Vec<Attribute>) {
+    self.inner.add_attrs_if_missing(target, attrs)
+  }
+
+  fn associate_with_form(&mut self, target: &S::Handle, form: &S::Handle, nodes: (&S::Handle, Option<&S::Handle>)) {
+    self.inner.associate_with_form(target, form, nodes)
+  }
+
+  fn remove_from_parent(&mut self, target: &S::Handle) {
+    self.inner.remove_from_parent(target)
+  }
+
+  fn reparent_children(&mut self, node: &S::Handle, new_parent: &S::Handle) {
+    self.inner.reparent_children(node, new_parent)
+  }
+
+  fn is_mathml_annotation_xml_integration_point(&self, handle: &S::Handle) -> bool {
+    self.inner.is_mathml_annotation_xml_integration_point(handle)
+  }
+
+  fn set_current_line(&mut self, line_number: u64) {
+    self.inner.set_current_line(line_number)
+  }
+
+  fn complete_script(&mut self, node: &S::Handle) -> NextParserState {
+    self.inner.complete_script(node)
+  }
+}
+
+fn decode_and_parse<S: TreeSink>(sink: S, bytes: &[u8], encoding: &'static Encoding) -> (S::Output, Option<&'static Encoding>, bool) {
+  let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+  let (output, charset) = parse_document(MetaCharsetSink { inner: sink, charset: None }, Default::default()).one(&*text);
+  (output, charset, had_errors)
+}
+
+/// Decodes and parses a document given as bytes. The encoding is sniffed as in `sniff`; when it is only tentative and
+/// a `<meta>` the prescan missed names a different one, the bytes are decoded again and parsed into a fresh sink from
+/// `new_sink`.
+pub fn parse_bytes<S, F>(mut new_sink: F, bytes: &[u8], options: &DecodeOptions) -> DecodedParse<S::Output>
+where
+  S: TreeSink,
+  F: FnMut() -> S,
+{
+  let sniffed = sniff(bytes, options);
+  let bytes = &bytes[sniffed.bom_length..];
+  let (output, charset, had_errors) = decode_and_parse(new_sink(), bytes, sniffed.encoding);
+  let mut result = DecodedParse {
+    output,
+    encoding: sniffed.encoding,
+    confidence: sniffed.confidence,
+    source: sniffed.source,
+    had_errors,
+  };
+  let charset = match charset.map(meta_encoding) {
+    Some(charset) if sniffed.confidence == Confidence::Tentative => charset,
+    _ => return result,
+  };
+  result.confidence = Confidence::Certain;
+  if charset != sniffed.encoding {
+    let (output, _, had_errors) = decode_and_parse(new_sink(), bytes, charset);
+    result = DecodedParse {
+      output,
+      encoding: charset,
+      confidence: Confidence::Certain,
+      source: EncodingSource::MetaReparse,
+      had_errors,
+    };
+  }
+  result
+}
+
+/// Decodes a document exactly as `parse_bytes` does, reparse on a late `<meta>` included, and returns the text that
+/// `parse_bytes` ends up parsing. For parsers of text such as `diagnostics`, so they see the same characters as every
+/// other reader of the bytes.
+pub fn decode_bytes(bytes: &[u8], options: &DecodeOptions) -> DecodedParse<String> {
+  let parsed = parse_bytes(RcDom::default, bytes, options);
+  let bom_length = sniff(bytes, options).bom_length;
+  let (text, _) = parsed.encoding.decode_without_bom_handling(&bytes[bom_length..]);
+  DecodedParse {
+    output: text.into_owned(),
+    encoding: parsed.encoding,
+    confidence: parsed.confidence,
+    source: parsed.source,
+    had_errors: parsed.had_errors,
+  }
+}
@@ -0,0 +1,335 @@
+use html5ever::{namespace_url, ns, QualName};
+use crate::node::{DomNode, NodeKind};
+
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+  "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input", "keygen", "link", "meta", "param", "source",
+  "track", "wbr",
+];
+
+/// Elements whose text the serializer writes without escaping.
+pub(crate) const RAW_TEXT_ELEMENTS: &[&str] = &["style", "script", "xmp", "iframe", "noembed", "noframes", "plaintext", "noscript"];
+
+const INLINE_ELEMENTS: &[&str] = &[
+  "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "del", "dfn", "em", "i", "img", "input", "ins", "kbd", "label",
+  "mark", "meter", "output", "progress", "q", "s", "samp", "select", "small", "span", "strong", "sub", "sup", "textarea", "time", "u",
+  "var", "wbr",
+];
+
+pub(crate) const PRESERVED_ELEMENTS: &[&str] = &["pre", "textarea", "script", "style", "listing", "plaintext", "xmp"];
+
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+  /// Spaces per nesting level.
+  pub indent_width: usize,
+  /// HTML elements kept on the line of the text around them; every other element starts its own line.
+  pub inline_elements: Vec<String>,
+  /// Elements whose contents are written exactly as parsed.
+  pub preserved_elements: Vec<String>,
+  /// Start tags longer than this, counting the indentation, get one attribute per line. `None` never wraps.
+  pub wrap_attributes_at: Option<usize>,
+}
+
+impl Default for FormatOptions {
+  fn default() -> Self {
+    FormatOptions {
+      indent_width: 2,
+      inline_elements: INLINE_ELEMENTS.iter().map(|name| name.to_string()).collect(),
+      preserved_elements: PRESERVED_ELEMENTS.iter().map(|name| name.to_string()).collect(),
+      wrap_attributes_at: Some(100),
+    }
+  }
+}
+
+pub(crate) fn escape_text_char(c: char, out: &mut String) {
+  match c {
+    '&' => out.push_str("&amp;"),
+    '\u{A0}' => out.push_str("&nbsp;"),
+    '<' => out.push_str("&lt;"),
+    '>' => out.push_str("&gt;"),
+    c => out.push(c),
+  }
+}
+
+pub(crate) fn escape_attr(value: &str, out: &mut String) {
+  for c in value.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '\u{A0}' => out.push_str("&nbsp;"),
+      '"' => out.push_str("&quot;"),
+      c => out.push(c),
+    }
+  }
+}
+
+/// The attribute name as `html5ever`'s serializer writes it.
+pub(crate) fn attr_name(name: &QualName) -> String {
+  if name.ns == ns!() {
+    name.local.to_string()
+  } else if name.ns == ns!(xml) {
+    format!("xml:{}", name.local)
+  } else if name.ns == ns!(xmlns) && &*name.local == "xmlns" {
+    "xmlns".to_string()
+  } else if name.ns == ns!(xmlns) {
+    format!("xmlns:{}", name.local)
+  } else if name.ns == ns!(xlink) {
+    format!("xlink:{}", name.local)
+  } else {
+    match name.prefix {
+      Some(ref prefix) => format!("{}:{}", prefix, name.local),
+      None => name.local.to_string(),
+    }
+  }
+}
+
+/// The doctype with its public and system ids when it has any, so a legacy doctype keeps its quirks mode.
+pub(crate) fn doctype(name: &str, public_id: &str, system_id: &str) -> String {
+  // An id quoted with apostrophes in the source can hold a quotation mark.
+  let quote = |id: &str| if id.contains('"') { format!("'{}'", id) } else { format!("\"{}\"", id) };
+  match (public_id.is_empty(), system_id.is_empty()) {
+    (true, true) => format!("<!DOCTYPE {}>", name),
+    (false, true) => format!("<!DOCTYPE {} PUBLIC {}>", name, quote(public_id)),
+    (true, false) => format!("<!DOCTYPE {} SYSTEM {}>", name, quote(system_id)),
+    (false, false) => format!("<!DOCTYPE {} PUBLIC {} {}>", name, quote(public_id), quote(system_id)),
+  }
+}
+
+pub(crate) fn html_local<N: DomNode>(node: &N) -> Option<&str> {
+  node.element_name().filter(|name| name.ns == ns!(html)).map(|name| &*name.local)
+}
+
+pub(crate) fn is_void<N: DomNode>(node: &N) -> bool {
+  html_local(node).is_some_and(|local| VOID_ELEMENTS.contains(&local))
+}
+
+/// The children written inside the element: the contents of a `<template>`, the node's own children otherwise.
+pub(crate) fn content_children<N: DomNode>(node: &N) -> Vec<N> {
+  match node.template_contents() {
+    Some(contents) => contents.children(),
+    None => node.children(),
+  }
+}
+
+struct Formatter<'a> {
+  options: &'a FormatOptions,
+  lines: Vec<String>,
+}
+
+impl<'a> Formatter<'a> {
+  fn indent(&self, depth: usize) -> String {
+    " ".repeat(depth * self.options.indent_width)
+  }
+
+  fn is_inline<N: DomNode>(&self, node: &N) -> bool {
+    match node.kind() {
+      NodeKind::Text => true,
+      NodeKind::Element => html_local(node).is_some_and(|local| self.options.inline_elements.iter().any(|name| name == local)),
+      _ => false,
+    }
+  }
+
+  /// Raw text elements are always preserved: their text is written unescaped, so it cannot be reflowed.
+  fn is_preserved<N: DomNode>(&self, node: &N) -> bool {
+    html_local(node)
+      .is_some_and(|local| RAW_TEXT_ELEMENTS.contains(&local) || self.options.preserved_elements.iter().any(|name| name == local))
+  }
+
+  /// The start tag on one line, or, for a tag starting a line at `wrap_depth`, with one attribute per line when it is
+  /// too long.
+  fn start_tag<N: DomNode>(&self, node: &N, wrap_depth: Option<usize>) -> String {
+    let name = node.element_name().unwrap();
+    let attrs: Vec<String> = node
+      .attrs()
+      .unwrap()
+      .iter()
+      .map(|attr| {
+        let mut text = attr_name(&attr.name);
+        text.push_str("=\"");
+        escape_attr(&attr.value, &mut text);
+        text.push('"');
+        text
+      })
+      .collect();
+
+    let mut tag = format!("<{}", name.local);
+    for attr in attrs.iter() {
+      tag.push(' ');
+      tag.push_str(attr);
+    }
+    tag.push('>');
+
+    let depth = match wrap_depth {
+      Some(depth) => depth,
+      None => return tag,
+    };
+    let too_long = self.options.wrap_attributes_at.is_some_and(|width| self.indent(depth).len() + tag.chars().count() > width);
+    if !too_long || attrs.len() < 2 {
+      return tag;
+    }
+    let mut tag = format!("<{}", name.local);
+    for attr in attrs.iter() {
+      tag.push('\n');
+      tag.push_str(&self.indent(depth + 1));
+      tag.push_str(attr);
+    }
+    tag.push('>');
+    tag
+  }
+
+  fn end_tag<N: DomNode>(&self, node: &N) -> String {
+    if is_void(node) {
+      String::new()
+    } else {
+      format!("</{}>", node.element_name().unwrap().local)
+    }
+  }
+
+  /// Writes the node and everything below it as the parser saw it, without touching whitespace.
+  fn write_verbatim<N: DomNode>(&self, node: &N, raw_text: bool, out: &mut String) {
+    match node.kind() {
+      NodeKind::Text => {
+        let text = node.text().unwrap();
+        if raw_text {
+          out.push_str(&text);
+        } else {
+          text.chars().for_each(|c| escape_text_char(c, out));
+        }
+      },
+      NodeKind::Element => {
+        out.push_str(&self.start_tag(node, None));
+        let local = html_local(node).unwrap_or_default();
+        let raw_text = RAW_TEXT_ELEMENTS.contains(&local);
+        let children = content_children(node);
+        // The parser drops a newline right after these start tags, so one that is part of the text needs another.
+        if matches!(local, "pre" | "textarea" | "listing") && children.first().and_then(|child| child.text()).is_some_and(|text| text.starts_with('\n')) {
+          out.push('\n');
+        }
+        for child in children {
+          self.write_verbatim(&child, raw_text, out);
+        }
+        out.push_str(&self.end_tag(node));
+      },
+      _ => out.push_str(&self.leaf(node)),
+    }
+  }
+
+  /// Doctypes, comments and processing instructions.
+  fn leaf<N: DomNode>(&self, node: &N) -> String {
+    match node.kind() {
+      NodeKind::Doctype => {
+        let (name, public_id, system_id) = node.doctype().unwrap();
+        doctype(name, public_id, system_id)
+      },
+      NodeKind::Comment => format!("<!--{}-->", node.comment().unwrap()),
+      NodeKind::ProcessingInstruction => format!("<?{} {}>", node.processing_instruction_target().unwrap(), node.comment().unwrap()),
+      _ => String::new(),
+    }
+  }
+
+  /// Writes inline content on the current line, collapsing whitespace runs to one space.
+  fn write_inline<N: DomNode>(&self, node: &N, out: &mut String) {
+    match node.kind() {
+      NodeKind::Text => {
+        let text = node.text().unwrap();
+        let mut last_was_space = out.ends_with(' ');
+        for c in text.chars() {
+          if c.is_ascii_whitespace() {
+            if !last_was_space {
+              out.push(' ');
+            }
+            last_was_space = true;
+          } else {
+            escape_text_char(c, out);
+            last_was_space = false;
+          }
+        }
+      },
+      NodeKind::Element if self.is_preserved(node) => self.write_verbatim(node, false, out),
+      NodeKind::Element => {
+        out.push_str(&self.start_tag(node, None));
+        for child in content_children(node) {
+          self.write_inline(&child, out);
+        }
+        out.push_str(&self.end_tag(node));
+      },
+      _ => out.push_str(&self.leaf(node)),
+    }
+  }
+
+  fn push_line(&mut self, depth: usize, text: &str) {
+    self.lines.push(format!("{}{}", self.indent(depth), text));
+  }
+
+  fn flush_inline(&mut self, run: &mut String, depth: usize) {
+    let line = run.trim();
+    if !line.is_empty() {
+      let line = line.to_string();
+      self.push_line(depth, &line);
+    }
+    run.clear();
+  }
+
+  fn write_children<N: DomNode>(&mut self, children: Vec<N>, depth: usize) {
+    let mut run = String::new();
+    for child in children {
+      if self.is_inline(&child) {
+        self.write_inline(&child, &mut run);
+      } else {
+        self.flush_inline(&mut run, depth);
+        self.write_block(&child, depth);
+      }
+    }
+    self.flush_inline(&mut run, depth);
+  }
+
+  fn write_block<N: DomNode>(&mut self, node: &N, depth: usize) {
+    match node.kind() {
+      NodeKind::Document => self.write_children(node.children(), depth),
+      NodeKind::Element => {
+        if self.is_preserved(node) {
+          let mut text = String::new();
+          self.write_verbatim(node, false, &mut text);
+          // Only the first line is indented, the contents stay exactly as parsed.
+          self.push_line(depth, &text);
+          return;
+        }
+
+        let start = self.start_tag(node, Some(depth));
+        let children = content_children(node);
+        if children.iter().all(|child| self.is_inline(child)) {
+          let mut run = String::new();
+          for child in children.iter() {
+            self.write_inline(child, &mut run);
+          }
+          let line = format!("{}{}{}", start, run.trim(), self.end_tag(node));
+          self.push_line(depth, &line);
+          return;
+        }
+
+        self.push_line(depth, &start);
+        self.write_children(children, depth + 1);
+        let end = self.end_tag(node);
+        self.push_line(depth, &end);
+      },
+      NodeKind::Text => {
+        let mut run = String::new();
+        self.write_inline(node, &mut run);
+        self.flush_inline(&mut run, depth);
+      },
+      _ => {
+        let leaf = self.leaf(node);
+        self.push_line(depth, &leaf);
+      },
+    }
+  }
+}
+
+/// Re-indents the tree under `node` (the node itself included) from either sink: block elements go on their own
+/// lines, inline runs are joined with collapsed whitespace, and preserved elements such as `<pre>` keep their contents
+/// byte for byte. The result ends with a newline.
+pub fn format<N: DomNode>(node: &N, options: &FormatOptions) -> String {
+  let mut formatter = Formatter { options, lines: vec![] };
+  formatter.write_block(node, 0);
+  let mut out = formatter.lines.join("\n");
+  out.push('\n');
+  out
+}
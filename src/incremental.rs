@@ -0,0 +1,144 @@
+use encoding_rs::{Decoder, Encoding, UTF_8};
+use html5ever::{driver::{self, Parser}, tendril::{StrTendril, TendrilSink}};
+use html5ever::interface::tree_builder::TreeSink;
+use crate::encoding::{hinted_encoding, is_utf8_prefix, sniff, DecodeOptions, DecodedParse, EncodingSource, Sniffed};
+
+/// How many bytes the prescan looks at, and so how many are held back before parsing starts.
+const PRESCAN_LENGTH: usize = 1024;
+
+/// How many bytes from the first non-ASCII one are looked at to tell UTF-8 from the fallback, enough for one whole
+/// character of either.
+const UTF8_CHECK_LENGTH: usize = 4;
+
+/// A document parser fed byte chunks as they arrive instead of the whole input up front.
+///
+/// The encoding is sniffed like `encoding::parse_bytes` does, once the first 1024 bytes are in (or on `finish`, or
+/// after 3 bytes when the transport hint names a charset), so nothing is parsed before that. When those bytes are all
+/// ASCII and name no encoding, the fallback stays undecided until the first non-ASCII bytes arrive: if they are UTF-8
+/// the input is decoded as UTF-8, as `parse_bytes` would detect from the whole input. A later `<meta>` that disagrees
+/// cannot trigger a reparse of bytes already parsed; the result then keeps the tentative encoding.
+///
+/// Between chunks the sink holds the tree built so far, with every element still open, and `document` returns its root
+/// for inspection. To stop early, call `finish` (which closes whatever is open) or drop the parser.
+pub struct IncrementalParser<S: TreeSink> {
+  parser: Parser<S>,
+  options: DecodeOptions,
+  /// Bytes held back until the encoding is known.
+  pending: Vec<u8>,
+  decoder: Option<(Sniffed, Decoder)>,
+  /// Whether only ASCII has been decoded with the fallback so far, so UTF-8 can still take over.
+  undecided: bool,
+  had_errors: bool,
+  bytes_fed: usize,
+}
+
+impl<S: TreeSink> IncrementalParser<S> {
+  pub fn new(sink: S) -> Self {
+    Self::with_options(sink, &DecodeOptions::default())
+  }
+
+  pub fn with_options(sink: S, options: &DecodeOptions) -> Self {
+    IncrementalParser {
+      parser: driver::parse_document(sink, Default::default()),
+      options: options.clone(),
+      pending: vec![],
+      decoder: None,
+      undecided: false,
+      had_errors: false,
+      bytes_fed: 0,
+    }
+  }
+
+  pub fn feed(&mut self, chunk: &[u8]) {
+    self.bytes_fed += chunk.len();
+    if self.decoder.is_some() {
+      self.decode(chunk, false);
+      return;
+    }
+    self.pending.extend_from_slice(chunk);
+    let hinted = self.options.transport_hint.as_deref().and_then(hinted_encoding).is_some();
+    let enough = if hinted { 3 } else { PRESCAN_LENGTH };
+    if self.pending.len() >= enough {
+      self.start();
+    }
+  }
+
+  fn start(&mut self) {
+    let sniffed = sniff(&self.pending, &self.options);
+    let pending = std::mem::take(&mut self.pending);
+    self.decoder = Some((sniffed, sniffed.encoding.new_decoder_without_bom_handling()));
+    self.undecided = sniffed.source == EncodingSource::Fallback && sniffed.encoding.is_ascii_compatible() && pending.is_ascii();
+    self.decode(&pending[sniffed.bom_length..], false);
+  }
+
+  fn decode(&mut self, bytes: &[u8], last: bool) {
+    if !self.undecided {
+      self.run_decoder(bytes, last);
+      return;
+    }
+    let mut pending = std::mem::take(&mut self.pending);
+    pending.extend_from_slice(bytes);
+    let ascii = pending.iter().position(|byte| !byte.is_ascii()).unwrap_or(pending.len());
+    if ascii == pending.len() || (pending.len() - ascii < UTF8_CHECK_LENGTH && !last) {
+      // ASCII reads the same in the fallback and in UTF-8, so it can be parsed right away.
+      self.run_decoder(&pending[..ascii], last && ascii == pending.len());
+      self.pending = pending[ascii..].to_vec();
+      return;
+    }
+    self.undecided = false;
+    if is_utf8_prefix(&pending[ascii..]) {
+      let sniffed = Sniffed {
+        encoding: UTF_8,
+        source: EncodingSource::Utf8Detected,
+        ..self.decoder.as_ref().unwrap().0
+      };
+      self.decoder = Some((sniffed, UTF_8.new_decoder_without_bom_handling()));
+    }
+    self.run_decoder(&pending, last);
+  }
+
+  fn run_decoder(&mut self, bytes: &[u8], last: bool) {
+    let (_, decoder) = self.decoder.as_mut().unwrap();
+    let mut text = String::with_capacity(decoder.max_utf8_buffer_length(bytes.len()).unwrap());
+    // With room for the worst case the decoder always takes all of `bytes`, keeping a split character for the next call.
+    let (_, _, had_errors) = decoder.decode_to_string(bytes, &mut text, last);
+    self.had_errors |= had_errors;
+    if !text.is_empty() {
+      self.parser.process(StrTendril::from(text));
+    }
+  }
+
+  /// Parses whatever is left, closes every open element and returns the sink's output.
+  pub fn finish(mut self) -> DecodedParse<S::Output> {
+    if self.decoder.is_none() {
+      self.start();
+    }
+    self.decode(&[], true);
+    let (sniffed, _) = self.decoder.unwrap();
+    DecodedParse {
+      output: self.parser.finish(),
+      encoding: sniffed.encoding,
+      confidence: sniffed.confidence,
+      source: sniffed.source,
+      had_errors: self.had_errors,
+    }
+  }
+
+  /// The encoding the input is decoded with, once enough bytes have arrived to decide.
+  pub fn encoding(&self) -> Option<&'static Encoding> {
+    self.decoder.as_ref().map(|(sniffed, _)| sniffed.encoding)
+  }
+
+  pub fn bytes_fed(&self) -> usize {
+    self.bytes_fed
+  }
+
+  pub fn sink(&self) -> &S {
+    &self.parser.tokenizer.sink.sink
+  }
+
+  /// The root of the tree built so far.
+  pub fn document(&mut self) -> S::Handle {
+    self.parser.tokenizer.sink.sink.get_document()
+  }
+}
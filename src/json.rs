@@ -0,0 +1,222 @@
+use html5ever::{expanded_name, local_name, namespace_url, ns, tendril::StrTendril, Attribute, LocalName, Namespace, Prefix, QualName};
+use html5ever::interface::tree_builder::{ElementFlags, NodeOrText, TreeSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use serde_json::{json, Map, Value};
+use std::fmt;
+use crate::{arena::{Arena, Ref, Sink}, node::{DomNode, NodeKind}};
+
+/// Converts a tree into JSON that front ends and data jobs can consume without an HTML parser.
+///
+/// Every node is an object with a `type` of `document`, `doctype`, `text`, `comment`, `element` or
+/// `processing-instruction`:
+///
+/// - `document`: `children`
+/// - `doctype`: `name`, `publicId`, `systemId`
+/// - `text` and `comment`: `data`
+/// - `element`: `name` (the local name), `namespace` (the namespace URL), `prefix` when it has one, `attributes` as an
+///   array of `{ name, value }` in source order, with `namespace` and `prefix` added for namespaced attributes,
+///   `children`, and for a `<template>` also `content` holding the children of its template contents
+/// - `processing-instruction`: `target`, `data`
+pub fn to_json<N: DomNode>(node: &N) -> Value {
+  let children = || Value::Array(node.children().iter().map(to_json).collect());
+  match node.kind() {
+    NodeKind::Document => json!({ "type": "document", "children": children() }),
+    NodeKind::Doctype => {
+      let (name, public_id, system_id) = node.doctype().unwrap();
+      json!({ "type": "doctype", "name": &**name, "publicId": &**public_id, "systemId": &**system_id })
+    },
+    NodeKind::Text => json!({ "type": "text", "data": &**node.text().unwrap() }),
+    NodeKind::Comment => json!({ "type": "comment", "data": &**node.comment().unwrap() }),
+    NodeKind::ProcessingInstruction => json!({
+      "type": "processing-instruction",
+      "target": &**node.processing_instruction_target().unwrap(),
+      "data": &**node.comment().unwrap(),
+    }),
+    NodeKind::Element => {
+      let name = node.element_name().unwrap();
+      let mut object = Map::new();
+      object.insert("type".to_string(), json!("element"));
+      object.insert("name".to_string(), json!(&*name.local));
+      object.insert("namespace".to_string(), json!(&*name.ns));
+      if let Some(ref prefix) = name.prefix {
+        object.insert("prefix".to_string(), json!(&**prefix));
+      }
+      let attributes = node.attrs().unwrap().iter().map(attribute_to_json).collect();
+      object.insert("attributes".to_string(), Value::Array(attributes));
+      object.insert("children".to_string(), children());
+      if let Some(contents) = node.template_contents() {
+        object.insert("content".to_string(), Value::Array(contents.children().iter().map(to_json).collect()));
+      }
+      Value::Object(object)
+    },
+  }
+}
+
+fn attribute_to_json(attr: &Attribute) -> Value {
+  let mut object = Map::new();
+  object.insert("name".to_string(), json!(&*attr.name.local));
+  object.insert("value".to_string(), json!(&*attr.value));
+  if attr.name.ns != ns!() {
+    object.insert("namespace".to_string(), json!(&*attr.name.ns));
+  }
+  if let Some(ref prefix) = attr.name.prefix {
+    object.insert("prefix".to_string(), json!(&**prefix));
+  }
+  Value::Object(object)
+}
+
+/// Where in the JSON a node could not be rebuilt, as a JSON pointer such as `/children/1/attributes/0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+  pub path: String,
+  pub message: String,
+}
+
+impl fmt::Display for JsonError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", if self.path.is_empty() { "/" } else { &self.path }, self.message)
+  }
+}
+
+impl std::error::Error for JsonError {}
+
+fn error(path: &str, message: impl Into<String>) -> JsonError {
+  JsonError {
+    path: path.to_string(),
+    message: message.into(),
+  }
+}
+
+fn object<'a>(value: &'a Value, path: &str) -> Result<&'a Map<String, Value>, JsonError> {
+  value.as_object().ok_or_else(|| error(path, "expected an object"))
+}
+
+fn string<'a>(object: &'a Map<String, Value>, key: &str, path: &str) -> Result<&'a str, JsonError> {
+  match object.get(key) {
+    Some(Value::String(value)) => Ok(value),
+    Some(_) => Err(error(&format!("{}/{}", path, key), "expected a string")),
+    None => Err(error(path, format!("missing {:?}", key))),
+  }
+}
+
+fn optional_string<'a>(object: &'a Map<String, Value>, key: &str, path: &str) -> Result<Option<&'a str>, JsonError> {
+  match object.get(key) {
+    None | Some(Value::Null) => Ok(None),
+    Some(_) => string(object, key, path).map(Some),
+  }
+}
+
+fn array<'a>(object: &'a Map<String, Value>, key: &str, path: &str) -> Result<&'a [Value], JsonError> {
+  match object.get(key) {
+    Some(Value::Array(values)) => Ok(values),
+    Some(_) => Err(error(&format!("{}/{}", path, key), "expected an array")),
+    None => Ok(&[]),
+  }
+}
+
+fn attribute_from_json(value: &Value, path: &str) -> Result<Attribute, JsonError> {
+  let object = object(value, path)?;
+  let namespace = optional_string(object, "namespace", path)?.unwrap_or_default();
+  Ok(Attribute {
+    name: QualName::new(
+      optional_string(object, "prefix", path)?.map(Prefix::from),
+      Namespace::from(namespace),
+      LocalName::from(string(object, "name", path)?),
+    ),
+    value: StrTendril::from(string(object, "value", path)?),
+  })
+}
+
+/// Rebuilds the subtree `value` describes through the `TreeSink` methods the parser uses, so that both trees get
+/// their template contents and text merging the same way.
+fn build<S: TreeSink>(sink: &mut S, parent: Option<&S::Handle>, value: &Value, path: &str) -> Result<Option<S::Handle>, JsonError> {
+  let object = object(value, path)?;
+  let node = match string(object, "type", path)? {
+    "document" => {
+      if parent.is_some() {
+        return Err(error(path, "a document cannot be a child"));
+      }
+      sink.get_document()
+    },
+    "doctype" => {
+      let document = sink.get_document();
+      if !parent.is_some_and(|parent| sink.same_node(parent, &document)) {
+        return Err(error(path, "a doctype can only be a child of a document"));
+      }
+      sink.append_doctype_to_document(
+        StrTendril::from(string(object, "name", path)?),
+        StrTendril::from(optional_string(object, "publicId", path)?.unwrap_or_default()),
+        StrTendril::from(optional_string(object, "systemId", path)?.unwrap_or_default()),
+      );
+      return Ok(None);
+    },
+    "text" => {
+      let parent = parent.ok_or_else(|| error(path, "a text node needs a parent"))?;
+      sink.append(parent, NodeOrText::AppendText(StrTendril::from(string(object, "data", path)?)));
+      return Ok(None);
+    },
+    "comment" => sink.create_comment(StrTendril::from(string(object, "data", path)?)),
+    "processing-instruction" => sink.create_pi(
+      StrTendril::from(string(object, "target", path)?),
+      StrTendril::from(string(object, "data", path)?),
+    ),
+    "element" => {
+      let namespace = Namespace::from(string(object, "namespace", path)?);
+      let name = QualName::new(
+        optional_string(object, "prefix", path)?.map(Prefix::from),
+        namespace,
+        LocalName::from(string(object, "name", path)?),
+      );
+      let attrs = array(object, "attributes", path)?
+        .iter()
+        .enumerate()
+        .map(|(index, attr)| attribute_from_json(attr, &format!("{}/attributes/{}", path, index)))
+        .collect::<Result<Vec<_>, _>>()?;
+      let mut flags = ElementFlags::default();
+      flags.template = name.expanded() == expanded_name!(html "template");
+      // The same test the tree builder applies to `<annotation-xml>` start tags.
+      flags.mathml_annotation_xml_integration_point = name.expanded() == expanded_name!(mathml "annotation-xml")
+        && attrs.iter().any(|attr| {
+          attr.name.expanded() == expanded_name!("", "encoding")
+            && (attr.value.eq_ignore_ascii_case("text/html") || attr.value.eq_ignore_ascii_case("application/xhtml+xml"))
+        });
+      let is_template = flags.template;
+      let element = sink.create_element(name, attrs, flags);
+      if is_template {
+        let contents = sink.get_template_contents(&element);
+        for (index, child) in array(object, "content", path)?.iter().enumerate() {
+          build(sink, Some(&contents), child, &format!("{}/content/{}", path, index))?;
+        }
+      } else if object.contains_key("content") {
+        return Err(error(&format!("{}/content", path), "only a template has content"));
+      }
+      element
+    },
+    other => return Err(error(&format!("{}/type", path), format!("unknown node type {:?}", other))),
+  };
+
+  if let Some(parent) = parent {
+    sink.append(parent, NodeOrText::AppendNode(node.clone()));
+  }
+  for (index, child) in array(object, "children", path)?.iter().enumerate() {
+    build(sink, Some(&node), child, &format!("{}/children/{}", path, index))?;
+  }
+  Ok(Some(node))
+}
+
+fn build_root<S: TreeSink>(sink: &mut S, value: &Value) -> Result<S::Handle, JsonError> {
+  build(sink, None, value, "")?.ok_or_else(|| error("", "a text node or doctype cannot be the root"))
+}
+
+/// Rebuilds an `RcDom` tree from the JSON `to_json` produces. The root can be a document or, for an exported
+/// subtree, an element, comment or processing instruction, which is returned without a parent.
+pub fn from_json(value: &Value) -> Result<Handle, JsonError> {
+  let mut dom = RcDom::default();
+  build_root(&mut dom, value)
+}
+
+/// Rebuilds a tree allocated in `arena` from the JSON `to_json` produces, like `from_json`.
+pub fn from_json_arena<'arena>(arena: Arena<'arena>, value: &Value) -> Result<Ref<'arena>, JsonError> {
+  let mut sink = Sink::new(arena);
+  build_root(&mut sink, value)
+}
@@ -0,0 +1,19 @@
+pub mod arena;
+pub mod attributes;
+pub mod diagnostics;
+pub mod diff;
+pub mod encoding;
+pub mod format;
+pub mod incremental;
+pub mod json;
+pub mod minify;
+pub mod mutation;
+pub mod node;
+pub mod quirks;
+pub mod rcdom;
+pub mod rewrite;
+pub mod sanitize;
+pub mod select;
+pub mod text;
+pub mod xpath;
+pub mod template;
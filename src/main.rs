@@ -1,5 +1,299 @@
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use rust_html5ever_sample::{diagnostics::parse_document_with_diagnostics, diff::{diff, report, unified_diff}, encoding::{decode_bytes, parse_bytes, DecodeOptions}, format::{format, FormatOptions}, json::to_json, minify::{minify, MinifyOptions}, quirks, rcdom::{convert_node_to_html_string, outer_html}, select::select, text::{inner_text, text_content}, template::{render_with_options, TemplateContext, TemplateOptions}};
+use std::{fs, io::{self, Read, Write}, process};
+
+const USAGE: &str = "usage: html5ever-sample <command> [options] [file]
+
+Reads HTML from [file], or from stdin when it is missing or `-`, and writes to stdout. The input's encoding is
+sniffed from its byte order mark or <meta charset>, falling back to UTF-8 when it is valid and EUC-KR otherwise.
+
+options:
+  --charset <label>              decode the input as <label>, such as euc-kr, instead of sniffing it
+
+commands:
+  parse                          dump the parsed tree
+  select <css>                   print the outerHTML of every element matching <css>
+  text                           print the text as a browser renders it
+      --raw                      print every text node as is instead
+  format                         print the document re-indented (alias: pretty)
+      --indent <n>               spaces per level (default 2)
+      --wrap <n>                 put attributes on their own lines past column <n> (default 100, 0 never wraps)
+  json                           print the tree as JSON
+  diff <old> <new>               print the structural differences between two documents
+      --unified                  print a unified diff of the re-indented documents instead
+  minify                         print the document without comments, insignificant whitespace and optional tags
+      --report                   print the byte savings to stderr
+  lint                           print parse errors as line:column: message
+      --max-errors <n>           fail when there are more than <n> errors (default 0)
+  render-template --vars <json>  substitute [##_name_##] placeholders and s_* sections
+      --section-tag <tag>        treat <tag> as a section too (repeatable)";
+
+enum CliError {
+  Usage(String),
+  Failed(String),
+}
+
+impl From<io::Error> for CliError {
+  fn from(error: io::Error) -> Self {
+    CliError::Failed(error.to_string())
+  }
+}
+
 fn main() {
-  let str = "hello\nworld";
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  match run(&args) {
+    Ok(()) => {},
+    Err(CliError::Usage(message)) => {
+      eprintln!("html5ever-sample: {}\n\n{}", message, USAGE);
+      process::exit(2);
+    },
+    Err(CliError::Failed(message)) => {
+      eprintln!("html5ever-sample: {}", message);
+      process::exit(1);
+    },
+  }
+}
+
+struct Args {
+  positional: Vec<String>,
+  vars: Option<String>,
+  section_tags: Vec<String>,
+  max_errors: Option<String>,
+  raw: bool,
+  report: bool,
+  unified: bool,
+  indent: Option<String>,
+  wrap: Option<String>,
+  charset: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, CliError> {
+  let mut parsed = Args {
+    positional: vec![],
+    vars: None,
+    section_tags: vec![],
+    max_errors: None,
+    raw: false,
+    report: false,
+    unified: false,
+    indent: None,
+    wrap: None,
+    charset: None,
+  };
+  let mut iter = args.iter();
+  while let Some(arg) = iter.next() {
+    let mut value = |name: &str| {
+      iter
+        .next()
+        .cloned()
+        .ok_or_else(|| CliError::Usage(format!("{} needs a value", name)))
+    };
+    match arg.as_str() {
+      "--vars" => parsed.vars = Some(value(arg)?),
+      "--section-tag" => parsed.section_tags.push(value(arg)?),
+      "--max-errors" => parsed.max_errors = Some(value(arg)?),
+      "--raw" => parsed.raw = true,
+      "--report" => parsed.report = true,
+      "--unified" => parsed.unified = true,
+      "--indent" => parsed.indent = Some(value(arg)?),
+      "--wrap" => parsed.wrap = Some(value(arg)?),
+      "--charset" => parsed.charset = Some(value(arg)?),
+      "-" => parsed.positional.push(arg.clone()),
+      _ if arg.starts_with("--") => return Err(CliError::Usage(format!("unknown option {}", arg))),
+      _ => parsed.positional.push(arg.clone()),
+    }
+  }
+  Ok(parsed)
+}
+
+fn read_input(path: Option<&String>) -> Result<Vec<u8>, CliError> {
+  let mut bytes = vec![];
+  match path.map(String::as_str) {
+    None | Some("-") => {
+      io::stdin().read_to_end(&mut bytes)?;
+    },
+    Some(path) => {
+      bytes = fs::read(path).map_err(|error| CliError::Failed(format!("{}: {}", path, error)))?;
+    },
+  }
+  Ok(bytes)
+}
+
+fn decode_options(charset: &Option<String>) -> DecodeOptions {
+  DecodeOptions {
+    transport_hint: charset.clone(),
+    ..Default::default()
+  }
+}
+
+fn parse_input(path: Option<&String>, charset: &Option<String>) -> Result<Handle, CliError> {
+  let bytes = read_input(path)?;
+  Ok(parse_bytes(RcDom::default, &bytes, &decode_options(charset)).output.document)
+}
+
+fn parse_number(option: &str, value: &str) -> Result<usize, CliError> {
+  value.parse().map_err(|_| CliError::Usage(format!("invalid {} {}", option, value)))
+}
+
+fn expect_arity(command: &str, positional: &[String], required: usize) -> Result<(), CliError> {
+  if positional.len() < required || positional.len() > required + 1 {
+    return Err(CliError::Usage(format!("wrong number of arguments for {}", command)));
+  }
+  Ok(())
+}
+
+fn run(args: &[String]) -> Result<(), CliError> {
+  let command = match args.first() {
+    Some(command) => command.as_str(),
+    None => return Err(CliError::Usage("missing command".to_string())),
+  };
+  if matches!(command, "help" | "-h" | "--help") {
+    println!("{}", USAGE);
+    return Ok(());
+  }
+  let args = parse_args(&args[1..])?;
+  let mut out = io::stdout().lock();
+
+  match command {
+    "parse" => {
+      expect_arity(command, &args.positional, 0)?;
+      let document = parse_input(args.positional.first(), &args.charset)?;
+      dump(&mut out, 0, &document)?;
+    },
+    "select" => {
+      expect_arity(command, &args.positional, 1)?;
+      let document = parse_input(args.positional.get(1), &args.charset)?;
+      let nodes = select(&document, &args.positional[0]).map_err(|error| CliError::Failed(format!("invalid selector: {}", error)))?;
+      for node in nodes {
+        writeln!(out, "{}", outer_html(&node))?;
+      }
+    },
+    "text" => {
+      expect_arity(command, &args.positional, 0)?;
+      let document = parse_input(args.positional.first(), &args.charset)?;
+      let text = if args.raw { text_content(&document) } else { inner_text(&document) };
+      writeln!(out, "{}", text)?;
+    },
+    "format" | "pretty" => {
+      expect_arity(command, &args.positional, 0)?;
+      let mut options = FormatOptions::default();
+      if let Some(indent) = args.indent {
+        options.indent_width = parse_number("--indent", &indent)?;
+      }
+      if let Some(wrap) = args.wrap {
+        options.wrap_attributes_at = Some(parse_number("--wrap", &wrap)?).filter(|width| *width > 0);
+      }
+      let document = parse_input(args.positional.first(), &args.charset)?;
+      write!(out, "{}", format(&document, &options))?;
+    },
+    "json" => {
+      expect_arity(command, &args.positional, 0)?;
+      let document = parse_input(args.positional.first(), &args.charset)?;
+      writeln!(out, "{}", serde_json::to_string_pretty(&to_json(&document)).unwrap())?;
+    },
+    "diff" => {
+      if args.positional.len() != 2 {
+        return Err(CliError::Usage(format!("wrong number of arguments for {}", command)));
+      }
+      let old = parse_input(args.positional.first(), &args.charset)?;
+      let new = parse_input(args.positional.get(1), &args.charset)?;
+      if args.unified {
+        write!(out, "{}", unified_diff(&old, &new, &args.positional[0], &args.positional[1]))?;
+      } else {
+        write!(out, "{}", report(&diff(&old, &new)))?;
+      }
+    },
+    "minify" => {
+      expect_arity(command, &args.positional, 0)?;
+      let document = parse_input(args.positional.first(), &args.charset)?;
+      let (html, report) = minify(&document, &MinifyOptions::default());
+      writeln!(out, "{}", html)?;
+      if args.report {
+        eprintln!("{} -> {} bytes, saved {} ({:.1}%)", report.original_bytes, report.minified_bytes, report.saved_bytes(), report.saved_percent());
+      }
+    },
+    "lint" => {
+      expect_arity(command, &args.positional, 0)?;
+      let max_errors = match args.max_errors {
+        Some(value) => parse_number("--max-errors", &value)?,
+        None => 0,
+      };
+      let bytes = read_input(args.positional.first())?;
+      let html = decode_bytes(&bytes, &decode_options(&args.charset)).output;
+      let result = parse_document_with_diagnostics(RcDom::default(), &html);
+      for diagnostic in result.diagnostics.iter() {
+        writeln!(out, "{}", diagnostic)?;
+      }
+      let audit = quirks::audit(&result.output.document, result.quirks_mode);
+      if audit.is_legacy() {
+        eprintln!("warning: renders in {:?} mode because {}", audit.mode, audit.reason);
+      }
+      if let Err(diagnostics) = result.lint(max_errors) {
+        return Err(CliError::Failed(format!("{} parse errors (at most {} allowed)", diagnostics.len(), max_errors)));
+      }
+    },
+    "render-template" => {
+      expect_arity(command, &args.positional, 0)?;
+      let vars_path = args.vars.ok_or_else(|| CliError::Usage("render-template needs --vars <json>".to_string()))?;
+      let vars = fs::read_to_string(&vars_path).map_err(|error| CliError::Failed(format!("{}: {}", vars_path, error)))?;
+      let json: serde_json::Value = serde_json::from_str(&vars).map_err(|error| CliError::Failed(format!("{}: {}", vars_path, error)))?;
+      let context = TemplateContext::from_json(&json).map_err(|error| CliError::Failed(format!("{}: {}", vars_path, error)))?;
+      let options = TemplateOptions {
+        section_tags: args.section_tags,
+        ..Default::default()
+      };
+
+      let document = parse_input(args.positional.first(), &args.charset)?;
+      let report = render_with_options(&document, &context, &options);
+      writeln!(out, "{}", convert_node_to_html_string(&document))?;
+      for name in report.unknown {
+        eprintln!("warning: no value for placeholder [##_{}_##]", name);
+      }
+      for name in report.missing_sections {
+        eprintln!("warning: no value for section <{}>", name);
+      }
+      for name in report.unused {
+        eprintln!("warning: unused variable {}", name);
+      }
+    },
+    _ => return Err(CliError::Usage(format!("unknown command {}", command))),
+  }
+  Ok(())
+}
+
+fn dump(out: &mut impl Write, depth: usize, handle: &Handle) -> io::Result<()> {
+  let indent = "  ".repeat(depth);
+  match handle.data {
+    NodeData::Document => writeln!(out, "{}#Document", indent)?,
+    NodeData::Doctype {
+      ref name,
+      ref public_id,
+      ref system_id,
+    } => writeln!(out, "{}<!DOCTYPE {} \"{}\" \"{}\">", indent, name, public_id, system_id)?,
+    NodeData::Text { ref contents } => {
+      writeln!(out, "{}#text: {}", indent, contents.borrow().escape_default())?
+    },
+    NodeData::Comment { ref contents } => writeln!(out, "{}<!-- {} -->", indent, contents.escape_default())?,
+    NodeData::Element {
+      ref name,
+      ref attrs,
+      ref template_contents,
+      ..
+    } => {
+      write!(out, "{}<{}", indent, name.local)?;
+      for attr in attrs.borrow().iter() {
+        write!(out, " {}=\"{}\"", attr.name.local, attr.value.escape_default())?;
+      }
+      writeln!(out, ">")?;
+      if let Some(contents) = template_contents.borrow().as_ref() {
+        dump(out, depth + 1, contents)?;
+      }
+    },
+    NodeData::ProcessingInstruction { ref target, ref contents } => writeln!(out, "{}<?{} {}>", indent, target, contents)?,
+  }
 
-  println!("str: {}", str);
-}
\ No newline at end of file
+  for child in handle.children.borrow().iter() {
+    dump(out, depth + 1, child)?;
+  }
+  Ok(())
+}
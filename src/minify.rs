@@ -0,0 +1,364 @@
+use markup5ever_rcdom::{Handle, NodeData};
+use crate::{format::{attr_name, content_children, doctype, escape_attr, escape_text_char, html_local, is_void, PRESERVED_ELEMENTS, RAW_TEXT_ELEMENTS}, mutation::clone_node, node::{DomNode, NodeKind}, rcdom::outer_html, text::BLOCK_ELEMENTS};
+
+/// Elements around which whitespace never renders, on top of the block elements.
+const WHITESPACE_INSENSITIVE_ELEMENTS: &[&str] = &[
+  "head", "title", "base", "thead", "tbody", "tfoot", "th", "td", "colgroup", "col", "option", "optgroup",
+];
+
+/// Elements that render nothing but can sit inline, so whitespace around them depends on what renders around them.
+const INVISIBLE_ELEMENTS: &[&str] = &["script", "style", "template", "noscript", "link", "meta"];
+
+const BOOLEAN_ATTRIBUTES: &[&str] = &[
+  "allowfullscreen", "async", "autofocus", "autoplay", "checked", "controls", "default", "defer", "disabled", "formnovalidate", "inert",
+  "ismap", "itemscope", "loop", "multiple", "muted", "nomodule", "novalidate", "open", "playsinline", "readonly", "required", "reversed",
+  "selected",
+];
+
+/// Elements a `</p>` can be left out in front of.
+const CLOSES_P: &[&str] = &[
+  "address", "article", "aside", "blockquote", "details", "div", "dl", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2",
+  "h3", "h4", "h5", "h6", "header", "hgroup", "hr", "main", "menu", "nav", "ol", "p", "pre", "section", "table", "ul",
+];
+
+#[derive(Debug, Clone)]
+pub struct MinifyOptions {
+  /// Drops whitespace-only text next to block elements and collapses other whitespace runs to one space.
+  pub collapse_whitespace: bool,
+  pub remove_comments: bool,
+  /// Keeps `<!--[if IE]>...<![endif]-->` style comments when removing comments.
+  pub keep_conditional_comments: bool,
+  /// Leaves out start and end tags the HTML spec lets the parser infer, such as `</li>` or `<head>`.
+  pub omit_optional_tags: bool,
+  /// Writes attribute values without quotes when they contain nothing that would end an unquoted value.
+  pub remove_attribute_quotes: bool,
+  /// Writes boolean attributes such as `disabled="disabled"`, and any attribute with an empty value, as a bare name.
+  pub collapse_boolean_attributes: bool,
+}
+
+impl Default for MinifyOptions {
+  fn default() -> Self {
+    MinifyOptions {
+      collapse_whitespace: true,
+      remove_comments: true,
+      keep_conditional_comments: true,
+      omit_optional_tags: true,
+      remove_attribute_quotes: true,
+      collapse_boolean_attributes: true,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinifyReport {
+  /// Size of the plain serialization before minifying.
+  pub original_bytes: usize,
+  pub minified_bytes: usize,
+}
+
+impl MinifyReport {
+  pub fn saved_bytes(&self) -> usize {
+    self.original_bytes.saturating_sub(self.minified_bytes)
+  }
+
+  /// Saved bytes as a percentage of the original size.
+  pub fn saved_percent(&self) -> f64 {
+    if self.original_bytes == 0 {
+      0.0
+    } else {
+      self.saved_bytes() as f64 * 100.0 / self.original_bytes as f64
+    }
+  }
+}
+
+fn is_conditional_comment(contents: &str) -> bool {
+  contents.starts_with("[if") || contents.starts_with("<![endif]")
+}
+
+fn is_whitespace_insensitive(handle: &Handle) -> bool {
+  html_local(handle).is_some_and(|local| BLOCK_ELEMENTS.contains(&local) || WHITESPACE_INSENSITIVE_ELEMENTS.contains(&local))
+}
+
+fn is_preserved(handle: &Handle) -> bool {
+  html_local(handle).is_some_and(|local| PRESERVED_ELEMENTS.contains(&local))
+}
+
+fn is_invisible(handle: &Handle) -> bool {
+  handle.kind() == NodeKind::Comment || html_local(handle).is_some_and(|local| INVISIBLE_ELEMENTS.contains(&local))
+}
+
+/// The nearest sibling in `siblings` that renders something, skipping comments and invisible elements.
+fn rendered_sibling<'a>(mut siblings: impl Iterator<Item = &'a Handle>) -> Option<&'a Handle> {
+  siblings.find(|sibling| !is_invisible(sibling))
+}
+
+/// Whether whitespace next to this rendered sibling is insignificant: there is none and the parent is a block, or it
+/// is a block.
+fn is_boundary(sibling: Option<&Handle>, parent: &Handle) -> bool {
+  match sibling {
+    Some(sibling) => is_whitespace_insensitive(sibling),
+    None => parent.kind() != NodeKind::Element || is_whitespace_insensitive(parent),
+  }
+}
+
+fn ends_with_space(node: Option<&Handle>) -> bool {
+  node.and_then(|node| node.text()).is_some_and(|text| text.ends_with(' '))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut last_was_space = false;
+  for c in text.chars() {
+    if c.is_ascii_whitespace() {
+      if !last_was_space {
+        out.push(' ');
+      }
+      last_was_space = true;
+    } else {
+      out.push(c);
+      last_was_space = false;
+    }
+  }
+  out
+}
+
+/// Removes comments and insignificant whitespace below `parent`, merging the text nodes that end up next to each other.
+fn clean(parent: &Handle, options: &MinifyOptions, preserved: bool) {
+  let mut children = parent.children.borrow_mut();
+  if options.remove_comments {
+    children.retain(|child| match child.data {
+      NodeData::Comment { ref contents } => options.keep_conditional_comments && is_conditional_comment(contents),
+      _ => true,
+    });
+  }
+
+  let mut merged: Vec<Handle> = Vec::with_capacity(children.len());
+  for child in children.drain(..) {
+    if let (Some(previous), NodeData::Text { contents: ref text }) = (merged.last(), &child.data) {
+      if let NodeData::Text { ref contents } = previous.data {
+        contents.borrow_mut().push_tendril(&text.borrow());
+        child.parent.set(None);
+        continue;
+      }
+    }
+    merged.push(child);
+  }
+
+  if options.collapse_whitespace && !preserved {
+    let mut index = 0;
+    while index < merged.len() {
+      if let NodeData::Text { ref contents } = merged[index].data {
+        let mut text = collapse_whitespace(&contents.borrow());
+        let previous = rendered_sibling(merged[..index].iter().rev());
+        // A space right after one that already renders, with only invisible nodes in between, collapses into it.
+        if text.starts_with(' ') && (is_boundary(previous, parent) || ends_with_space(previous)) {
+          text.remove(0);
+        }
+        if text.ends_with(' ') && is_boundary(rendered_sibling(merged[index + 1..].iter()), parent) {
+          text.pop();
+        }
+        if text.is_empty() {
+          merged.remove(index).parent.set(None);
+          continue;
+        }
+        *contents.borrow_mut() = text.into();
+      }
+      index += 1;
+    }
+  }
+  *children = merged;
+  drop(children);
+
+  for child in parent.children.borrow().clone().iter() {
+    if let NodeData::Element { ref template_contents, .. } = child.data {
+      let preserved = preserved || is_preserved(child);
+      clean(child, options, preserved);
+      if let Some(contents) = template_contents.borrow().as_ref() {
+        clean(contents, options, preserved);
+      }
+    }
+  }
+}
+
+fn is_element_named(node: Option<&Handle>, names: &[&str]) -> bool {
+  node.and_then(html_local).is_some_and(|local| names.contains(&local))
+}
+
+fn is_comment(node: Option<&Handle>) -> bool {
+  matches!(node.map(|node| &node.data), Some(NodeData::Comment { .. }))
+}
+
+fn starts_with_space(node: Option<&Handle>) -> bool {
+  match node.map(|node| &node.data) {
+    Some(NodeData::Text { ref contents }) => contents.borrow().starts_with(|c: char| c.is_ascii_whitespace()),
+    _ => false,
+  }
+}
+
+fn has_attrs(handle: &Handle) -> bool {
+  handle.attrs().is_some_and(|attrs| !attrs.is_empty())
+}
+
+/// The start tag rules of the spec's "optional tags" section.
+fn can_omit_start_tag(handle: &Handle, children: &[Handle]) -> bool {
+  let first = children.first();
+  match html_local(handle) {
+    Some("html") => !has_attrs(handle) && !is_comment(first),
+    Some("head") => !has_attrs(handle) && (first.is_none() || first.is_some_and(|node| node.is_element())),
+    Some("body") => {
+      !has_attrs(handle)
+        && !starts_with_space(first)
+        && !is_comment(first)
+        && !is_element_named(first, &["meta", "link", "script", "style", "template"])
+    },
+    _ => false,
+  }
+}
+
+/// The end tag rules of the spec's "optional tags" section.
+fn can_omit_end_tag(handle: &Handle) -> bool {
+  let next = handle.next_sibling();
+  let next = next.as_ref();
+  let last = next.is_none();
+  match html_local(handle) {
+    Some("html") | Some("body") => !is_comment(next),
+    Some("head") | Some("colgroup") => !is_comment(next) && !starts_with_space(next),
+    Some("li") => last || is_element_named(next, &["li"]),
+    Some("dt") => is_element_named(next, &["dt", "dd"]),
+    Some("dd") => last || is_element_named(next, &["dt", "dd"]),
+    Some("p") => {
+      is_element_named(next, CLOSES_P)
+        || (last
+          && !handle
+            .parent()
+            .is_some_and(|parent| is_element_named(Some(&parent), &["a", "audio", "del", "ins", "map", "noscript", "video"]) || html_local(&parent).is_some_and(|local| local.contains('-'))))
+    },
+    Some("rt") | Some("rp") => last || is_element_named(next, &["rt", "rp"]),
+    Some("optgroup") => last || is_element_named(next, &["optgroup", "hr"]),
+    Some("option") => last || is_element_named(next, &["option", "optgroup", "hr"]),
+    Some("thead") => is_element_named(next, &["tbody", "tfoot"]),
+    Some("tbody") => last || is_element_named(next, &["tbody", "tfoot"]),
+    Some("tfoot") => last,
+    Some("tr") => last || is_element_named(next, &["tr"]),
+    Some("td") | Some("th") => last || is_element_named(next, &["td", "th"]),
+    _ => false,
+  }
+}
+
+fn can_be_unquoted(value: &str) -> bool {
+  !value.is_empty() && !value.contains(|c: char| c.is_ascii_whitespace() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '`'))
+}
+
+struct Writer<'a> {
+  options: &'a MinifyOptions,
+  out: String,
+}
+
+impl<'a> Writer<'a> {
+  fn write_start_tag(&mut self, handle: &Handle) {
+    let name = handle.element_name().unwrap();
+    self.out.push('<');
+    self.out.push_str(&name.local);
+    for attr in handle.attrs().unwrap().iter() {
+      let attr_name = attr_name(&attr.name);
+      self.out.push(' ');
+      self.out.push_str(&attr_name);
+
+      let is_boolean = html_local(handle).is_some()
+        && BOOLEAN_ATTRIBUTES.contains(&attr_name.as_str())
+        && (attr.value.is_empty() || attr.value.eq_ignore_ascii_case(&attr_name));
+      if self.options.collapse_boolean_attributes && (is_boolean || attr.value.is_empty()) {
+        continue;
+      }
+      self.out.push('=');
+      if self.options.remove_attribute_quotes && can_be_unquoted(&attr.value) {
+        for c in attr.value.chars() {
+          match c {
+            '&' => self.out.push_str("&amp;"),
+            '\u{A0}' => self.out.push_str("&nbsp;"),
+            c => self.out.push(c),
+          }
+        }
+      } else {
+        self.out.push('"');
+        escape_attr(&attr.value, &mut self.out);
+        self.out.push('"');
+      }
+    }
+    self.out.push('>');
+  }
+
+  fn write(&mut self, handle: &Handle, raw_text: bool) {
+    match handle.data {
+      NodeData::Document => {
+        for child in handle.children.borrow().iter() {
+          self.write(child, false);
+        }
+      },
+      NodeData::Doctype { ref name, ref public_id, ref system_id } => self.out.push_str(&doctype(name, public_id, system_id)),
+      NodeData::Text { ref contents } => {
+        if raw_text {
+          self.out.push_str(&contents.borrow());
+        } else {
+          contents.borrow().chars().for_each(|c| escape_text_char(c, &mut self.out));
+        }
+      },
+      NodeData::Comment { ref contents } => {
+        self.out.push_str("<!--");
+        self.out.push_str(contents);
+        self.out.push_str("-->");
+      },
+      NodeData::ProcessingInstruction { ref target, ref contents } => {
+        self.out.push_str("<?");
+        self.out.push_str(target);
+        self.out.push(' ');
+        self.out.push_str(contents);
+        self.out.push('>');
+      },
+      NodeData::Element { ref name, .. } => {
+        let children = content_children(handle);
+        if !(self.options.omit_optional_tags && can_omit_start_tag(handle, &children)) {
+          self.write_start_tag(handle);
+        }
+        let local = html_local(handle).unwrap_or_default();
+        // The parser drops a newline right after these start tags, so one that is part of the text needs another.
+        if matches!(local, "pre" | "textarea" | "listing") && children.first().and_then(|child| child.text()).is_some_and(|text| text.starts_with('\n')) {
+          self.out.push('\n');
+        }
+        let raw_text = RAW_TEXT_ELEMENTS.contains(&local);
+        for child in children.iter() {
+          self.write(child, raw_text);
+        }
+        if !(is_void(handle) || self.options.omit_optional_tags && can_omit_end_tag(handle)) {
+          self.out.push_str("</");
+          self.out.push_str(&name.local);
+          self.out.push('>');
+        }
+      },
+    }
+  }
+}
+
+/// Serializes the tree under `handle` without comments and insignificant whitespace, and with optional tags, attribute
+/// quotes and boolean attribute values left out as `options` allow. The tree itself is left as it is: a copy is
+/// minified.
+pub fn minify(handle: &Handle, options: &MinifyOptions) -> (String, MinifyReport) {
+  let original_bytes = outer_html(handle).len();
+  let handle = &clone_node(handle, true);
+
+  let preserved = is_preserved(handle);
+  clean(handle, options, preserved);
+  if let NodeData::Element { ref template_contents, .. } = handle.data {
+    if let Some(contents) = template_contents.borrow().as_ref() {
+      clean(contents, options, preserved);
+    }
+  }
+
+  let mut writer = Writer { options, out: String::new() };
+  writer.write(handle, false);
+  let report = MinifyReport {
+    original_bytes,
+    minified_bytes: writer.out.len(),
+  };
+  (writer.out, report)
+}
@@ -0,0 +1,171 @@
+use markup5ever_rcdom::{Handle, Node, NodeData};
+use std::{cell::RefCell, fmt, rc::Rc};
+use crate::node::DomNode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MutationError {
+  /// The node would become its own ancestor.
+  Cycle,
+  /// The reference node has no parent to insert next to.
+  NoParent,
+  /// Only documents and elements can have children.
+  NotAContainer,
+}
+
+impl fmt::Display for MutationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MutationError::Cycle => write!(f, "a node cannot be inserted into itself or its descendants"),
+      MutationError::NoParent => write!(f, "the reference node has no parent"),
+      MutationError::NotAContainer => write!(f, "only documents and elements can have children"),
+    }
+  }
+}
+
+impl std::error::Error for MutationError {}
+
+/// Removes the node from its parent, if it has one. The node and its subtree stay usable.
+pub fn detach(node: &Handle) {
+  if let Some(parent) = node.parent() {
+    parent.children.borrow_mut().retain(|child| !Rc::ptr_eq(child, node));
+  }
+  node.parent.set(None);
+}
+
+fn index_in_parent(node: &Handle) -> Option<(Handle, usize)> {
+  let parent = node.parent()?;
+  let index = parent.children.borrow().iter().position(|child| Rc::ptr_eq(child, node))?;
+  Some((parent, index))
+}
+
+fn check_insertion(parent: &Handle, node: &Handle) -> Result<(), MutationError> {
+  if !matches!(parent.data, NodeData::Document | NodeData::Element { .. }) {
+    return Err(MutationError::NotAContainer);
+  }
+  let mut ancestor = Some(parent.clone());
+  while let Some(current) = ancestor {
+    if Rc::ptr_eq(&current, node) {
+      return Err(MutationError::Cycle);
+    }
+    ancestor = current.parent();
+  }
+  Ok(())
+}
+
+/// Moves `node` from wherever it is, including another document, to `parent`'s children at the index
+/// `index` computes once the node has been detached.
+fn insert_at<F>(parent: &Handle, node: &Handle, index: F) -> Result<(), MutationError>
+where
+  F: FnOnce(&[Handle]) -> usize,
+{
+  check_insertion(parent, node)?;
+  detach(node);
+  let mut children = parent.children.borrow_mut();
+  let index = index(&children);
+  node.parent.set(Some(Rc::downgrade(parent)));
+  children.insert(index, node.clone());
+  Ok(())
+}
+
+/// Appends `child` as the last child of `parent`, moving it out of its current parent or document first.
+pub fn append(parent: &Handle, child: &Handle) -> Result<(), MutationError> {
+  insert_at(parent, child, |children| children.len())
+}
+
+/// Inserts `child` as the first child of `parent`, moving it out of its current parent or document first.
+pub fn prepend(parent: &Handle, child: &Handle) -> Result<(), MutationError> {
+  insert_at(parent, child, |_| 0)
+}
+
+fn insert_next_to(reference: &Handle, node: &Handle, offset: usize) -> Result<(), MutationError> {
+  if Rc::ptr_eq(reference, node) {
+    return Ok(());
+  }
+  let parent = reference.parent().ok_or(MutationError::NoParent)?;
+  insert_at(&parent, node, |children| {
+    children.iter().position(|child| Rc::ptr_eq(child, reference)).unwrap() + offset
+  })
+}
+
+pub fn insert_before(reference: &Handle, node: &Handle) -> Result<(), MutationError> {
+  insert_next_to(reference, node, 0)
+}
+
+pub fn insert_after(reference: &Handle, node: &Handle) -> Result<(), MutationError> {
+  insert_next_to(reference, node, 1)
+}
+
+/// Puts `replacement` where `node` is and detaches `node`.
+pub fn replace_with(node: &Handle, replacement: &Handle) -> Result<(), MutationError> {
+  if Rc::ptr_eq(node, replacement) {
+    return Ok(());
+  }
+  insert_before(node, replacement)?;
+  detach(node);
+  Ok(())
+}
+
+/// Puts `wrapper` where `node` is and moves `node` to the end of `wrapper`'s children.
+pub fn wrap(node: &Handle, wrapper: &Handle) -> Result<(), MutationError> {
+  if node.parent().is_none() {
+    return Err(MutationError::NoParent);
+  }
+  check_insertion(wrapper, node)?;
+  replace_with(node, wrapper)?;
+  append(wrapper, node)
+}
+
+/// Replaces `node` with its children, which keep their order.
+pub fn unwrap(node: &Handle) -> Result<(), MutationError> {
+  let (parent, index) = index_in_parent(node).ok_or(MutationError::NoParent)?;
+  let children = node.children.take();
+  for child in children.iter() {
+    child.parent.set(Some(Rc::downgrade(&parent)));
+  }
+  parent.children.borrow_mut().splice(index..index + 1, children);
+  node.parent.set(None);
+  Ok(())
+}
+
+/// Copies a node without a parent. A deep clone copies its children and template contents too.
+pub fn clone_node(node: &Handle, deep: bool) -> Handle {
+  let data = match node.data {
+    NodeData::Document => NodeData::Document,
+    NodeData::Doctype { ref name, ref public_id, ref system_id } => NodeData::Doctype {
+      name: name.clone(),
+      public_id: public_id.clone(),
+      system_id: system_id.clone(),
+    },
+    NodeData::Text { ref contents } => NodeData::Text {
+      contents: RefCell::new(contents.borrow().clone()),
+    },
+    NodeData::Comment { ref contents } => NodeData::Comment {
+      contents: contents.clone(),
+    },
+    NodeData::Element {
+      ref name,
+      ref attrs,
+      ref template_contents,
+      mathml_annotation_xml_integration_point,
+    } => NodeData::Element {
+      name: name.clone(),
+      attrs: RefCell::new(attrs.borrow().clone()),
+      // Like the DOM, even a shallow clone of a `<template>` gets its own (empty) contents.
+      template_contents: RefCell::new(template_contents.borrow().as_ref().map(|contents| clone_node(contents, deep))),
+      mathml_annotation_xml_integration_point,
+    },
+    NodeData::ProcessingInstruction { ref target, ref contents } => NodeData::ProcessingInstruction {
+      target: target.clone(),
+      contents: contents.clone(),
+    },
+  };
+  let clone = Node::new(data);
+  if deep {
+    for child in node.children.borrow().iter() {
+      let child_clone = clone_node(child, true);
+      child_clone.parent.set(Some(Rc::downgrade(&clone)));
+      clone.children.borrow_mut().push(child_clone);
+    }
+  }
+  clone
+}
@@ -0,0 +1,193 @@
+use html5ever::{serialize::{Serializer, TraversalScope}, tendril::StrTendril, Attribute, QualName};
+use std::{cell::Ref, collections::VecDeque, io};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+  Document,
+  Doctype,
+  Text,
+  Comment,
+  Element,
+  ProcessingInstruction,
+}
+
+/// Read access to a tree node, implemented for both `markup5ever_rcdom::Handle` and the arena `Ref<'arena>`
+/// so that queries are written once and work on either tree.
+pub trait DomNode: Clone {
+  fn kind(&self) -> NodeKind;
+
+  fn same_node(&self, other: &Self) -> bool;
+
+  fn parent(&self) -> Option<Self>;
+
+  fn children(&self) -> Vec<Self>;
+
+  /// Qualified name of an element, `None` for every other node kind.
+  fn element_name(&self) -> Option<&QualName>;
+
+  /// Attributes of an element, `None` for every other node kind.
+  fn attrs(&self) -> Option<Ref<'_, Vec<Attribute>>>;
+
+  /// Contents of a text node, `None` for every other node kind.
+  fn text(&self) -> Option<Ref<'_, StrTendril>>;
+
+  /// Contents of a comment or the data of a processing instruction.
+  fn comment(&self) -> Option<&StrTendril>;
+
+  /// Target of a processing instruction, `None` for every other node kind.
+  fn processing_instruction_target(&self) -> Option<&StrTendril>;
+
+  fn template_contents(&self) -> Option<Self>;
+
+  /// `(name, public_id, system_id)` of a doctype, `None` for every other node kind.
+  fn doctype(&self) -> Option<(&StrTendril, &StrTendril, &StrTendril)>;
+
+  fn is_element(&self) -> bool {
+    self.kind() == NodeKind::Element
+  }
+
+  fn previous_sibling(&self) -> Option<Self> {
+    let children = self.parent()?.children();
+    let index = children.iter().position(|child| child.same_node(self))?;
+    index.checked_sub(1).map(|i| children[i].clone())
+  }
+
+  fn next_sibling(&self) -> Option<Self> {
+    let children = self.parent()?.children();
+    let index = children.iter().position(|child| child.same_node(self))?;
+    children.get(index + 1).cloned()
+  }
+
+  fn previous_element_sibling(&self) -> Option<Self> {
+    let mut sibling = self.previous_sibling();
+    while let Some(node) = sibling {
+      if node.is_element() {
+        return Some(node);
+      }
+      sibling = node.previous_sibling();
+    }
+    None
+  }
+
+  fn next_element_sibling(&self) -> Option<Self> {
+    let mut sibling = self.next_sibling();
+    while let Some(node) = sibling {
+      if node.is_element() {
+        return Some(node);
+      }
+      sibling = node.next_sibling();
+    }
+    None
+  }
+
+  /// Position among the parent's children, counting every node kind.
+  fn index_in_parent(&self) -> Option<usize> {
+    self.parent()?.children().iter().position(|child| child.same_node(self))
+  }
+
+  /// Parent, grandparent and so on, nearest first.
+  fn ancestors(&self) -> Vec<Self> {
+    let mut result = vec![];
+    let mut node = self.parent();
+    while let Some(parent) = node {
+      node = parent.parent();
+      result.push(parent);
+    }
+    result
+  }
+
+  /// The topmost ancestor, usually the document. A node without a parent is its own root.
+  fn root(&self) -> Self {
+    let mut root = self.clone();
+    while let Some(parent) = root.parent() {
+      root = parent;
+    }
+    root
+  }
+
+  fn element_children(&self) -> Vec<Self> {
+    self.children().into_iter().filter(|child| child.is_element()).collect()
+  }
+
+  /// Value of the first attribute with this local name, regardless of its namespace.
+  fn attr(&self, local_name: &str) -> Option<String> {
+    self
+      .attrs()?
+      .iter()
+      .find(|attr| &*attr.name.local == local_name)
+      .map(|attr| attr.value.to_string())
+  }
+
+  /// This node and all of its descendants in tree order, not including template contents.
+  fn descendants(&self) -> Vec<Self> {
+    let mut result = vec![];
+    let mut stack = vec![self.clone()];
+    while let Some(node) = stack.pop() {
+      stack.extend(node.children().into_iter().rev());
+      result.push(node);
+    }
+    result
+  }
+}
+
+enum SerializeOp<N> {
+  Open(N),
+  Close(QualName),
+}
+
+/// The children written for the node: the contents of a `<template>` when `template_contents` is set, the node's own
+/// children otherwise.
+fn serialized_children<N: DomNode>(node: &N, template_contents: bool) -> Vec<N> {
+  match node.template_contents() {
+    Some(contents) if template_contents => contents.children(),
+    _ => node.children(),
+  }
+}
+
+/// The serialization walk behind both trees' `Serialize` impls. It writes like `markup5ever_rcdom::SerializableHandle`,
+/// except that a document under `TraversalScope::IncludeNode` is written out as its children instead of panicking, and
+/// that `<template>` contents are written out when `template_contents` is set instead of being dropped.
+pub(crate) fn serialize<N: DomNode, S: Serializer>(
+  node: &N,
+  serializer: &mut S,
+  traversal_scope: TraversalScope,
+  template_contents: bool,
+) -> io::Result<()> {
+  let mut ops: VecDeque<SerializeOp<N>> = match traversal_scope {
+    TraversalScope::IncludeNode => VecDeque::from([SerializeOp::Open(node.clone())]),
+    TraversalScope::ChildrenOnly(_) => serialized_children(node, template_contents).into_iter().map(SerializeOp::Open).collect(),
+  };
+
+  while let Some(op) = ops.pop_front() {
+    let node = match op {
+      SerializeOp::Open(node) => node,
+      SerializeOp::Close(name) => {
+        serializer.end_elem(name)?;
+        continue;
+      },
+    };
+    match node.kind() {
+      NodeKind::Element => {
+        let name = node.element_name().unwrap();
+        serializer.start_elem(name.clone(), node.attrs().unwrap().iter().map(|at| (&at.name, &at.value[..])))?;
+        ops.push_front(SerializeOp::Close(name.clone()));
+        for child in serialized_children(&node, template_contents).into_iter().rev() {
+          ops.push_front(SerializeOp::Open(child));
+        }
+      },
+      NodeKind::Document => {
+        for child in node.children().into_iter().rev() {
+          ops.push_front(SerializeOp::Open(child));
+        }
+      },
+      NodeKind::Doctype => serializer.write_doctype(node.doctype().unwrap().0)?,
+      NodeKind::Text => serializer.write_text(&node.text().unwrap())?,
+      NodeKind::Comment => serializer.write_comment(node.comment().unwrap())?,
+      NodeKind::ProcessingInstruction => {
+        serializer.write_processing_instruction(node.processing_instruction_target().unwrap(), node.comment().unwrap())?
+      },
+    }
+  }
+
+  Ok(())
+}
@@ -0,0 +1,207 @@
+use html5ever::tree_builder::QuirksMode;
+use std::fmt;
+use crate::node::{DomNode, NodeKind};
+
+// The lists from the HTML spec's "initial" insertion mode, lowercased for ASCII-case-insensitive matching.
+const QUIRKY_PUBLIC_PREFIXES: &[&str] = &[
+  "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+  "-//as//dtd html 3.0 aswedit + extensions//",
+  "-//ietf//dtd html 2.0 level 1//",
+  "-//ietf//dtd html 2.0 level 2//",
+  "-//ietf//dtd html 2.0 strict level 1//",
+  "-//ietf//dtd html 2.0 strict level 2//",
+  "-//ietf//dtd html 2.0 strict//",
+  "-//ietf//dtd html 2.0//",
+  "-//ietf//dtd html 2.1e//",
+  "-//ietf//dtd html 3.0//",
+  "-//ietf//dtd html 3.2 final//",
+  "-//ietf//dtd html 3.2//",
+  "-//ietf//dtd html 3//",
+  "-//ietf//dtd html level 0//",
+  "-//ietf//dtd html level 1//",
+  "-//ietf//dtd html level 2//",
+  "-//ietf//dtd html level 3//",
+  "-//ietf//dtd html strict level 0//",
+  "-//ietf//dtd html strict level 1//",
+  "-//ietf//dtd html strict level 2//",
+  "-//ietf//dtd html strict level 3//",
+  "-//ietf//dtd html strict//",
+  "-//ietf//dtd html//",
+  "-//metrius//dtd metrius presentational//",
+  "-//microsoft//dtd internet explorer 2.0 html strict//",
+  "-//microsoft//dtd internet explorer 2.0 html//",
+  "-//microsoft//dtd internet explorer 2.0 tables//",
+  "-//microsoft//dtd internet explorer 3.0 html strict//",
+  "-//microsoft//dtd internet explorer 3.0 html//",
+  "-//microsoft//dtd internet explorer 3.0 tables//",
+  "-//netscape comm. corp.//dtd html//",
+  "-//netscape comm. corp.//dtd strict html//",
+  "-//o'reilly and associates//dtd html 2.0//",
+  "-//o'reilly and associates//dtd html extended 1.0//",
+  "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+  "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+  "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//",
+  "-//spyglass//dtd html 2.0 extended//",
+  "-//sq//dtd html 2.0 hotmetal + extensions//",
+  "-//sun microsystems corp.//dtd hotjava html//",
+  "-//sun microsystems corp.//dtd hotjava strict html//",
+  "-//w3c//dtd html 3 1995-03-24//",
+  "-//w3c//dtd html 3.2 draft//",
+  "-//w3c//dtd html 3.2 final//",
+  "-//w3c//dtd html 3.2//",
+  "-//w3c//dtd html 3.2s draft//",
+  "-//w3c//dtd html 4.0 frameset//",
+  "-//w3c//dtd html 4.0 transitional//",
+  "-//w3c//dtd html experimental 19960712//",
+  "-//w3c//dtd html experimental 970421//",
+  "-//w3c//dtd w3 html//",
+  "-//w3o//dtd w3 html 3.0//",
+  "-//webtechs//dtd mozilla html 2.0//",
+  "-//webtechs//dtd mozilla html//",
+];
+
+const QUIRKY_PUBLIC_MATCHES: &[&str] = &[
+  "-//w3o//dtd w3 html strict 3.0//en//",
+  "-/w3c/dtd html 4.0 transitional/en",
+  "html",
+];
+
+const QUIRKY_SYSTEM_MATCHES: &[&str] = &[
+  "http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd",
+];
+
+const LIMITED_QUIRKY_PUBLIC_PREFIXES: &[&str] = &[
+  "-//w3c//dtd xhtml 1.0 frameset//",
+  "-//w3c//dtd xhtml 1.0 transitional//",
+];
+
+const HTML4_PUBLIC_PREFIXES: &[&str] = &[
+  "-//w3c//dtd html 4.01 frameset//",
+  "-//w3c//dtd html 4.01 transitional//",
+];
+
+/// The doctype that decided the quirks mode, as stored in `NodeData::Doctype`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Doctype {
+  pub name: String,
+  pub public_id: String,
+  pub system_id: String,
+}
+
+/// Which rule of the HTML spec's doctype check picked the mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuirksReason {
+  MissingDoctype,
+  /// The doctype name is not `html`.
+  NotHtml,
+  QuirkyPublicId(&'static str),
+  QuirkyPublicIdPrefix(&'static str),
+  QuirkySystemId(&'static str),
+  /// XHTML 1.0 Frameset or Transitional.
+  LimitedQuirkyPublicIdPrefix(&'static str),
+  /// HTML 4.01 Frameset or Transitional without a system identifier.
+  Html4WithoutSystemId(&'static str),
+  /// HTML 4.01 Frameset or Transitional with a system identifier.
+  Html4WithSystemId(&'static str),
+  /// The doctype looks standard but was malformed (a missing quote or whitespace), which forces quirks mode.
+  Malformed,
+  Standard,
+}
+
+impl fmt::Display for QuirksReason {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      QuirksReason::MissingDoctype => write!(f, "the document has no doctype"),
+      QuirksReason::NotHtml => write!(f, "the doctype name is not \"html\""),
+      QuirksReason::QuirkyPublicId(id) => write!(f, "the public identifier is \"{}\"", id),
+      QuirksReason::QuirkyPublicIdPrefix(prefix) => write!(f, "the public identifier starts with \"{}\"", prefix),
+      QuirksReason::QuirkySystemId(id) => write!(f, "the system identifier is \"{}\"", id),
+      QuirksReason::LimitedQuirkyPublicIdPrefix(prefix) => write!(f, "the public identifier starts with \"{}\"", prefix),
+      QuirksReason::Html4WithoutSystemId(prefix) => write!(f, "the public identifier starts with \"{}\" and there is no system identifier", prefix),
+      QuirksReason::Html4WithSystemId(prefix) => write!(f, "the public identifier starts with \"{}\" and there is a system identifier", prefix),
+      QuirksReason::Malformed => write!(f, "the doctype is malformed"),
+      QuirksReason::Standard => write!(f, "the doctype is a standards-mode doctype"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuirksAudit {
+  pub mode: QuirksMode,
+  pub doctype: Option<Doctype>,
+  pub reason: QuirksReason,
+}
+
+impl QuirksAudit {
+  /// Whether the page renders in quirks or limited-quirks mode.
+  pub fn is_legacy(&self) -> bool {
+    self.mode != QuirksMode::NoQuirks
+  }
+}
+
+fn find_prefix(prefixes: &[&'static str], id: &str) -> Option<&'static str> {
+  prefixes.iter().copied().find(|prefix| id.starts_with(prefix))
+}
+
+/// Applies the spec's doctype check to a well-formed doctype. Empty identifiers count as missing, the same way
+/// `NodeData::Doctype` stores them.
+pub fn classify(name: &str, public_id: &str, system_id: &str) -> (QuirksMode, QuirksReason) {
+  if name != "html" {
+    return (QuirksMode::Quirks, QuirksReason::NotHtml);
+  }
+  let public_id = public_id.to_ascii_lowercase();
+  let system_id = system_id.to_ascii_lowercase();
+
+  if let Some(&id) = QUIRKY_PUBLIC_MATCHES.iter().find(|&&id| id == public_id) {
+    return (QuirksMode::Quirks, QuirksReason::QuirkyPublicId(id));
+  }
+  if let Some(&id) = QUIRKY_SYSTEM_MATCHES.iter().find(|&&id| id == system_id) {
+    return (QuirksMode::Quirks, QuirksReason::QuirkySystemId(id));
+  }
+  if public_id.is_empty() {
+    return (QuirksMode::NoQuirks, QuirksReason::Standard);
+  }
+  if let Some(prefix) = find_prefix(QUIRKY_PUBLIC_PREFIXES, &public_id) {
+    return (QuirksMode::Quirks, QuirksReason::QuirkyPublicIdPrefix(prefix));
+  }
+  if let Some(prefix) = find_prefix(LIMITED_QUIRKY_PUBLIC_PREFIXES, &public_id) {
+    return (QuirksMode::LimitedQuirks, QuirksReason::LimitedQuirkyPublicIdPrefix(prefix));
+  }
+  if let Some(prefix) = find_prefix(HTML4_PUBLIC_PREFIXES, &public_id) {
+    return if system_id.is_empty() {
+      (QuirksMode::Quirks, QuirksReason::Html4WithoutSystemId(prefix))
+    } else {
+      (QuirksMode::LimitedQuirks, QuirksReason::Html4WithSystemId(prefix))
+    };
+  }
+  (QuirksMode::NoQuirks, QuirksReason::Standard)
+}
+
+/// Explains `mode`, the quirks mode the parser reported for `document`, from the document's doctype.
+pub fn audit<N: DomNode>(document: &N, mode: QuirksMode) -> QuirksAudit {
+  let doctype = document
+    .children()
+    .into_iter()
+    .find(|child| child.kind() == NodeKind::Doctype)
+    .and_then(|child| {
+      child.doctype().map(|(name, public_id, system_id)| Doctype {
+        name: name.to_string(),
+        public_id: public_id.to_string(),
+        system_id: system_id.to_string(),
+      })
+    });
+
+  let reason = match doctype {
+    None if mode == QuirksMode::Quirks => QuirksReason::MissingDoctype,
+    None => QuirksReason::Standard,
+    Some(ref doctype) => match classify(&doctype.name, &doctype.public_id, &doctype.system_id) {
+      (expected, reason) if expected == mode => reason,
+      // An empty system identifier is stored like a missing one; the parser's mode tells the two apart.
+      (QuirksMode::Quirks, QuirksReason::Html4WithoutSystemId(prefix)) if mode == QuirksMode::LimitedQuirks => {
+        QuirksReason::Html4WithSystemId(prefix)
+      },
+      _ => QuirksReason::Malformed,
+    },
+  };
+  QuirksAudit { mode, doctype, reason }
+}
@@ -0,0 +1,446 @@
+use html5ever::{local_name, namespace_url, ns, serialize::{serialize, Serialize, SerializeOpts, Serializer, TraversalScope}, tendril::{StrTendril, TendrilSink}, Attribute, LocalName, Namespace, Prefix, QualName};
+use markup5ever_rcdom::{Handle, Node, NodeData, RcDom, SerializableHandle};
+use std::{cell::{self, RefCell}, io, ops::Deref, rc::Rc};
+use crate::{attributes::Attributes, mutation::{self, MutationError}, node::{self, DomNode, NodeKind}};
+
+pub type NodeList = Rc<RefCell<Vec<(usize, Rc<Node>)>>>;
+
+impl DomNode for Handle {
+  fn kind(&self) -> NodeKind {
+    match self.data {
+      NodeData::Document => NodeKind::Document,
+      NodeData::Doctype { .. } => NodeKind::Doctype,
+      NodeData::Text { .. } => NodeKind::Text,
+      NodeData::Comment { .. } => NodeKind::Comment,
+      NodeData::Element { .. } => NodeKind::Element,
+      NodeData::ProcessingInstruction { .. } => NodeKind::ProcessingInstruction,
+    }
+  }
+
+  fn same_node(&self, other: &Self) -> bool {
+    Rc::ptr_eq(self, other)
+  }
+
+  fn parent(&self) -> Option<Self> {
+    let weak = self.parent.take();
+    let result = weak.as_ref().and_then(|w| w.upgrade());
+    self.parent.set(weak);
+    result
+  }
+
+  fn children(&self) -> Vec<Self> {
+    self.children.borrow().clone()
+  }
+
+  fn previous_sibling(&self) -> Option<Self> {
+    let parent = self.parent()?;
+    let children = parent.children.borrow();
+    let index = children.iter().position(|child| Rc::ptr_eq(child, self))?;
+    index.checked_sub(1).map(|i| children[i].clone())
+  }
+
+  fn next_sibling(&self) -> Option<Self> {
+    let parent = self.parent()?;
+    let children = parent.children.borrow();
+    let index = children.iter().position(|child| Rc::ptr_eq(child, self))?;
+    children.get(index + 1).cloned()
+  }
+
+  fn index_in_parent(&self) -> Option<usize> {
+    self.parent()?.children.borrow().iter().position(|child| Rc::ptr_eq(child, self))
+  }
+
+  fn element_name(&self) -> Option<&QualName> {
+    match self.data {
+      NodeData::Element { ref name, .. } => Some(name),
+      _ => None,
+    }
+  }
+
+  fn attrs(&self) -> Option<cell::Ref<'_, Vec<Attribute>>> {
+    match self.data {
+      NodeData::Element { ref attrs, .. } => Some(attrs.borrow()),
+      _ => None,
+    }
+  }
+
+  fn text(&self) -> Option<cell::Ref<'_, StrTendril>> {
+    match self.data {
+      NodeData::Text { ref contents } => Some(contents.borrow()),
+      _ => None,
+    }
+  }
+
+  fn comment(&self) -> Option<&StrTendril> {
+    match self.data {
+      NodeData::Comment { ref contents } => Some(contents),
+      NodeData::ProcessingInstruction { ref contents, .. } => Some(contents),
+      _ => None,
+    }
+  }
+
+  fn processing_instruction_target(&self) -> Option<&StrTendril> {
+    match self.data {
+      NodeData::ProcessingInstruction { ref target, .. } => Some(target),
+      _ => None,
+    }
+  }
+
+  fn template_contents(&self) -> Option<Self> {
+    match self.data {
+      NodeData::Element { ref template_contents, .. } => template_contents.borrow().clone(),
+      _ => None,
+    }
+  }
+
+  fn doctype(&self) -> Option<(&StrTendril, &StrTendril, &StrTendril)> {
+    match self.data {
+      NodeData::Doctype {
+        ref name,
+        ref public_id,
+        ref system_id,
+      } => Some((name, public_id, system_id)),
+      _ => None,
+    }
+  }
+}
+
+impl Attributes for Handle {
+  fn attributes(&self) -> Option<&RefCell<Vec<Attribute>>> {
+    match self.data {
+      NodeData::Element { ref attrs, .. } => Some(attrs),
+      _ => None,
+    }
+  }
+
+  fn is_html_element(&self) -> bool {
+    matches!(self.data, NodeData::Element { ref name, .. } if name.ns == ns!(html))
+  }
+}
+
+/// Adds the attribute unless the node already has one with this name.
+pub fn add_attr(node: &Rc<Node>, attr_name: &str, attr_value: &str) {
+  if !node.has_attr(attr_name) {
+    node.set_attr(attr_name, attr_value);
+  }
+}
+
+/// Changes an existing attribute, or adds it when `if_empty_append` is set.
+pub fn modify_attr(node: &Rc<Node>, attr_name: &str, attr_value: &str, if_empty_append: bool) {
+  if if_empty_append || node.has_attr(attr_name) {
+    node.set_attr(attr_name, attr_value);
+  }
+}
+
+pub fn get_attr_name_and_value(attribute: &Attribute) -> (String, String) {
+  let name = attribute.name.local.to_string();
+  let value = attribute.value.to_string();
+  (name, value)
+}
+
+pub fn convert_node_to_html_string(node: &Rc<Node>) -> String {
+  let mut bytes = vec![];
+  let node_handle: SerializableHandle = node.clone().into();
+  serialize(&mut bytes, &node_handle, SerializeOpts::default()).unwrap();
+  String::from_utf8(bytes).unwrap()
+}
+
+/// Collects the elements under `handle` (itself included) named `search_element_name`, or every element for `*`, in
+/// document order along with their depth.
+pub fn walk(depth: usize, handle: &Handle, vec: NodeList, search_element_name: &str, search_attr_list: &Option<&Vec<(&str, &str)>>) {
+  let node = handle;
+  if let NodeData::Element {
+    ref name,
+    ref attrs,
+    ..
+  } = node.data {
+    let current_element_name = name.local.to_string();
+    let is_name_matched = search_element_name == "*" || current_element_name == search_element_name;
+    let mut is_required_search_attr = false;
+    let mut is_exist_matched_attr = false;
+    if let Some(search_attrs) = search_attr_list {
+      is_required_search_attr = true;
+      for attr in attrs.borrow().iter() {
+        let (attr_name, attr_value) = get_attr_name_and_value(attr);
+        for search_attr in *search_attrs {
+          if search_attr.0 == attr_name && search_attr.1 == attr_value {
+            is_exist_matched_attr = true;
+            break;
+          }
+        }
+      }
+    }
+    if is_required_search_attr {
+      if is_name_matched && is_exist_matched_attr {
+        vec.deref().borrow_mut().push((depth, node.clone()));
+      }
+    } else if is_name_matched {
+      vec.deref().borrow_mut().push((depth, node.clone()));
+    }
+  }
+
+  for child in node.children.borrow().iter() {
+    walk(depth + 1, child, vec.clone(), search_element_name, search_attr_list);
+  }
+}
+
+pub fn node_select(target_node: &Rc<Node>, search_element_name: &str, search_attr_list: &Option<&Vec<(&str, &str)>>) -> NodeList {
+  let vec: NodeList = Rc::new(RefCell::new(vec![]));
+  walk(0, target_node, vec.clone(), search_element_name, search_attr_list);
+  vec
+}
+
+pub fn node_select_one(target_node: &Rc<Node>, search_element_name: &str, search_attr_list: &Option<&Vec<(&str, &str)>>) -> Option<Rc<Node>> {
+  let mut result: Option<Rc<Node>> = None;
+  let vec = node_select(target_node, search_element_name, search_attr_list);
+  if let Some(v) = vec.deref().borrow().first() {
+    result = Some(v.1.clone());
+  }
+  result
+}
+
+pub fn node_delete(target_node: &Rc<Node>) {
+  mutation::detach(target_node);
+}
+
+/// Creates an element in the HTML namespace. Use `create_element` for validated names and other namespaces.
+pub fn node_create(element_name: &str, attr_list: &Option<&Vec<(&str, &str)>>) -> Rc<Node> {
+  let attrs: Vec<(&str, &str)> = attr_list.map(|list| list.to_vec()).unwrap_or_default();
+  new_element(QualName::new(None, ns!(html), LocalName::from(element_name)), &attrs)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameError {
+  pub name: String,
+}
+
+impl std::fmt::Display for NameError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{:?} is not a valid element or attribute name", self.name)
+  }
+}
+
+impl std::error::Error for NameError {}
+
+fn is_name_start_char(c: char) -> bool {
+  matches!(c,
+    'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}'
+    | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}'
+    | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}'
+  )
+}
+
+fn is_name_char(c: char) -> bool {
+  is_name_start_char(c) || matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+fn is_ncname(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars.next().is_some_and(is_name_start_char) && chars.all(is_name_char)
+}
+
+/// Whether `name` is a valid (optionally `prefix:`ed) name under the XML `Name` production that `createElement` and
+/// `setAttribute` check.
+pub fn is_valid_name(name: &str) -> bool {
+  match name.split_once(':') {
+    Some((prefix, local)) => is_ncname(prefix) && is_ncname(local),
+    None => is_ncname(name),
+  }
+}
+
+fn new_element(name: QualName, attrs: &[(&str, &str)]) -> Handle {
+  let is_template = name.ns == ns!(html) && name.local == local_name!("template");
+  let mathml_annotation_xml_integration_point = name.ns == ns!(mathml)
+    && name.local == local_name!("annotation-xml")
+    && attrs.iter().any(|(attr, value)| {
+      attr.eq_ignore_ascii_case("encoding") && (value.eq_ignore_ascii_case("text/html") || value.eq_ignore_ascii_case("application/xhtml+xml"))
+    });
+  let attrs = attrs
+    .iter()
+    .map(|(name, value)| Attribute {
+      name: QualName::new(None, ns!(), LocalName::from(*name)),
+      value: StrTendril::from(*value),
+    })
+    .collect();
+  Node::new(NodeData::Element {
+    name,
+    attrs: RefCell::new(attrs),
+    template_contents: RefCell::new(if is_template { Some(Node::new(NodeData::Document)) } else { None }),
+    mathml_annotation_xml_integration_point,
+  })
+}
+
+fn validate<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<(), NameError> {
+  match names.into_iter().find(|name| !is_valid_name(name)) {
+    Some(name) => Err(NameError { name: name.to_string() }),
+    None => Ok(()),
+  }
+}
+
+/// Creates an HTML element like `document.createElement`: the name is lowercased, and a `<template>` gets its contents
+/// document.
+pub fn create_element(name: &str, attrs: &[(&str, &str)]) -> Result<Handle, NameError> {
+  create_element_ns(ns!(html), name, attrs)
+}
+
+/// Creates an element in `namespace`, keeping the prefix of `qualified_name`. HTML names are lowercased; SVG and MathML
+/// names keep their case (`foreignObject`, `linearGradient`).
+pub fn create_element_ns(namespace: Namespace, qualified_name: &str, attrs: &[(&str, &str)]) -> Result<Handle, NameError> {
+  validate(std::iter::once(qualified_name).chain(attrs.iter().map(|(name, _)| *name)))?;
+  let (prefix, local) = match qualified_name.split_once(':') {
+    Some((prefix, local)) => (Some(Prefix::from(prefix)), local),
+    None => (None, qualified_name),
+  };
+  let local = if namespace == ns!(html) { local.to_ascii_lowercase() } else { local.to_string() };
+  Ok(new_element(QualName::new(prefix, namespace, LocalName::from(local)), attrs))
+}
+
+/// The namespace the parser would give a `name` element inserted into `parent`, which can be a node of either tree or an
+/// element of the rewriter's stream. `<svg>` and `<math>` start their own namespace; other names inherit SVG or MathML
+/// from the parent except inside integration points: `foreignObject`, `desc` and `title` in SVG, and in MathML `mi`,
+/// `mo`, `mn`, `ms` and `mtext` (except for `mglyph` and `malignmark` children) and an `annotation-xml` whose encoding
+/// is HTML, which also takes an `<svg>` child.
+pub fn namespace_for_child<N: DomNode>(parent: &N, name: &str) -> Namespace {
+  let is = |local: &str| name.eq_ignore_ascii_case(local);
+  if let Some(parent_name) = parent.element_name() {
+    let parent_local = &*parent_name.local;
+    let integration_point = if parent_name.ns == ns!(svg) {
+      ["foreignObject", "desc", "title"].iter().any(|local| parent_local.eq_ignore_ascii_case(local))
+    } else if parent_name.ns == ns!(mathml) {
+      match parent_local {
+        "mi" | "mo" | "mn" | "ms" | "mtext" => !is("mglyph") && !is("malignmark"),
+        "annotation-xml" => {
+          is("svg")
+            || parent
+              .attr("encoding")
+              .is_some_and(|encoding| encoding.eq_ignore_ascii_case("text/html") || encoding.eq_ignore_ascii_case("application/xhtml+xml"))
+        },
+        _ => false,
+      }
+    } else {
+      true
+    };
+    if !integration_point {
+      return parent_name.ns.clone();
+    }
+  }
+  if is("svg") {
+    ns!(svg)
+  } else if is("math") {
+    ns!(mathml)
+  } else {
+    ns!(html)
+  }
+}
+
+/// Creates an element in the namespace `namespace_for_child` picks for `parent`. The element is not inserted.
+pub fn create_element_for(parent: &Handle, name: &str, attrs: &[(&str, &str)]) -> Result<Handle, NameError> {
+  create_element_ns(namespace_for_child(parent, name), name, attrs)
+}
+
+/// Appends a node, such as one from `node_create` or `parse_fragment`, as the last child of `parent`.
+///
+/// Panics if `parent` cannot have children or is `child` itself or one of its descendants; see `mutation::append`.
+pub fn node_append(parent: &Rc<Node>, child: &Rc<Node>) {
+  mutation::append(parent, child).unwrap();
+}
+
+/// Parses `html` as the content of `context_element` (`<body>` when it is not an element) and returns the parsed nodes,
+/// detached and ready to be appended elsewhere.
+pub fn parse_fragment(context_element: &Handle, html: &str) -> Vec<Handle> {
+  let (name, attrs) = match context_element.data {
+    NodeData::Element { ref name, ref attrs, .. } => (name.clone(), attrs.borrow().clone()),
+    _ => (QualName::new(None, ns!(html), local_name!("body")), vec![]),
+  };
+  let dom = html5ever::parse_fragment(RcDom::default(), Default::default(), name, attrs).one(html);
+  // The fragment parser puts everything inside an `<html>` element under the document.
+  let root = dom.document.children.borrow()[0].clone();
+  let nodes = root.children.take();
+  for node in nodes.iter() {
+    node.parent.set(None);
+  }
+  nodes
+}
+
+pub fn node_parent(target_node: &Rc<Node>) -> Option<Rc<Node>> {
+  target_node.parent()
+}
+
+/// The node whose children are serialized for `handle`: the template contents for `<template>`, the node itself otherwise.
+fn serialized_children_parent(handle: &Handle) -> Handle {
+  match handle.data {
+    NodeData::Element { ref template_contents, .. } => template_contents.borrow().clone().unwrap_or_else(|| handle.clone()),
+    _ => handle.clone(),
+  }
+}
+
+/// Serializes like `SerializableHandle`, except that `<template>` contents are written out like a browser's `innerHTML`
+/// and `outerHTML` instead of being dropped.
+struct HtmlHandle(Handle);
+
+impl Serialize for HtmlHandle {
+  fn serialize<S>(&self, serializer: &mut S, traversal_scope: TraversalScope) -> io::Result<()>
+  where
+    S: Serializer
+  {
+    node::serialize(&self.0, serializer, traversal_scope, true)
+  }
+}
+
+fn serialize_handle(handle: &Handle, traversal_scope: TraversalScope) -> String {
+  let mut bytes = vec![];
+  let opts = SerializeOpts {
+    traversal_scope,
+    ..Default::default()
+  };
+  serialize(&mut bytes, &HtmlHandle(handle.clone()), opts).unwrap();
+  String::from_utf8(bytes).unwrap()
+}
+
+/// The markup of the node's children, or of its contents for a `<template>`.
+pub fn inner_html(handle: &Handle) -> String {
+  serialize_handle(handle, TraversalScope::ChildrenOnly(None))
+}
+
+/// The markup of the node itself. A document serializes as its children.
+pub fn outer_html(handle: &Handle) -> String {
+  serialize_handle(handle, TraversalScope::IncludeNode)
+}
+
+/// Replaces the node's children (a `<template>`'s contents) with `html` parsed using the node as context.
+///
+/// Fails with `MutationError::NotAContainer`, leaving the node alone, unless it is a document or an element.
+pub fn set_inner_html(handle: &Handle, html: &str) -> Result<(), MutationError> {
+  if !matches!(handle.data, NodeData::Document | NodeData::Element { .. }) {
+    return Err(MutationError::NotAContainer);
+  }
+  let nodes = parse_fragment(handle, html);
+  let target = serialized_children_parent(handle);
+  for child in target.children.take() {
+    child.parent.set(None);
+  }
+  for node in nodes.iter() {
+    mutation::append(&target, node)?;
+  }
+  Ok(())
+}
+
+/// Replaces the node with `html` parsed using its parent as context.
+///
+/// Fails with `MutationError::NoParent`, leaving the node alone, when it has no parent element: a browser either
+/// ignores the change (detached node) or throws (child of the document).
+pub fn set_outer_html(handle: &Handle, html: &str) -> Result<(), MutationError> {
+  let parent = match handle.parent() {
+    Some(parent) if parent.is_element() => parent,
+    _ => return Err(MutationError::NoParent),
+  };
+  let nodes = parse_fragment(&parent, html);
+  let mut children = parent.children.borrow_mut();
+  let index = children.iter().position(|child| Rc::ptr_eq(child, handle)).ok_or(MutationError::NoParent)?;
+  for node in nodes.iter() {
+    node.parent.set(Some(Rc::downgrade(&parent)));
+  }
+  children.splice(index..index + 1, nodes);
+  handle.parent.set(None);
+  Ok(())
+}
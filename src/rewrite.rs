@@ -0,0 +1,872 @@
+use encoding_rs::{Decoder, UTF_8};
+use html5ever::{namespace_url, ns, tendril::StrTendril, tokenizer::{BufferQueue, Doctype, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts}, Attribute, QualName};
+use html5ever::tokenizer::states::RawKind;
+use std::{cell::{self, RefCell}, collections::BTreeSet, io::{self, Write}, rc::Rc};
+use crate::{attributes::Attributes, format::{escape_attr, escape_text_char, RAW_TEXT_ELEMENTS, VOID_ELEMENTS}, node::{DomNode, NodeKind}, rcdom::namespace_for_child, select::{Selector, SelectorError}, template::{escape_for, replace_placeholders, RenderReport, TemplateContext, TemplateValue, TextContext}};
+
+/// Text runs longer than this are handed to text handlers in pieces, so memory stays bounded.
+const TEXT_CHUNK_LENGTH: usize = 16 * 1024;
+
+/// Elements a start tag implicitly closes an open `<p>` for.
+const CLOSES_P: &[&str] = &[
+  "address", "article", "aside", "blockquote", "center", "dd", "details", "dialog", "dir", "div", "dl", "dt", "fieldset", "figcaption",
+  "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup", "hr", "li", "listing", "main", "menu", "nav", "ol",
+  "p", "plaintext", "pre", "search", "section", "summary", "table", "ul", "xmp",
+];
+
+/// Elements an implied end tag does not look past.
+const SCOPE_BOUNDARIES: &[&str] = &["applet", "button", "caption", "html", "marquee", "object", "table", "td", "template", "th"];
+
+/// Elements that may appear in `<head>`; any other start tag closes it.
+const HEAD_ELEMENTS: &[&str] = &["base", "basefont", "bgsound", "link", "meta", "noframes", "noscript", "script", "style", "template", "title"];
+
+/// HTML start tags that end SVG or MathML content.
+const FOREIGN_BREAKOUT: &[&str] = &[
+  "b", "big", "blockquote", "body", "br", "center", "code", "dd", "div", "dl", "dt", "em", "embed", "h1", "h2", "h3", "h4", "h5", "h6",
+  "head", "hr", "i", "img", "li", "listing", "menu", "meta", "nobr", "ol", "p", "pre", "ruby", "s", "small", "span", "strong", "strike",
+  "sub", "sup", "table", "tt", "u", "ul", "var",
+];
+
+#[derive(Clone, Default)]
+struct Edits {
+  before: String,
+  prepend: String,
+  append: String,
+  after: String,
+  remove_tags: bool,
+  remove_content: bool,
+}
+
+struct ElementData {
+  // `None` for the document the open elements hang off.
+  name: Option<QualName>,
+  attrs: RefCell<Vec<Attribute>>,
+  parent: Option<StreamElement>,
+  edits: RefCell<Edits>,
+}
+
+/// An element of the stream while it is open. Selectors and handlers see its name, attributes and ancestors; its
+/// siblings and children are never kept.
+///
+/// The edit methods only take effect from an element handler, which runs before the start tag is written.
+#[derive(Clone)]
+pub struct StreamElement(Rc<ElementData>);
+
+impl StreamElement {
+  fn new(name: Option<QualName>, attrs: Vec<Attribute>, parent: Option<StreamElement>) -> Self {
+    StreamElement(Rc::new(ElementData {
+      name,
+      attrs: RefCell::new(attrs),
+      parent,
+      edits: RefCell::new(Edits::default()),
+    }))
+  }
+
+  /// The local name, empty for the document.
+  pub fn name(&self) -> &str {
+    self.0.name.as_ref().map(|name| &*name.local).unwrap_or_default()
+  }
+
+  fn is_html(&self, names: &[&str]) -> bool {
+    self.0.name.as_ref().is_some_and(|name| name.ns == ns!(html) && names.contains(&&*name.local))
+  }
+
+  fn is_foreign(&self) -> bool {
+    self.0.name.as_ref().is_some_and(|name| name.ns != ns!(html))
+  }
+
+  /// Inserts `html` before the start tag.
+  pub fn before(&self, html: &str) {
+    self.0.edits.borrow_mut().before.push_str(html);
+  }
+
+  /// Inserts `html` right after the start tag.
+  pub fn prepend(&self, html: &str) {
+    self.0.edits.borrow_mut().prepend.push_str(html);
+  }
+
+  /// Inserts `html` right before the end tag.
+  pub fn append(&self, html: &str) {
+    self.0.edits.borrow_mut().append.push_str(html);
+  }
+
+  /// Inserts `html` after the end tag.
+  pub fn after(&self, html: &str) {
+    self.0.edits.borrow_mut().after.push_str(html);
+  }
+
+  /// Drops the element with everything inside it.
+  pub fn remove(&self) {
+    let mut edits = self.0.edits.borrow_mut();
+    edits.remove_tags = true;
+    edits.remove_content = true;
+  }
+
+  /// Drops the start and end tags, keeping what is between them.
+  pub fn unwrap(&self) {
+    self.0.edits.borrow_mut().remove_tags = true;
+  }
+
+  /// Puts `html` in place of the element and everything inside it.
+  pub fn replace(&self, html: &str) {
+    self.before(html);
+    self.remove();
+  }
+
+  /// Puts `html` in place of everything inside the element.
+  pub fn set_inner_html(&self, html: &str) {
+    let mut edits = self.0.edits.borrow_mut();
+    edits.prepend = html.to_string();
+    edits.append.clear();
+    edits.remove_content = true;
+  }
+
+  /// Whether `remove` or `replace` was called.
+  pub fn is_removed(&self) -> bool {
+    let edits = self.0.edits.borrow();
+    edits.remove_tags && edits.remove_content
+  }
+}
+
+impl DomNode for StreamElement {
+  fn kind(&self) -> NodeKind {
+    match self.0.name {
+      Some(_) => NodeKind::Element,
+      None => NodeKind::Document,
+    }
+  }
+
+  fn same_node(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.0, &other.0)
+  }
+
+  fn parent(&self) -> Option<Self> {
+    self.0.parent.clone()
+  }
+
+  fn children(&self) -> Vec<Self> {
+    vec![]
+  }
+
+  fn element_name(&self) -> Option<&QualName> {
+    self.0.name.as_ref()
+  }
+
+  fn attrs(&self) -> Option<cell::Ref<'_, Vec<Attribute>>> {
+    self.0.name.as_ref().map(|_| self.0.attrs.borrow())
+  }
+
+  fn text(&self) -> Option<cell::Ref<'_, StrTendril>> {
+    None
+  }
+
+  fn comment(&self) -> Option<&StrTendril> {
+    None
+  }
+
+  fn processing_instruction_target(&self) -> Option<&StrTendril> {
+    None
+  }
+
+  fn template_contents(&self) -> Option<Self> {
+    None
+  }
+
+  fn doctype(&self) -> Option<(&StrTendril, &StrTendril, &StrTendril)> {
+    None
+  }
+}
+
+impl Attributes for StreamElement {
+  fn attributes(&self) -> Option<&RefCell<Vec<Attribute>>> {
+    self.0.name.as_ref().map(|_| &self.0.attrs)
+  }
+
+  fn is_html_element(&self) -> bool {
+    self.0.name.as_ref().is_some_and(|name| name.ns == ns!(html))
+  }
+}
+
+/// The end of an element, explicit or implied by what follows it.
+pub struct EndTag {
+  element: StreamElement,
+  implied: bool,
+  before: String,
+  after: String,
+  removed: bool,
+}
+
+impl EndTag {
+  /// The element being closed, with its attributes as they were written.
+  pub fn element(&self) -> &StreamElement {
+    &self.element
+  }
+
+  pub fn name(&self) -> &str {
+    self.element.name()
+  }
+
+  /// Whether the input has no end tag here and the element is closed by the next tag or the end of the input.
+  pub fn is_implied(&self) -> bool {
+    self.implied
+  }
+
+  pub fn before(&mut self, html: &str) {
+    self.before.push_str(html);
+  }
+
+  pub fn after(&mut self, html: &str) {
+    self.after.push_str(html);
+  }
+
+  /// Leaves the end tag out of the output.
+  pub fn remove(&mut self) {
+    self.removed = true;
+  }
+}
+
+/// A piece of a text run, with character references already decoded.
+pub struct TextChunk {
+  text: String,
+  parent: StreamElement,
+  html: Option<String>,
+  last_in_run: bool,
+}
+
+impl TextChunk {
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  /// The element the text is in.
+  pub fn parent(&self) -> &StreamElement {
+    &self.parent
+  }
+
+  /// Runs longer than 16 KiB arrive in several chunks; this is set on the one that ends the run.
+  pub fn is_last_in_run(&self) -> bool {
+    self.last_in_run
+  }
+
+  /// Replaces the text, which is escaped on output.
+  pub fn set_text(&mut self, text: &str) {
+    self.text = text.to_string();
+    self.html = None;
+  }
+
+  /// Replaces the text with markup written as is.
+  pub fn set_html(&mut self, html: &str) {
+    self.html = Some(html.to_string());
+  }
+
+  pub fn remove(&mut self) {
+    self.set_html("");
+  }
+}
+
+pub struct Comment {
+  text: String,
+  parent: StreamElement,
+  removed: bool,
+}
+
+impl Comment {
+  pub fn text(&self) -> &str {
+    &self.text
+  }
+
+  pub fn parent(&self) -> &StreamElement {
+    &self.parent
+  }
+
+  pub fn set_text(&mut self, text: &str) {
+    self.text = text.to_string();
+  }
+
+  pub fn remove(&mut self) {
+    self.removed = true;
+  }
+}
+
+type ElementHandler<'h> = Box<dyn FnMut(&StreamElement) + 'h>;
+type Handler<'h, T> = Box<dyn FnMut(&mut T) + 'h>;
+
+#[derive(Default)]
+struct Handlers<'h> {
+  elements: Vec<(Selector, ElementHandler<'h>)>,
+  end_tags: Vec<(Selector, Handler<'h, EndTag>)>,
+  attributes: Vec<(Selector, String, Handler<'h, String>)>,
+  texts: Vec<(Selector, Handler<'h, TextChunk>)>,
+  comments: Vec<(Selector, Handler<'h, Comment>)>,
+}
+
+/// How far a long text run can be handed on without cutting a `[##_name_##]` marker, or its start, in two.
+fn safe_split(text: &str) -> usize {
+  let start = match text.rfind('[') {
+    Some(start) => start,
+    None => return text.len(),
+  };
+  let tail = &text[start..];
+  let open_marker = if tail.len() < 4 {
+    "[##_".starts_with(tail)
+  } else {
+    tail.strip_prefix("[##_").is_some_and(|name| {
+      let name = name.strip_suffix("_##").or_else(|| name.strip_suffix("_#")).unwrap_or(name);
+      name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+    })
+  };
+  if open_marker { start } else { text.len() }
+}
+
+fn write_start_tag(element: &StreamElement, self_closing: bool, out: &mut String) {
+  out.push('<');
+  out.push_str(element.name());
+  for attr in element.0.attrs.borrow().iter() {
+    out.push(' ');
+    out.push_str(&attr.name.local);
+    out.push_str("=\"");
+    escape_attr(&attr.value, out);
+    out.push('"');
+  }
+  if self_closing && element.is_foreign() {
+    out.push_str(" /");
+  }
+  out.push('>');
+}
+
+fn write_doctype(doctype: &Doctype, out: &mut String) {
+  out.push_str("<!DOCTYPE");
+  if let Some(ref name) = doctype.name {
+    out.push(' ');
+    out.push_str(name);
+  }
+  match (&doctype.public_id, &doctype.system_id) {
+    (Some(public_id), system_id) => {
+      out.push_str(&format!(" PUBLIC \"{}\"", public_id));
+      if let Some(system_id) = system_id {
+        out.push_str(&format!(" \"{}\"", system_id));
+      }
+    },
+    (None, Some(system_id)) => out.push_str(&format!(" SYSTEM \"{}\"", system_id)),
+    (None, None) => {},
+  }
+  out.push('>');
+}
+
+struct RewriteSink<'h, W: Write> {
+  output: W,
+  error: Option<io::Error>,
+  handlers: Handlers<'h>,
+  placeholders: Option<&'h TemplateContext>,
+  used: BTreeSet<String>,
+  unknown: Vec<String>,
+  document: StreamElement,
+  open: Vec<StreamElement>,
+  // How many open elements have had their contents removed; nothing is written while it is above 0.
+  hidden_depth: usize,
+  text: String,
+}
+
+impl<'h, W: Write> RewriteSink<'h, W> {
+  fn write(&mut self, text: &str) {
+    if self.error.is_none() && !text.is_empty() {
+      if let Err(error) = self.output.write_all(text.as_bytes()) {
+        self.error = Some(error);
+      }
+    }
+  }
+
+  fn current(&self) -> StreamElement {
+    self.open.last().unwrap_or(&self.document).clone()
+  }
+
+  fn substitute(&mut self, text: &str, context: TextContext) -> String {
+    let placeholders = match self.placeholders {
+      Some(placeholders) => placeholders,
+      None => return text.to_string(),
+    };
+    replace_placeholders(text, |name| match placeholders.get(name) {
+      Some(TemplateValue::Text(value)) => {
+        self.used.insert(name.to_string());
+        Some(escape_for(context, value))
+      },
+      _ => {
+        if !self.unknown.iter().any(|unknown| unknown == name) {
+          self.unknown.push(name.to_string());
+        }
+        None
+      },
+    })
+  }
+
+  /// Hands the buffered text to the text handlers and writes it, all of it at the end of a run and otherwise as much
+  /// as can go without splitting a placeholder.
+  fn flush_text(&mut self, end_of_run: bool) {
+    let length = if end_of_run { self.text.len() } else { safe_split(&self.text) };
+    if length == 0 {
+      return;
+    }
+    let text: String = self.text.drain(..length).collect();
+    if self.hidden_depth > 0 {
+      return;
+    }
+    let parent = self.current();
+    let context = if parent.is_html(RAW_TEXT_ELEMENTS) { TextContext::for_html_element(parent.name()) } else { TextContext::Normal };
+    let raw = context != TextContext::Normal;
+    let text = self.substitute(&text, context);
+    let mut chunk = TextChunk {
+      text,
+      parent,
+      html: None,
+      last_in_run: end_of_run,
+    };
+    for (selector, handler) in self.handlers.texts.iter_mut() {
+      if selector.matches(&chunk.parent) {
+        handler(&mut chunk);
+      }
+    }
+    let out = match chunk.html {
+      Some(html) => html,
+      None if raw => chunk.text,
+      None => {
+        let mut out = String::with_capacity(chunk.text.len());
+        chunk.text.chars().for_each(|c| escape_text_char(c, &mut out));
+        out
+      },
+    };
+    self.write(&out);
+  }
+
+  /// Pops open elements down to and including the one at `index`, closing them without end tags.
+  fn pop_to(&mut self, index: usize) {
+    while self.open.len() > index {
+      let element = self.open.pop().unwrap();
+      self.close(element, false);
+    }
+  }
+
+  /// Closes the nearest open HTML element named in `names`, and everything opened after it, unless a scope boundary,
+  /// one of `boundaries` or foreign content comes first.
+  fn close_nearest(&mut self, names: &[&str], boundaries: &[&str]) {
+    for index in (0..self.open.len()).rev() {
+      let element = &self.open[index];
+      if element.is_html(names) {
+        self.pop_to(index);
+        return;
+      }
+      if element.is_foreign() || element.is_html(SCOPE_BOUNDARIES) || element.is_html(boundaries) {
+        return;
+      }
+    }
+  }
+
+  /// The end tags a start tag named `local` implies, for the common cases of omitted end tags.
+  fn close_implied(&mut self, local: &str) {
+    // Inside an integration point such as `<desc>` HTML start tags are children, not a way out.
+    let current = self.current();
+    if current.is_foreign() && namespace_for_child(&current, local) != ns!(html) && FOREIGN_BREAKOUT.contains(&local) {
+      while let Some(index) = self.open.iter().rposition(|element| element.is_foreign()) {
+        self.pop_to(index);
+      }
+    }
+    if self.current().is_foreign() {
+      return;
+    }
+    if self.current().is_html(&["head"]) && !HEAD_ELEMENTS.contains(&local) {
+      self.pop_to(self.open.len() - 1);
+    }
+    match local {
+      "li" => self.close_nearest(&["li"], &["ol", "ul"]),
+      "dd" | "dt" => self.close_nearest(&["dd", "dt"], &["dl"]),
+      "tr" => self.close_nearest(&["tr"], &["tbody", "thead", "tfoot"]),
+      "td" | "th" => self.close_nearest(&["td", "th"], &["tr"]),
+      "tbody" | "thead" | "tfoot" => self.close_nearest(&["tbody", "thead", "tfoot"], &[]),
+      "a" => self.close_nearest(&["a"], &[]),
+      "option" | "optgroup" => {
+        if self.current().is_html(&["option"]) {
+          self.pop_to(self.open.len() - 1);
+        }
+        if local == "optgroup" && self.current().is_html(&["optgroup"]) {
+          self.pop_to(self.open.len() - 1);
+        }
+      },
+      _ => {},
+    }
+    if CLOSES_P.contains(&local) {
+      self.close_nearest(&["p"], &[]);
+    }
+    if matches!(local, "h1" | "h2" | "h3" | "h4" | "h5" | "h6") && self.current().is_html(&["h1", "h2", "h3", "h4", "h5", "h6"]) {
+      self.pop_to(self.open.len() - 1);
+    }
+  }
+
+  fn start_tag(&mut self, tag: Tag) -> TokenSinkResult<()> {
+    self.close_implied(&tag.name);
+    let parent = self.current();
+    let namespace = namespace_for_child(&parent, &tag.name);
+    let element = StreamElement::new(Some(QualName::new(None, namespace, tag.name)), tag.attrs, Some(parent));
+    let is_void = if element.is_foreign() { tag.self_closing } else { element.is_html(VOID_ELEMENTS) };
+
+    if self.hidden_depth == 0 {
+      let mut attrs = element.0.attrs.take();
+      for attr in attrs.iter_mut() {
+        let value = self.substitute(&attr.value, TextContext::Normal);
+        attr.value = value.into();
+      }
+      element.0.attrs.replace(attrs);
+      for (selector, name, handler) in self.handlers.attributes.iter_mut() {
+        if !selector.matches(&element) {
+          continue;
+        }
+        if let Some(mut value) = element.get_attr(name) {
+          handler(&mut value);
+          element.set_attr(name, &value);
+        }
+      }
+      for (selector, handler) in self.handlers.elements.iter_mut() {
+        if selector.matches(&element) {
+          handler(&element);
+        }
+      }
+
+      let edits = element.0.edits.borrow().clone();
+      let mut out = edits.before;
+      if !edits.remove_tags {
+        write_start_tag(&element, tag.self_closing, &mut out);
+      }
+      if !(edits.remove_tags && edits.remove_content) {
+        out.push_str(&edits.prepend);
+      }
+      if is_void {
+        out.push_str(&edits.after);
+      }
+      self.write(&out);
+    }
+
+    if is_void {
+      return TokenSinkResult::Continue;
+    }
+    if element.0.edits.borrow().remove_content {
+      self.hidden_depth += 1;
+    }
+    self.open.push(element.clone());
+    if element.is_html(&["script"]) {
+      TokenSinkResult::RawData(RawKind::ScriptData)
+    } else if element.is_html(&["title", "textarea"]) {
+      TokenSinkResult::RawData(RawKind::Rcdata)
+    } else if element.is_html(&["plaintext"]) {
+      TokenSinkResult::Plaintext
+    } else if element.is_html(RAW_TEXT_ELEMENTS) {
+      TokenSinkResult::RawData(RawKind::Rawtext)
+    } else {
+      TokenSinkResult::Continue
+    }
+  }
+
+  /// The open element an end tag named `local` closes, the way the tree builder finds it: in foreign content the nearest
+  /// element with that name whatever its namespace, otherwise the nearest HTML element with that name unless a scope
+  /// boundary or foreign content comes first. Table end tags look past the cells and rows they close.
+  fn end_tag_target(&self, local: &str) -> Option<usize> {
+    let boundaries: &[&str] = if matches!(local, "table" | "tbody" | "tfoot" | "thead" | "tr") {
+      &["html", "table", "template"]
+    } else {
+      SCOPE_BOUNDARIES
+    };
+    let mut in_foreign_content = self.current().is_foreign();
+    for index in (0..self.open.len()).rev() {
+      let element = &self.open[index];
+      if element.is_foreign() {
+        if !in_foreign_content {
+          return None;
+        }
+        if element.name().eq_ignore_ascii_case(local) {
+          return Some(index);
+        }
+        continue;
+      }
+      in_foreign_content = false;
+      if element.is_html(&[local]) {
+        return Some(index);
+      }
+      if element.is_html(boundaries) {
+        return None;
+      }
+    }
+    None
+  }
+
+  fn end_tag(&mut self, tag: Tag) {
+    match self.end_tag_target(&tag.name) {
+      Some(index) => {
+        self.pop_to(index + 1);
+        let element = self.open.pop().unwrap();
+        self.close(element, true);
+      },
+      // A stray end tag the tree builder would ignore or repair; it is passed through as written.
+      None if self.hidden_depth == 0 => self.write(&format!("</{}>", tag.name)),
+      None => {},
+    }
+  }
+
+  fn close(&mut self, element: StreamElement, explicit: bool) {
+    let edits = element.0.edits.borrow().clone();
+    if edits.remove_content {
+      self.hidden_depth -= 1;
+    }
+    if self.hidden_depth > 0 {
+      return;
+    }
+    if edits.remove_tags && edits.remove_content {
+      self.write(&edits.after);
+      return;
+    }
+
+    let mut end = EndTag {
+      element,
+      implied: !explicit,
+      before: edits.append,
+      after: String::new(),
+      removed: edits.remove_tags,
+    };
+    for (selector, handler) in self.handlers.end_tags.iter_mut() {
+      if selector.matches(&end.element) {
+        handler(&mut end);
+      }
+    }
+    let mut out = end.before;
+    if explicit && !end.removed {
+      out.push_str(&format!("</{}>", end.element.name()));
+    }
+    out.push_str(&end.after);
+    out.push_str(&edits.after);
+    self.write(&out);
+  }
+
+  fn comment(&mut self, text: StrTendril) {
+    if self.hidden_depth > 0 {
+      return;
+    }
+    let mut comment = Comment {
+      text: text.to_string(),
+      parent: self.current(),
+      removed: false,
+    };
+    for (selector, handler) in self.handlers.comments.iter_mut() {
+      if selector.matches(&comment.parent) {
+        handler(&mut comment);
+      }
+    }
+    if !comment.removed {
+      self.write(&format!("<!--{}-->", comment.text));
+    }
+  }
+}
+
+impl<'h, W: Write> TokenSink for RewriteSink<'h, W> {
+  type Handle = ();
+
+  fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+    match token {
+      Token::CharacterTokens(text) => {
+        self.text.push_str(&text);
+        if self.text.len() >= TEXT_CHUNK_LENGTH {
+          self.flush_text(false);
+        }
+        return TokenSinkResult::Continue;
+      },
+      // The tree builder drops these outside of raw text, where the tokenizer has already replaced them.
+      Token::NullCharacterToken | Token::ParseError(_) => return TokenSinkResult::Continue,
+      _ => self.flush_text(true),
+    }
+    match token {
+      Token::TagToken(tag) if tag.kind == TagKind::StartTag => return self.start_tag(tag),
+      Token::TagToken(tag) => self.end_tag(tag),
+      Token::CommentToken(text) => self.comment(text),
+      Token::DoctypeToken(doctype) if self.hidden_depth == 0 => {
+        let mut out = String::new();
+        write_doctype(&doctype, &mut out);
+        self.write(&out);
+      },
+      Token::EOFToken => self.pop_to(0),
+      _ => {},
+    }
+    TokenSinkResult::Continue
+  }
+
+  fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+    self.current().is_foreign()
+  }
+}
+
+/// Rewrites HTML as it streams through, without building a tree: the input is tokenized, handlers registered with
+/// selectors edit elements, attributes, text and comments, and the result is written out token by token. Memory stays
+/// bounded by the nesting depth and 16 KiB of text, whatever the size of the input.
+///
+/// Without a tree builder the open elements are tracked from the tags, with the common omitted end tags (`<p>`,
+/// `<li>`, table parts, `<head>`) implied, so selectors match what the parser would build for reasonable markup. They
+/// can only look at an element and its ancestors. Tokens are written back normalized, as the serializer would: names
+/// lowercased, attributes double-quoted, character references decoded and re-escaped where needed.
+pub struct Rewriter<'h, W: Write> {
+  tokenizer: Tokenizer<RewriteSink<'h, W>>,
+  input: BufferQueue,
+  decoder: Decoder,
+}
+
+fn streamable(css: &str) -> Result<Selector, SelectorError> {
+  let selector = Selector::parse(css)?;
+  if !selector.uses_only_ancestors() {
+    return Err(SelectorError {
+      message: "only selectors on an element and its ancestors can be matched while streaming".to_string(),
+      position: 0,
+    });
+  }
+  Ok(selector)
+}
+
+impl<'h, W: Write> Rewriter<'h, W> {
+  pub fn new(output: W) -> Self {
+    let sink = RewriteSink {
+      output,
+      error: None,
+      handlers: Handlers::default(),
+      placeholders: None,
+      used: BTreeSet::new(),
+      unknown: vec![],
+      document: StreamElement::new(None, vec![], None),
+      open: vec![],
+      hidden_depth: 0,
+      text: String::new(),
+    };
+    Rewriter {
+      tokenizer: Tokenizer::new(sink, TokenizerOpts::default()),
+      input: BufferQueue::new(),
+      decoder: UTF_8.new_decoder_with_bom_removal(),
+    }
+  }
+
+  /// Runs `handler` on every element matching `css` before its start tag is written.
+  pub fn on_element<F>(&mut self, css: &str, handler: F) -> Result<&mut Self, SelectorError>
+  where
+    F: FnMut(&StreamElement) + 'h,
+  {
+    let selector = streamable(css)?;
+    self.tokenizer.sink.handlers.elements.push((selector, Box::new(handler)));
+    Ok(self)
+  }
+
+  /// Runs `handler` when an element matching `css` ends, whether its end tag is written out or implied.
+  pub fn on_end_tag<F>(&mut self, css: &str, handler: F) -> Result<&mut Self, SelectorError>
+  where
+    F: FnMut(&mut EndTag) + 'h,
+  {
+    let selector = streamable(css)?;
+    self.tokenizer.sink.handlers.end_tags.push((selector, Box::new(handler)));
+    Ok(self)
+  }
+
+  /// Lets `handler` change the value of the `attribute` of every element matching `css` that has one.
+  pub fn on_attribute<F>(&mut self, css: &str, attribute: &str, handler: F) -> Result<&mut Self, SelectorError>
+  where
+    F: FnMut(&mut String) + 'h,
+  {
+    let selector = streamable(css)?;
+    self.tokenizer.sink.handlers.attributes.push((selector, attribute.to_string(), Box::new(handler)));
+    Ok(self)
+  }
+
+  /// Runs `handler` on the text directly inside elements matching `css`.
+  pub fn on_text<F>(&mut self, css: &str, handler: F) -> Result<&mut Self, SelectorError>
+  where
+    F: FnMut(&mut TextChunk) + 'h,
+  {
+    let selector = streamable(css)?;
+    self.tokenizer.sink.handlers.texts.push((selector, Box::new(handler)));
+    Ok(self)
+  }
+
+  /// Runs `handler` on the comments directly inside elements matching `css`.
+  pub fn on_comment<F>(&mut self, css: &str, handler: F) -> Result<&mut Self, SelectorError>
+  where
+    F: FnMut(&mut Comment) + 'h,
+  {
+    let selector = streamable(css)?;
+    self.tokenizer.sink.handlers.comments.push((selector, Box::new(handler)));
+    Ok(self)
+  }
+
+  /// Substitutes `[##_name_##]` placeholders in text and attribute values before any handler sees them, like
+  /// `template::render`. Sections need the whole tree and are passed through untouched.
+  pub fn replace_placeholders(&mut self, context: &'h TemplateContext) -> &mut Self {
+    self.tokenizer.sink.placeholders = Some(context);
+    self
+  }
+
+  /// Rewrites a chunk of UTF-8 input; a character split between chunks is fine.
+  pub fn feed(&mut self, chunk: &[u8]) -> io::Result<()> {
+    let mut text = String::with_capacity(self.decoder.max_utf8_buffer_length(chunk.len()).unwrap());
+    let _ = self.decoder.decode_to_string(chunk, &mut text, false);
+    self.process(text)
+  }
+
+  fn process(&mut self, text: String) -> io::Result<()> {
+    if !text.is_empty() {
+      self.input.push_back(StrTendril::from(text));
+      let _ = self.tokenizer.feed(&mut self.input);
+    }
+    match self.tokenizer.sink.error.take() {
+      Some(error) => Err(error),
+      None => Ok(()),
+    }
+  }
+
+  /// Rewrites the rest of the input, closes every open element and returns the output with a report on the
+  /// placeholders (sections are never reported missing).
+  pub fn finish(mut self) -> io::Result<(W, RenderReport)> {
+    let mut text = String::with_capacity(self.decoder.max_utf8_buffer_length(0).unwrap());
+    let _ = self.decoder.decode_to_string(&[], &mut text, true);
+    self.process(text)?;
+    self.tokenizer.end();
+    let mut sink = self.tokenizer.sink;
+    if let Some(error) = sink.error.take() {
+      return Err(error);
+    }
+    sink.output.flush()?;
+    let unused = match sink.placeholders {
+      Some(context) => {
+        let mut unused: Vec<String> = context.names().filter(|name| !sink.used.contains(*name)).cloned().collect();
+        unused.sort();
+        unused
+      },
+      None => vec![],
+    };
+    let report = RenderReport {
+      unknown: sink.unknown,
+      missing_sections: vec![],
+      unused,
+    };
+    Ok((sink.output, report))
+  }
+}
+
+impl<'h, W: Write> Write for Rewriter<'h, W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.feed(buf)?;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.tokenizer.sink.output.flush()
+  }
+}
+
+/// Rewrites a whole string with the handlers `configure` registers.
+pub fn rewrite_str<'h, F>(html: &str, configure: F) -> Result<String, SelectorError>
+where
+  F: FnOnce(&mut Rewriter<'h, Vec<u8>>) -> Result<(), SelectorError>,
+{
+  let mut rewriter = Rewriter::new(vec![]);
+  configure(&mut rewriter)?;
+  rewriter.feed(html.as_bytes()).unwrap();
+  let (output, _) = rewriter.finish().unwrap();
+  Ok(String::from_utf8(output).unwrap())
+}
@@ -0,0 +1,231 @@
+use html5ever::{namespace_url, ns};
+use markup5ever_rcdom::{Handle, NodeData};
+use crate::{format::attr_name, mutation, node::{DomNode, NodeKind}, rcdom::{node_delete, node_select}};
+
+const ALLOWED_ELEMENTS: &[&str] = &[
+  "a", "abbr", "b", "blockquote", "br", "caption", "cite", "code", "dd", "del", "div", "dl", "dt", "em", "figcaption", "figure", "h1",
+  "h2", "h3", "h4", "h5", "h6", "hr", "i", "img", "ins", "kbd", "li", "mark", "ol", "p", "pre", "q", "s", "small", "span", "strong",
+  "sub", "sup", "table", "tbody", "td", "tfoot", "th", "thead", "tr", "u", "ul",
+];
+
+const ALLOWED_ATTRIBUTES: &[(&str, &[&str])] = &[
+  ("*", &["class", "dir", "lang", "title"]),
+  ("a", &["href", "name", "target"]),
+  ("blockquote", &["cite"]),
+  ("img", &["alt", "height", "src", "width"]),
+  ("ol", &["reversed", "start"]),
+  ("q", &["cite"]),
+  ("td", &["colspan", "rowspan"]),
+  ("th", &["colspan", "rowspan", "scope"]),
+];
+
+const URL_ATTRIBUTES: &[&str] = &["action", "background", "cite", "formaction", "href", "longdesc", "poster", "src", "srcset"];
+
+/// Elements whose contents are code, raw text or otherwise unsafe to keep once the element is gone.
+const DROP_CONTENT_ELEMENTS: &[&str] = &[
+  "applet", "base", "embed", "frame", "frameset", "iframe", "link", "meta", "noembed", "noframes", "noscript", "object", "plaintext",
+  "script", "select", "style", "template", "textarea", "title", "xmp",
+];
+
+/// What `sanitize` lets through. Anything not listed is removed.
+#[derive(Debug, Clone)]
+pub struct Policy {
+  /// HTML elements kept in the tree.
+  pub allowed_elements: Vec<String>,
+  /// `(element, attribute)` pairs kept on allowed elements. The element `*` matches every element.
+  pub allowed_attributes: Vec<(String, String)>,
+  /// Attributes holding a URL, which are removed unless the URL passes `allowed_url_schemes`.
+  pub url_attributes: Vec<String>,
+  /// Lowercase schemes without the colon, such as `https`.
+  pub allowed_url_schemes: Vec<String>,
+  /// Whether URLs without a scheme, such as `/posts/1` or `#top`, are kept.
+  pub allow_relative_urls: bool,
+  /// Disallowed elements removed together with their contents. Other disallowed elements are replaced with their
+  /// children.
+  pub drop_content_elements: Vec<String>,
+  pub keep_comments: bool,
+}
+
+impl Default for Policy {
+  /// Basic formatting, lists, tables, links and images with `http`, `https` and `mailto` URLs.
+  fn default() -> Self {
+    let mut policy = Policy::new();
+    policy.allow_elements(ALLOWED_ELEMENTS).allow_url_schemes(&["http", "https", "mailto"]);
+    for (element, attributes) in ALLOWED_ATTRIBUTES {
+      policy.allow_attributes(element, attributes);
+    }
+    policy
+  }
+}
+
+impl Policy {
+  /// A policy that keeps only text: every element is removed, though the contents of harmless ones stay.
+  pub fn new() -> Self {
+    Policy {
+      allowed_elements: vec![],
+      allowed_attributes: vec![],
+      url_attributes: URL_ATTRIBUTES.iter().map(|name| name.to_string()).collect(),
+      allowed_url_schemes: vec![],
+      allow_relative_urls: true,
+      drop_content_elements: DROP_CONTENT_ELEMENTS.iter().map(|name| name.to_string()).collect(),
+      keep_comments: false,
+    }
+  }
+
+  pub fn allow_elements(&mut self, elements: &[&str]) -> &mut Self {
+    for element in elements {
+      let element = element.to_ascii_lowercase();
+      if !self.allowed_elements.contains(&element) {
+        self.allowed_elements.push(element);
+      }
+    }
+    self
+  }
+
+  /// Allows the attributes on `element`, or on every element for `*`.
+  pub fn allow_attributes(&mut self, element: &str, attributes: &[&str]) -> &mut Self {
+    for attribute in attributes {
+      let pair = (element.to_ascii_lowercase(), attribute.to_ascii_lowercase());
+      if !self.allowed_attributes.contains(&pair) {
+        self.allowed_attributes.push(pair);
+      }
+    }
+    self
+  }
+
+  pub fn allow_url_schemes(&mut self, schemes: &[&str]) -> &mut Self {
+    for scheme in schemes {
+      let scheme = scheme.trim_end_matches(':').to_ascii_lowercase();
+      if !self.allowed_url_schemes.contains(&scheme) {
+        self.allowed_url_schemes.push(scheme);
+      }
+    }
+    self
+  }
+
+  pub fn is_element_allowed(&self, element: &str) -> bool {
+    self.allowed_elements.iter().any(|name| name == element)
+  }
+
+  /// Event handlers (`on*`) are never allowed, whatever the policy lists.
+  pub fn is_attribute_allowed(&self, element: &str, attribute: &str) -> bool {
+    if attribute.starts_with("on") {
+      return false;
+    }
+    self.allowed_attributes.iter().any(|(name, allowed)| (name == "*" || name == element) && allowed == attribute)
+  }
+
+  /// Whether the URL's scheme is allowed, judged the way a browser would read it.
+  pub fn is_url_allowed(&self, url: &str) -> bool {
+    // Browsers ignore leading and trailing control characters and spaces, and tabs and newlines anywhere, so
+    // ` java\tscript:` still runs script.
+    let url: String = url.trim_matches(|c: char| c <= ' ').chars().filter(|c| !matches!(c, '\t' | '\n' | '\r')).collect();
+    match url.find([':', '/', '?', '#']) {
+      Some(index) if url[index..].starts_with(':') => {
+        let scheme = url[..index].to_ascii_lowercase();
+        self.allowed_url_schemes.contains(&scheme)
+      },
+      _ => self.allow_relative_urls,
+    }
+  }
+
+  fn is_attribute_value_allowed(&self, attribute: &str, value: &str) -> bool {
+    if !self.url_attributes.iter().any(|name| name == attribute) {
+      return true;
+    }
+    if attribute == "srcset" {
+      // `url 2x, url 640w`: every candidate's URL has to pass.
+      return value.split(',').all(|candidate| self.is_url_allowed(candidate.split_ascii_whitespace().next().unwrap_or_default()));
+    }
+    self.is_url_allowed(value)
+  }
+
+  fn drops_content(&self, element: &str) -> bool {
+    self.drop_content_elements.iter().any(|name| name == element)
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+  /// Names of the elements removed, once each in the order first seen.
+  pub removed_elements: Vec<String>,
+  /// Names of the attributes removed, once each in the order first seen.
+  pub removed_attributes: Vec<String>,
+}
+
+fn push_unique(list: &mut Vec<String>, name: String) {
+  if !list.contains(&name) {
+    list.push(name);
+  }
+}
+
+fn clean_attributes(element: &Handle, local: &str, policy: &Policy, report: &mut SanitizeReport) {
+  if let NodeData::Element { ref attrs, .. } = element.data {
+    attrs.borrow_mut().retain(|attr| {
+      // Namespaced attributes such as `xlink:href` only belong on SVG and MathML, which never get this far.
+      let keep = attr.name.ns == ns!()
+        && policy.is_attribute_allowed(local, &attr.name.local)
+        && policy.is_attribute_value_allowed(&attr.name.local, &attr.value);
+      if !keep {
+        push_unique(&mut report.removed_attributes, attr_name(&attr.name));
+      }
+      keep
+    });
+  }
+}
+
+fn sanitize_tree(root: &Handle, policy: &Policy, report: &mut SanitizeReport) {
+  for node in root.descendants() {
+    let remove = match node.kind() {
+      NodeKind::Comment => !policy.keep_comments,
+      NodeKind::ProcessingInstruction => true,
+      _ => false,
+    };
+    if remove {
+      node_delete(&node);
+    }
+  }
+
+  let elements = node_select(root, "*", &None);
+  // While set, entries deeper than this are inside an element dropped with its contents.
+  let mut dropped_depth: Option<usize> = None;
+  for (depth, element) in elements.borrow().iter() {
+    if *depth == 0 || dropped_depth.is_some_and(|dropped| *depth > dropped) {
+      continue;
+    }
+    dropped_depth = None;
+
+    let name = element.element_name().unwrap();
+    let local = name.local.to_string();
+    // SVG and MathML parse differently from HTML (`<style>` in `<svg>` holds markup, not text), which is the usual way
+    // to smuggle markup past a sanitizer, so foreign content goes with everything inside it.
+    let foreign = name.ns != ns!(html);
+    if !foreign && policy.is_element_allowed(&local) {
+      clean_attributes(element, &local, policy, report);
+      // Template contents live in a separate fragment the traversal does not enter.
+      if let Some(contents) = element.template_contents() {
+        sanitize_tree(&contents, policy, report);
+      }
+      continue;
+    }
+
+    push_unique(&mut report.removed_elements, local.clone());
+    if foreign || policy.drops_content(&local) || element.template_contents().is_some() {
+      node_delete(element);
+      dropped_depth = Some(*depth);
+    } else {
+      mutation::unwrap(element).unwrap();
+    }
+  }
+}
+
+/// Removes from the tree under `handle` (not the node itself) every element, attribute and URL `policy` does not
+/// allow. Disallowed elements are replaced with their children, or dropped with them when listed in
+/// `drop_content_elements`; SVG and MathML are always dropped. Comments and processing instructions are removed too.
+///
+/// `style` attributes are not checked beyond the allowlist, so allow them only for trusted markup.
+pub fn sanitize(handle: &Handle, policy: &Policy) -> SanitizeReport {
+  let mut report = SanitizeReport::default();
+  sanitize_tree(handle, policy, &mut report);
+  report
+}
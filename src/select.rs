@@ -0,0 +1,867 @@
+use html5ever::{namespace_url, ns, Namespace};
+use std::{collections::HashMap, fmt};
+use crate::node::{DomNode, NodeKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+  pub message: String,
+  pub position: usize,
+}
+
+impl fmt::Display for SelectorError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} at position {}", self.message, self.position)
+  }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// A parsed selector list such as `body > div.card[data-id^=x]:nth-child(2n+1), p`.
+///
+/// Supports type, universal, class, id and attribute selectors (`= ~= |= ^= $= *=` with the `i`/`s` flags),
+/// the four combinators, `:not()`, `:is()`/`:where()`, `:has()`, the structural pseudo-classes and
+/// `prefix|name` namespaces. The prefixes `html`, `svg`, `math`, `xlink`, `xml` and `xmlns` are predefined.
+#[derive(Debug, Clone)]
+pub struct Selector {
+  list: Vec<ComplexSelector>,
+}
+
+impl Selector {
+  pub fn parse(css: &str) -> Result<Selector, SelectorError> {
+    Selector::parse_with_namespaces(css, &HashMap::new())
+  }
+
+  /// Like `parse`, with extra `prefix -> namespace` bindings on top of the predefined prefixes.
+  pub fn parse_with_namespaces(css: &str, namespaces: &HashMap<String, Namespace>) -> Result<Selector, SelectorError> {
+    let mut prefixes: HashMap<String, Namespace> = HashMap::from([
+      ("html".to_string(), ns!(html)),
+      ("svg".to_string(), ns!(svg)),
+      ("math".to_string(), ns!(mathml)),
+      ("xlink".to_string(), ns!(xlink)),
+      ("xml".to_string(), ns!(xml)),
+      ("xmlns".to_string(), ns!(xmlns)),
+    ]);
+    prefixes.extend(namespaces.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let mut parser = Parser {
+      chars: css.chars().collect(),
+      pos: 0,
+      namespaces: &prefixes,
+    };
+    let list = parser.parse_selector_list(false)?;
+    parser.skip_whitespace();
+    if !parser.is_eof() {
+      return Err(parser.error("unexpected character"));
+    }
+    Ok(Selector { list })
+  }
+
+  pub fn matches<N: DomNode>(&self, node: &N) -> bool {
+    node.is_element() && self.list.iter().any(|complex| matches_complex(node, complex, None))
+  }
+
+  /// Elements below `root` (not `root` itself) matching this selector, in document order.
+  pub fn select<N: DomNode>(&self, root: &N) -> Vec<N> {
+    root
+      .descendants()
+      .into_iter()
+      .skip(1)
+      .filter(|node| self.matches(node))
+      .collect()
+  }
+
+  pub fn select_one<N: DomNode>(&self, root: &N) -> Option<N> {
+    root
+      .descendants()
+      .into_iter()
+      .skip(1)
+      .find(|node| self.matches(node))
+  }
+
+  /// The node itself or its nearest ancestor matching this selector, like `Element.closest()`.
+  pub fn closest<N: DomNode>(&self, node: &N) -> Option<N> {
+    if self.matches(node) {
+      return Some(node.clone());
+    }
+    node.ancestors().into_iter().find(|ancestor| self.matches(ancestor))
+  }
+
+  /// Whether the selector can be matched while streaming, before the element's siblings and children are known: no
+  /// sibling combinators, `:has()`, `:empty` or structural pseudo-classes.
+  pub(crate) fn uses_only_ancestors(&self) -> bool {
+    self.list.iter().all(ComplexSelector::uses_only_ancestors)
+  }
+}
+
+pub fn select<N: DomNode>(root: &N, css: &str) -> Result<Vec<N>, SelectorError> {
+  Ok(Selector::parse(css)?.select(root))
+}
+
+pub fn select_one<N: DomNode>(root: &N, css: &str) -> Result<Option<N>, SelectorError> {
+  Ok(Selector::parse(css)?.select_one(root))
+}
+
+pub fn matches<N: DomNode>(node: &N, css: &str) -> Result<bool, SelectorError> {
+  Ok(Selector::parse(css)?.matches(node))
+}
+
+pub fn closest<N: DomNode>(node: &N, css: &str) -> Result<Option<N>, SelectorError> {
+  Ok(Selector::parse(css)?.closest(node))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+  Descendant,
+  Child,
+  NextSibling,
+  SubsequentSibling,
+}
+
+#[derive(Debug, Clone)]
+struct ComplexSelector {
+  // Only set for the relative selectors inside `:has()`.
+  leading: Option<Combinator>,
+  compounds: Vec<Compound>,
+  // `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`.
+  combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone)]
+enum NamespaceConstraint {
+  Any,
+  Specific(Namespace),
+}
+
+impl NamespaceConstraint {
+  fn matches(&self, namespace: &Namespace) -> bool {
+    match self {
+      NamespaceConstraint::Any => true,
+      NamespaceConstraint::Specific(ns) => ns == namespace,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+struct Compound {
+  namespace: NamespaceConstraint,
+  // `None` is the universal selector.
+  local_name: Option<String>,
+  simple: Vec<Simple>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrOperator {
+  Equals,
+  Includes,
+  DashMatch,
+  Prefix,
+  Suffix,
+  Substring,
+}
+
+#[derive(Debug, Clone)]
+enum Simple {
+  Id(String),
+  Class(String),
+  Attr {
+    namespace: NamespaceConstraint,
+    local_name: String,
+    operation: Option<(AttrOperator, String)>,
+    case_insensitive: bool,
+  },
+  Not(Vec<ComplexSelector>),
+  Is(Vec<ComplexSelector>),
+  Has(Vec<ComplexSelector>),
+  Nth {
+    a: i64,
+    b: i64,
+    of_type: bool,
+    from_end: bool,
+    of: Option<Vec<ComplexSelector>>,
+  },
+  OnlyChild {
+    of_type: bool,
+  },
+  Root,
+  Empty,
+}
+
+struct Parser<'a> {
+  chars: Vec<char>,
+  pos: usize,
+  namespaces: &'a HashMap<String, Namespace>,
+}
+
+impl Parser<'_> {
+  fn error(&self, message: &str) -> SelectorError {
+    SelectorError {
+      message: message.to_string(),
+      position: self.pos,
+    }
+  }
+
+  fn is_eof(&self) -> bool {
+    self.pos >= self.chars.len()
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<char> {
+    self.chars.get(self.pos + offset).copied()
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    if self.peek() == Some(c) {
+      self.pos += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, c: char) -> Result<(), SelectorError> {
+    if self.eat(c) {
+      Ok(())
+    } else {
+      Err(self.error(&format!("expected '{}'", c)))
+    }
+  }
+
+  fn skip_whitespace(&mut self) -> bool {
+    let start = self.pos;
+    while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+      self.pos += 1;
+    }
+    self.pos > start
+  }
+
+  fn is_ident_start(&self) -> bool {
+    match self.peek() {
+      Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() || c == '\\' => true,
+      Some('-') => matches!(self.peek_at(1), Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '-' || !c.is_ascii() || c == '\\'),
+      _ => false,
+    }
+  }
+
+  fn parse_escape(&mut self) -> Result<char, SelectorError> {
+    let mut hex = String::new();
+    while hex.len() < 6 && self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+      hex.push(self.peek().unwrap());
+      self.pos += 1;
+    }
+    if hex.is_empty() {
+      let c = self.peek().ok_or_else(|| self.error("unexpected end of escape"))?;
+      self.pos += 1;
+      return Ok(c);
+    }
+    // A single whitespace after a hex escape belongs to the escape.
+    if self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+      self.pos += 1;
+    }
+    let code = u32::from_str_radix(&hex, 16).unwrap();
+    Ok(char::from_u32(code).filter(|&c| c != '\0').unwrap_or('\u{FFFD}'))
+  }
+
+  fn parse_ident(&mut self) -> Result<String, SelectorError> {
+    if !self.is_ident_start() {
+      return Err(self.error("expected identifier"));
+    }
+    let mut ident = String::new();
+    while let Some(c) = self.peek() {
+      if c == '\\' {
+        self.pos += 1;
+        ident.push(self.parse_escape()?);
+      } else if c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+        ident.push(c);
+        self.pos += 1;
+      } else {
+        break;
+      }
+    }
+    Ok(ident)
+  }
+
+  fn parse_string(&mut self) -> Result<String, SelectorError> {
+    let quote = self.peek().unwrap();
+    self.pos += 1;
+    let mut value = String::new();
+    loop {
+      match self.peek() {
+        None => return Err(self.error("unterminated string")),
+        Some(c) if c == quote => {
+          self.pos += 1;
+          return Ok(value);
+        },
+        Some('\\') => {
+          self.pos += 1;
+          // An escaped newline is a line continuation.
+          if self.eat('\n') {
+            continue;
+          }
+          value.push(self.parse_escape()?);
+        },
+        Some(c) => {
+          value.push(c);
+          self.pos += 1;
+        },
+      }
+    }
+  }
+
+  fn parse_selector_list(&mut self, relative: bool) -> Result<Vec<ComplexSelector>, SelectorError> {
+    let mut list = vec![self.parse_complex(relative)?];
+    loop {
+      self.skip_whitespace();
+      if !self.eat(',') {
+        return Ok(list);
+      }
+      list.push(self.parse_complex(relative)?);
+    }
+  }
+
+  fn parse_combinator(&mut self) -> Option<Combinator> {
+    let combinator = match self.peek()? {
+      '>' => Combinator::Child,
+      '+' => Combinator::NextSibling,
+      '~' => Combinator::SubsequentSibling,
+      _ => return None,
+    };
+    self.pos += 1;
+    Some(combinator)
+  }
+
+  fn parse_complex(&mut self, relative: bool) -> Result<ComplexSelector, SelectorError> {
+    self.skip_whitespace();
+    let leading = if relative {
+      let leading = self.parse_combinator();
+      self.skip_whitespace();
+      leading
+    } else {
+      None
+    };
+
+    let mut compounds = vec![self.parse_compound()?];
+    let mut combinators = vec![];
+    loop {
+      let had_whitespace = self.skip_whitespace();
+      let combinator = match self.parse_combinator() {
+        Some(combinator) => {
+          self.skip_whitespace();
+          combinator
+        },
+        None if had_whitespace && !matches!(self.peek(), None | Some(',') | Some(')')) => Combinator::Descendant,
+        None => break,
+      };
+      combinators.push(combinator);
+      compounds.push(self.parse_compound()?);
+    }
+
+    Ok(ComplexSelector {
+      leading,
+      compounds,
+      combinators,
+    })
+  }
+
+  fn parse_name_or_star(&mut self) -> Result<Option<String>, SelectorError> {
+    if self.eat('*') {
+      Ok(None)
+    } else {
+      Ok(Some(self.parse_ident()?))
+    }
+  }
+
+  fn lookup_namespace(&self, prefix: &str) -> Result<Namespace, SelectorError> {
+    self
+      .namespaces
+      .get(prefix)
+      .cloned()
+      .ok_or_else(|| self.error(&format!("unknown namespace prefix '{}'", prefix)))
+  }
+
+  /// Parses `name`, `*`, `prefix|name`, `*|name` and `|name` (no namespace).
+  fn parse_qualified_name(&mut self, in_attr: bool) -> Result<(NamespaceConstraint, Option<String>), SelectorError> {
+    let is_namespace_bar = |parser: &Parser, offset: usize| {
+      parser.peek_at(offset) == Some('|') && parser.peek_at(offset + 1) != Some('=')
+    };
+
+    if is_namespace_bar(self, 0) {
+      self.pos += 1;
+      return Ok((NamespaceConstraint::Specific(ns!()), self.parse_name_or_star()?));
+    }
+
+    let first = self.parse_name_or_star()?;
+    if is_namespace_bar(self, 0) {
+      self.pos += 1;
+      let namespace = match first {
+        None => NamespaceConstraint::Any,
+        Some(prefix) => NamespaceConstraint::Specific(self.lookup_namespace(&prefix)?),
+      };
+      return Ok((namespace, self.parse_name_or_star()?));
+    }
+
+    // Unprefixed attribute names only match attributes without a namespace.
+    let namespace = if in_attr {
+      NamespaceConstraint::Specific(ns!())
+    } else {
+      NamespaceConstraint::Any
+    };
+    Ok((namespace, first))
+  }
+
+  fn parse_compound(&mut self) -> Result<Compound, SelectorError> {
+    let start = self.pos;
+    let (namespace, local_name) = if self.is_ident_start() || matches!(self.peek(), Some('*') | Some('|')) {
+      self.parse_qualified_name(false)?
+    } else {
+      (NamespaceConstraint::Any, None)
+    };
+
+    let mut simple = vec![];
+    loop {
+      match self.peek() {
+        Some('#') => {
+          self.pos += 1;
+          simple.push(Simple::Id(self.parse_ident()?));
+        },
+        Some('.') => {
+          self.pos += 1;
+          simple.push(Simple::Class(self.parse_ident()?));
+        },
+        Some('[') => {
+          self.pos += 1;
+          simple.push(self.parse_attribute()?);
+        },
+        Some(':') => {
+          self.pos += 1;
+          if self.peek() == Some(':') {
+            return Err(self.error("pseudo-elements are not supported"));
+          }
+          simple.push(self.parse_pseudo_class()?);
+        },
+        _ => break,
+      }
+    }
+
+    if self.pos == start {
+      return Err(self.error("expected selector"));
+    }
+    Ok(Compound {
+      namespace,
+      local_name,
+      simple,
+    })
+  }
+
+  fn parse_attribute(&mut self) -> Result<Simple, SelectorError> {
+    self.skip_whitespace();
+    let (namespace, local_name) = self.parse_qualified_name(true)?;
+    let local_name = local_name.ok_or_else(|| self.error("expected attribute name"))?;
+    self.skip_whitespace();
+
+    let operator = match self.peek() {
+      Some(']') => None,
+      Some('=') => Some(AttrOperator::Equals),
+      Some(c) => {
+        let operator = match c {
+          '~' => AttrOperator::Includes,
+          '|' => AttrOperator::DashMatch,
+          '^' => AttrOperator::Prefix,
+          '$' => AttrOperator::Suffix,
+          '*' => AttrOperator::Substring,
+          _ => return Err(self.error("expected attribute operator")),
+        };
+        self.pos += 1;
+        if self.peek() != Some('=') {
+          return Err(self.error("expected '='"));
+        }
+        Some(operator)
+      },
+      None => return Err(self.error("unterminated attribute selector")),
+    };
+
+    let mut case_insensitive = false;
+    let operation = match operator {
+      None => None,
+      Some(operator) => {
+        self.expect('=')?;
+        self.skip_whitespace();
+        let value = match self.peek() {
+          Some('"') | Some('\'') => self.parse_string()?,
+          _ => self.parse_ident()?,
+        };
+        self.skip_whitespace();
+        if self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+          match self.parse_ident()?.to_ascii_lowercase().as_str() {
+            "i" => case_insensitive = true,
+            "s" => case_insensitive = false,
+            _ => return Err(self.error("unknown attribute selector flag")),
+          }
+          self.skip_whitespace();
+        }
+        Some((operator, value))
+      },
+    };
+    self.expect(']')?;
+
+    Ok(Simple::Attr {
+      namespace,
+      local_name,
+      operation,
+      case_insensitive,
+    })
+  }
+
+  fn parse_pseudo_class(&mut self) -> Result<Simple, SelectorError> {
+    let name = self.parse_ident()?.to_ascii_lowercase();
+    if self.eat('(') {
+      let simple = match name.as_str() {
+        "not" => Simple::Not(self.parse_selector_list(false)?),
+        "is" | "where" | "matches" => Simple::Is(self.parse_selector_list(false)?),
+        "has" => Simple::Has(self.parse_selector_list(true)?),
+        "nth-child" => self.parse_nth(false, false)?,
+        "nth-last-child" => self.parse_nth(false, true)?,
+        "nth-of-type" => self.parse_nth(true, false)?,
+        "nth-last-of-type" => self.parse_nth(true, true)?,
+        _ => return Err(self.error(&format!("unsupported pseudo-class ':{}()'", name))),
+      };
+      self.skip_whitespace();
+      self.expect(')')?;
+      return Ok(simple);
+    }
+
+    let nth = |of_type, from_end| Simple::Nth {
+      a: 0,
+      b: 1,
+      of_type,
+      from_end,
+      of: None,
+    };
+    Ok(match name.as_str() {
+      "first-child" => nth(false, false),
+      "last-child" => nth(false, true),
+      "first-of-type" => nth(true, false),
+      "last-of-type" => nth(true, true),
+      "only-child" => Simple::OnlyChild { of_type: false },
+      "only-of-type" => Simple::OnlyChild { of_type: true },
+      "root" => Simple::Root,
+      "empty" => Simple::Empty,
+      _ => return Err(self.error(&format!("unsupported pseudo-class ':{}'", name))),
+    })
+  }
+
+  fn parse_integer(&mut self) -> Result<Option<i64>, SelectorError> {
+    let start = self.pos;
+    while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    if self.pos == start {
+      return Ok(None);
+    }
+    let digits: String = self.chars[start..self.pos].iter().collect();
+    digits.parse().map(Some).map_err(|_| self.error("number out of range"))
+  }
+
+  /// Parses the `An+B [of S]` argument of the `:nth-*` pseudo-classes.
+  fn parse_nth(&mut self, of_type: bool, from_end: bool) -> Result<Simple, SelectorError> {
+    self.skip_whitespace();
+    let (a, b) = if self.is_ident_start() && !matches!(self.peek(), Some('n') | Some('N') | Some('-')) {
+      match self.parse_ident()?.to_ascii_lowercase().as_str() {
+        "odd" => (2, 1),
+        "even" => (2, 0),
+        _ => return Err(self.error("invalid nth expression")),
+      }
+    } else {
+      let sign = if self.eat('-') {
+        -1
+      } else {
+        self.eat('+');
+        1
+      };
+      let number = self.parse_integer()?;
+      if self.eat('n') || self.eat('N') {
+        let a = sign * number.unwrap_or(1);
+        self.skip_whitespace();
+        let b_sign = if self.eat('-') {
+          Some(-1)
+        } else if self.eat('+') {
+          Some(1)
+        } else {
+          None
+        };
+        let b = match b_sign {
+          Some(b_sign) => {
+            self.skip_whitespace();
+            b_sign * self.parse_integer()?.ok_or_else(|| self.error("expected number"))?
+          },
+          None => 0,
+        };
+        (a, b)
+      } else {
+        (0, sign * number.ok_or_else(|| self.error("invalid nth expression"))?)
+      }
+    };
+
+    self.skip_whitespace();
+    let of = if !of_type && self.is_ident_start() {
+      if !self.parse_ident()?.eq_ignore_ascii_case("of") {
+        return Err(self.error("expected 'of'"));
+      }
+      Some(self.parse_selector_list(false)?)
+    } else {
+      None
+    };
+
+    Ok(Simple::Nth {
+      a,
+      b,
+      of_type,
+      from_end,
+      of,
+    })
+  }
+}
+
+fn parent_element<N: DomNode>(node: &N) -> Option<N> {
+  node.parent().filter(|parent| parent.is_element())
+}
+
+impl ComplexSelector {
+  /// Whether matching looks at nothing but the element, its attributes and its ancestors.
+  fn uses_only_ancestors(&self) -> bool {
+    self.leading.is_none()
+      && self.combinators.iter().all(|combinator| matches!(combinator, Combinator::Descendant | Combinator::Child))
+      && self.compounds.iter().all(|compound| compound.simple.iter().all(Simple::uses_only_ancestors))
+  }
+}
+
+impl Simple {
+  fn uses_only_ancestors(&self) -> bool {
+    match self {
+      Simple::Id(_) | Simple::Class(_) | Simple::Attr { .. } | Simple::Root => true,
+      Simple::Not(list) | Simple::Is(list) => list.iter().all(ComplexSelector::uses_only_ancestors),
+      Simple::Has(_) | Simple::Nth { .. } | Simple::OnlyChild { .. } | Simple::Empty => false,
+    }
+  }
+}
+
+fn is_html_element<N: DomNode>(node: &N) -> bool {
+  node.element_name().is_some_and(|name| name.ns == ns!(html))
+}
+
+fn matches_complex<N: DomNode>(node: &N, complex: &ComplexSelector, anchor: Option<&N>) -> bool {
+  matches_from(node, complex, complex.compounds.len() - 1, anchor)
+}
+
+fn matches_from<N: DomNode>(node: &N, complex: &ComplexSelector, index: usize, anchor: Option<&N>) -> bool {
+  if !matches_compound(node, &complex.compounds[index]) {
+    return false;
+  }
+
+  if index == 0 {
+    return match anchor {
+      Some(anchor) => is_related(anchor, node, complex.leading.unwrap_or(Combinator::Descendant)),
+      None => true,
+    };
+  }
+
+  match complex.combinators[index - 1] {
+    Combinator::Child => parent_element(node).is_some_and(|parent| matches_from(&parent, complex, index - 1, anchor)),
+    Combinator::Descendant => {
+      let mut current = parent_element(node);
+      while let Some(ancestor) = current {
+        if matches_from(&ancestor, complex, index - 1, anchor) {
+          return true;
+        }
+        current = parent_element(&ancestor);
+      }
+      false
+    },
+    Combinator::NextSibling => node.previous_element_sibling().is_some_and(|sibling| matches_from(&sibling, complex, index - 1, anchor)),
+    Combinator::SubsequentSibling => {
+      let mut current = node.previous_element_sibling();
+      while let Some(sibling) = current {
+        if matches_from(&sibling, complex, index - 1, anchor) {
+          return true;
+        }
+        current = sibling.previous_element_sibling();
+      }
+      false
+    },
+  }
+}
+
+/// Whether `node` stands in the `combinator` relation to `anchor`, as in `anchor <combinator> node`.
+fn is_related<N: DomNode>(anchor: &N, node: &N, combinator: Combinator) -> bool {
+  match combinator {
+    Combinator::Descendant => {
+      let mut current = node.parent();
+      while let Some(ancestor) = current {
+        if ancestor.same_node(anchor) {
+          return true;
+        }
+        current = ancestor.parent();
+      }
+      false
+    },
+    Combinator::Child => node.parent().is_some_and(|parent| parent.same_node(anchor)),
+    Combinator::NextSibling => node.previous_element_sibling().is_some_and(|sibling| sibling.same_node(anchor)),
+    Combinator::SubsequentSibling => {
+      let mut current = node.previous_element_sibling();
+      while let Some(sibling) = current {
+        if sibling.same_node(anchor) {
+          return true;
+        }
+        current = sibling.previous_element_sibling();
+      }
+      false
+    },
+  }
+}
+
+fn matches_compound<N: DomNode>(node: &N, compound: &Compound) -> bool {
+  let name = match node.element_name() {
+    Some(name) => name,
+    None => return false,
+  };
+  if !compound.namespace.matches(&name.ns) {
+    return false;
+  }
+  if let Some(ref local_name) = compound.local_name {
+    let is_match = if name.ns == ns!(html) {
+      local_name.eq_ignore_ascii_case(&name.local)
+    } else {
+      **local_name == *name.local
+    };
+    if !is_match {
+      return false;
+    }
+  }
+  compound.simple.iter().all(|simple| matches_simple(node, simple))
+}
+
+fn matches_simple<N: DomNode>(node: &N, simple: &Simple) -> bool {
+  match simple {
+    Simple::Id(id) => node.attr("id").is_some_and(|value| value == *id),
+    Simple::Class(class) => node
+      .attr("class")
+      .is_some_and(|value| value.split_ascii_whitespace().any(|c| c == class)),
+    Simple::Attr {
+      namespace,
+      local_name,
+      operation,
+      case_insensitive,
+    } => matches_attr(node, namespace, local_name, operation.as_ref(), *case_insensitive),
+    Simple::Not(list) => !list.iter().any(|complex| matches_complex(node, complex, None)),
+    Simple::Is(list) => list.iter().any(|complex| matches_complex(node, complex, None)),
+    Simple::Has(list) => list.iter().any(|complex| has_relative_match(node, complex)),
+    Simple::Nth {
+      a,
+      b,
+      of_type,
+      from_end,
+      of,
+    } => match nth_index(node, *of_type, *from_end, of.as_deref()) {
+      Some(index) => matches_nth(*a, *b, index),
+      None => false,
+    },
+    Simple::OnlyChild { of_type } => {
+      nth_index(node, *of_type, false, None) == Some(1) && nth_index(node, *of_type, true, None) == Some(1)
+    },
+    Simple::Root => node.parent().is_some_and(|parent| parent.kind() == NodeKind::Document),
+    Simple::Empty => node
+      .children()
+      .iter()
+      .all(|child| !child.is_element() && child.text().is_none_or(|text| text.is_empty())),
+  }
+}
+
+fn matches_attr<N: DomNode>(node: &N, namespace: &NamespaceConstraint, local_name: &str, operation: Option<&(AttrOperator, String)>, case_insensitive: bool) -> bool {
+  let html = is_html_element(node);
+  let attrs = match node.attrs() {
+    Some(attrs) => attrs,
+    None => return false,
+  };
+
+  attrs.iter().any(|attr| {
+    let name_matches = if html {
+      local_name.eq_ignore_ascii_case(&attr.name.local)
+    } else {
+      local_name == &*attr.name.local
+    };
+    if !name_matches || !namespace.matches(&attr.name.ns) {
+      return false;
+    }
+
+    let (operator, expected) = match operation {
+      Some((operator, expected)) => (operator, expected),
+      None => return true,
+    };
+    let (value, expected) = if case_insensitive {
+      (attr.value.to_lowercase(), expected.to_lowercase())
+    } else {
+      (attr.value.to_string(), expected.clone())
+    };
+    match operator {
+      AttrOperator::Equals => value == expected,
+      AttrOperator::Includes => !expected.is_empty() && value.split_ascii_whitespace().any(|v| v == expected),
+      AttrOperator::DashMatch => value == expected || value.starts_with(&format!("{}-", expected)),
+      AttrOperator::Prefix => !expected.is_empty() && value.starts_with(&expected),
+      AttrOperator::Suffix => !expected.is_empty() && value.ends_with(&expected),
+      AttrOperator::Substring => !expected.is_empty() && value.contains(&expected),
+    }
+  })
+}
+
+fn has_relative_match<N: DomNode>(anchor: &N, complex: &ComplexSelector) -> bool {
+  let candidates: Vec<N> = match complex.leading {
+    None | Some(Combinator::Descendant) | Some(Combinator::Child) => anchor.descendants().into_iter().skip(1).collect(),
+    Some(Combinator::NextSibling) | Some(Combinator::SubsequentSibling) => {
+      let mut candidates = vec![];
+      let mut current = anchor.next_sibling();
+      while let Some(sibling) = current {
+        candidates.extend(sibling.descendants());
+        current = sibling.next_sibling();
+      }
+      candidates
+    },
+  };
+  candidates
+    .iter()
+    .any(|candidate| candidate.is_element() && matches_complex(candidate, complex, Some(anchor)))
+}
+
+/// 1-based position of `node` among its element siblings that are counted by an `:nth-*` pseudo-class.
+fn nth_index<N: DomNode>(node: &N, of_type: bool, from_end: bool, of: Option<&[ComplexSelector]>) -> Option<i64> {
+  let parent = node.parent()?;
+  let name = node.element_name()?;
+  if let Some(of) = of {
+    if !of.iter().any(|complex| matches_complex(node, complex, None)) {
+      return None;
+    }
+  }
+
+  let mut siblings: Vec<N> = parent
+    .element_children()
+    .into_iter()
+    .filter(|sibling| !of_type || sibling.element_name() == Some(name))
+    .filter(|sibling| of.is_none_or(|of| of.iter().any(|complex| matches_complex(sibling, complex, None))))
+    .collect();
+  if from_end {
+    siblings.reverse();
+  }
+  siblings
+    .iter()
+    .position(|sibling| sibling.same_node(node))
+    .map(|index| index as i64 + 1)
+}
+
+fn matches_nth(a: i64, b: i64, index: i64) -> bool {
+  if a == 0 {
+    return index == b;
+  }
+  let diff = index - b;
+  diff % a == 0 && diff / a >= 0
+}
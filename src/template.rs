@@ -0,0 +1,375 @@
+use html5ever::{namespace_url, ns};
+use markup5ever_rcdom::{Handle, NodeData};
+use std::collections::{BTreeSet, HashMap};
+use crate::{format::RAW_TEXT_ELEMENTS, mutation::{self, clone_node}, rcdom::convert_node_to_html_string};
+
+const PLACEHOLDER_START: &str = "[##_";
+const PLACEHOLDER_END: &str = "_##]";
+
+fn is_placeholder_name_char(c: char) -> bool {
+  c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')
+}
+
+/// Rewrites every `[##_name_##]` marker in `text` with `replace(name)`, leaving markers for which it returns `None` as they are.
+pub fn replace_placeholders<F>(text: &str, mut replace: F) -> String
+where
+  F: FnMut(&str) -> Option<String>,
+{
+  let mut result = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find(PLACEHOLDER_START) {
+    result.push_str(&rest[..start]);
+    let after_start = &rest[start + PLACEHOLDER_START.len()..];
+    let name_len = after_start
+      .char_indices()
+      .find(|&(i, c)| after_start[i..].starts_with(PLACEHOLDER_END) || !is_placeholder_name_char(c))
+      .map(|(i, _)| i)
+      .unwrap_or(after_start.len());
+    let name = &after_start[..name_len];
+
+    if !name.is_empty() && after_start[name_len..].starts_with(PLACEHOLDER_END) {
+      let marker_len = PLACEHOLDER_START.len() + name_len + PLACEHOLDER_END.len();
+      match replace(name) {
+        Some(value) => result.push_str(&value),
+        None => result.push_str(&rest[start..start + marker_len]),
+      }
+      rest = &rest[start + marker_len..];
+    } else {
+      result.push_str(PLACEHOLDER_START);
+      rest = after_start;
+    }
+  }
+  result.push_str(rest);
+  result
+}
+
+/// Names of the `[##_name_##]` markers in `text`, in order of appearance.
+pub fn find_placeholders(text: &str) -> Vec<String> {
+  let mut names = vec![];
+  replace_placeholders(text, |name| {
+    names.push(name.to_string());
+    None
+  });
+  names
+}
+
+#[derive(Debug, Clone)]
+pub enum TemplateValue {
+  Text(String),
+  /// Keeps (`true`) or drops (`false`) a section.
+  Flag(bool),
+  /// Repeats a section once per item; each item's values shadow the enclosing ones.
+  List(Vec<TemplateContext>),
+}
+
+/// Values substituted into a template, keyed by placeholder or section name.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateContext {
+  values: HashMap<String, TemplateValue>,
+}
+
+impl TemplateContext {
+  pub fn new() -> Self {
+    TemplateContext::default()
+  }
+
+  pub fn set(&mut self, name: &str, value: &str) -> &mut Self {
+    self.values.insert(name.to_string(), TemplateValue::Text(value.to_string()));
+    self
+  }
+
+  pub fn set_flag(&mut self, name: &str, flag: bool) -> &mut Self {
+    self.values.insert(name.to_string(), TemplateValue::Flag(flag));
+    self
+  }
+
+  pub fn set_list(&mut self, name: &str, items: Vec<TemplateContext>) -> &mut Self {
+    self.values.insert(name.to_string(), TemplateValue::List(items));
+    self
+  }
+
+  pub fn get(&self, name: &str) -> Option<&TemplateValue> {
+    self.values.get(name)
+  }
+
+  pub(crate) fn names(&self) -> impl Iterator<Item = &String> {
+    self.values.keys()
+  }
+
+  /// Builds a context from a JSON object: strings and numbers become text, booleans flags and arrays of objects lists.
+  pub fn from_json(json: &serde_json::Value) -> Result<TemplateContext, String> {
+    let object = json.as_object().ok_or("template variables must be a JSON object")?;
+    let mut context = TemplateContext::new();
+    for (name, value) in object {
+      match value {
+        serde_json::Value::String(text) => context.set(name, text),
+        serde_json::Value::Number(number) => context.set(name, &number.to_string()),
+        serde_json::Value::Bool(flag) => context.set_flag(name, *flag),
+        serde_json::Value::Null => context.set_flag(name, false),
+        serde_json::Value::Array(items) => {
+          let items = items
+            .iter()
+            .map(TemplateContext::from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| format!("{}: {}", name, error))?;
+          context.set_list(name, items)
+        },
+        serde_json::Value::Object(_) => context.set_list(name, vec![TemplateContext::from_json(value)?]),
+      };
+    }
+    Ok(context)
+  }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for TemplateContext {
+  fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+    let mut context = TemplateContext::new();
+    for (name, value) in iter {
+      context.set(name.as_ref(), value.as_ref());
+    }
+    context
+  }
+}
+
+/// Which elements are sections. A section element is looked up in the context by its tag name and is never
+/// itself part of the output: it is repeated for a list, kept for `true` or non-empty text, and dropped otherwise.
+#[derive(Debug, Clone)]
+pub struct TemplateOptions {
+  /// Tags starting with this prefix are sections.
+  pub section_prefix: Option<String>,
+  /// Additional tags that are sections, such as `s3`.
+  pub section_tags: Vec<String>,
+}
+
+impl Default for TemplateOptions {
+  fn default() -> Self {
+    TemplateOptions {
+      section_prefix: Some("s_".to_string()),
+      section_tags: vec![],
+    }
+  }
+}
+
+impl TemplateOptions {
+  fn section_name(&self, handle: &Handle) -> Option<String> {
+    let name = match handle.data {
+      NodeData::Element { ref name, .. } if name.ns == ns!(html) => name.local.to_string(),
+      _ => return None,
+    };
+    let is_section = self.section_prefix.as_ref().is_some_and(|prefix| name.starts_with(prefix.as_str()))
+      || self.section_tags.iter().any(|tag| tag.eq_ignore_ascii_case(&name));
+    if is_section { Some(name) } else { None }
+  }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderReport {
+  /// Placeholders found in the document with no value in the context. They are left in the output untouched.
+  pub unknown: Vec<String>,
+  /// Sections with no value in the context. They are dropped from the output.
+  pub missing_sections: Vec<String>,
+  /// Context values nothing in the document referred to.
+  pub unused: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TextContext {
+  Normal,
+  // Contents of the named raw text element, such as `<script>` or `<style>`, which the serializer writes out without
+  // escaping.
+  RawText(&'static str),
+}
+
+impl TextContext {
+  /// The context for the children of an element named `local` in the HTML namespace.
+  pub(crate) fn for_html_element(local: &str) -> TextContext {
+    match RAW_TEXT_ELEMENTS.iter().find(|name| name.eq_ignore_ascii_case(local)) {
+      Some(name) => TextContext::RawText(name),
+      None => TextContext::Normal,
+    }
+  }
+}
+
+/// Escapes a value for the place it is substituted into.
+///
+/// Text and attribute values are left as they are: the serializer escapes them. Raw text elements are written out as
+/// is, so a value must not be able to end the one it is in: `</name` of that element is broken up as `<\/name`, which
+/// reads the same inside a JavaScript or CSS string. In `<script>`, `<!--` and `<script` become `<\!--` and
+/// `<\script` too, as they could otherwise keep the next `</script>` from closing the element.
+pub(crate) fn escape_for(context: TextContext, value: &str) -> String {
+  let name = match context {
+    TextContext::Normal => return value.to_string(),
+    TextContext::RawText(name) => name,
+  };
+  let closing = format!("/{}", name);
+  let mut sequences = vec![closing.as_str()];
+  if name == "script" {
+    sequences.extend(["!--", "script"]);
+  }
+
+  let mut result = String::with_capacity(value.len());
+  let mut rest = value;
+  while let Some(index) = rest.find('<') {
+    result.push_str(&rest[..=index]);
+    rest = &rest[index + 1..];
+    let breaks = sequences.iter().any(|sequence| rest.get(..sequence.len()).is_some_and(|start| start.eq_ignore_ascii_case(sequence)));
+    if breaks {
+      result.push('\\');
+    }
+  }
+  result.push_str(rest);
+  result
+}
+
+struct Renderer<'a> {
+  options: &'a TemplateOptions,
+  // Innermost scope last.
+  scopes: Vec<&'a TemplateContext>,
+  used: BTreeSet<String>,
+  unknown: Vec<String>,
+  missing_sections: Vec<String>,
+}
+
+fn push_unique(list: &mut Vec<String>, name: &str) {
+  if !list.iter().any(|item| item == name) {
+    list.push(name.to_string());
+  }
+}
+
+impl<'a> Renderer<'a> {
+  fn lookup(&mut self, name: &str) -> Option<&'a TemplateValue> {
+    let value = self.scopes.iter().rev().find_map(|scope| scope.get(name));
+    if value.is_some() {
+      self.used.insert(name.to_string());
+    }
+    value
+  }
+
+  fn substitute(&mut self, text: &str, text_context: TextContext) -> String {
+    replace_placeholders(text, |name| match self.lookup(name) {
+      Some(TemplateValue::Text(value)) => Some(escape_for(text_context, value)),
+      _ => {
+        push_unique(&mut self.unknown, name);
+        None
+      },
+    })
+  }
+
+  /// Renders a section's contents once per scope, inserting each rendered copy before the section element.
+  fn expand_section(&mut self, section: &Handle, scopes: Vec<Option<&'a TemplateContext>>, text_context: TextContext) {
+    let children = section.children.borrow().clone();
+    for scope in scopes {
+      if let Some(scope) = scope {
+        self.scopes.push(scope);
+      }
+      for child in children.iter() {
+        let copy = clone_node(child, true);
+        mutation::insert_before(section, &copy).unwrap();
+        self.render_child(&copy, text_context);
+      }
+      if scope.is_some() {
+        self.scopes.pop();
+      }
+    }
+  }
+
+  fn render_children(&mut self, parent: &Handle, text_context: TextContext) {
+    let children = parent.children.borrow().clone();
+    for child in children.iter() {
+      self.render_child(child, text_context);
+    }
+  }
+
+  /// Renders a node in place, or replaces a section element with its expansion.
+  fn render_child(&mut self, child: &Handle, text_context: TextContext) {
+    let section_name = match self.options.section_name(child) {
+      Some(name) => name,
+      None => {
+        self.render_node(child, text_context);
+        return;
+      },
+    };
+
+    let scopes: Vec<Option<&'a TemplateContext>> = match self.lookup(&section_name) {
+      Some(TemplateValue::List(items)) => items.iter().map(Some).collect(),
+      Some(TemplateValue::Flag(true)) => vec![None],
+      Some(TemplateValue::Text(text)) if !text.is_empty() => vec![None],
+      Some(_) => vec![],
+      None => {
+        push_unique(&mut self.missing_sections, &section_name);
+        vec![]
+      },
+    };
+    self.expand_section(child, scopes, text_context);
+    mutation::detach(child);
+  }
+
+  fn render_node(&mut self, handle: &Handle, text_context: TextContext) {
+    match handle.data {
+      NodeData::Text { ref contents } => {
+        let rendered = self.substitute(&contents.borrow(), text_context);
+        let mut contents = contents.borrow_mut();
+        contents.clear();
+        contents.push_slice(&rendered);
+      },
+      NodeData::Element {
+        ref name,
+        ref attrs,
+        ref template_contents,
+        ..
+      } => {
+        for attr in attrs.borrow_mut().iter_mut() {
+          let rendered = self.substitute(&attr.value, TextContext::Normal);
+          attr.value.clear();
+          attr.value.push_slice(&rendered);
+        }
+        let child_context = if name.ns == ns!(html) { TextContext::for_html_element(&name.local) } else { TextContext::Normal };
+        self.render_children(handle, child_context);
+        if let Some(contents) = template_contents.borrow().as_ref() {
+          self.render_node(contents, TextContext::Normal);
+        }
+      },
+      _ => self.render_children(handle, text_context),
+    }
+  }
+}
+
+/// Substitutes `[##_name_##]` placeholders in the text nodes and attribute values under `handle`, in place,
+/// treating `s_*` elements as sections.
+///
+/// Values go into text and attributes as they are, markup included, and are escaped when the tree is serialized. In
+/// raw text elements such as `<script>` and `<style>`, which are serialized without escaping, a value is kept from
+/// closing the element by breaking up `</script` or `</style` (case-insensitively) as `<\/script` or `<\/style`.
+pub fn render(handle: &Handle, context: &TemplateContext) -> RenderReport {
+  render_with_options(handle, context, &TemplateOptions::default())
+}
+
+pub fn render_with_options(handle: &Handle, context: &TemplateContext, options: &TemplateOptions) -> RenderReport {
+  let mut renderer = Renderer {
+    options,
+    scopes: vec![context],
+    used: BTreeSet::new(),
+    unknown: vec![],
+    missing_sections: vec![],
+  };
+  renderer.render_node(handle, TextContext::Normal);
+
+  let mut unused: Vec<String> = context
+    .values
+    .keys()
+    .filter(|name| !renderer.used.contains(*name))
+    .cloned()
+    .collect();
+  unused.sort();
+  RenderReport {
+    unknown: renderer.unknown,
+    missing_sections: renderer.missing_sections,
+    unused,
+  }
+}
+
+/// Renders `handle` in place and serializes it with `convert_node_to_html_string`.
+pub fn render_to_string(handle: &Handle, context: &TemplateContext) -> (String, RenderReport) {
+  let report = render(handle, context);
+  (convert_node_to_html_string(handle), report)
+}
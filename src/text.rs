@@ -0,0 +1,162 @@
+use html5ever::{namespace_url, ns};
+use crate::node::{DomNode, NodeKind};
+
+/// Every text node below `node` concatenated in tree order, like `Node.textContent`. Comments and template contents
+/// are left out.
+pub fn text_content<N: DomNode>(node: &N) -> String {
+  let mut text = String::new();
+  for descendant in node.descendants() {
+    if let Some(contents) = descendant.text() {
+      text.push_str(&contents);
+    }
+  }
+  text
+}
+
+/// Elements that are never rendered, so `inner_text` skips them with their contents.
+const HIDDEN_ELEMENTS: &[&str] = &["head", "script", "style", "template", "noscript", "title", "iframe", "object", "video", "audio", "select", "datalist"];
+
+/// Elements rendered as blocks, which get a line break before and after their text.
+pub(crate) const BLOCK_ELEMENTS: &[&str] = &[
+  "address", "article", "aside", "blockquote", "body", "caption", "dd", "details", "dialog", "div", "dl", "dt", "fieldset", "figcaption",
+  "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hgroup", "hr", "html", "legend", "li", "listing", "main",
+  "menu", "nav", "ol", "p", "pre", "section", "summary", "table", "tr", "ul",
+];
+
+/// Elements whose whitespace is kept as written.
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "listing", "textarea", "plaintext", "xmp"];
+
+enum Chunk {
+  /// Text whose whitespace runs collapse to one space.
+  Collapsible(String),
+  /// Text written out as is.
+  Literal(String),
+  /// At least this many line breaks, unless at the very start or end.
+  Break(usize),
+}
+
+fn collect<N: DomNode>(node: &N, preformatted: bool, chunks: &mut Vec<Chunk>) {
+  match node.kind() {
+    NodeKind::Text => {
+      let text = node.text().unwrap().to_string();
+      chunks.push(if preformatted { Chunk::Literal(text) } else { Chunk::Collapsible(text) });
+    },
+    NodeKind::Element => {
+      let name = node.element_name().unwrap();
+      if name.ns != ns!(html) {
+        for child in node.children() {
+          collect(&child, preformatted, chunks);
+        }
+        return;
+      }
+      let local = &*name.local;
+      if HIDDEN_ELEMENTS.contains(&local) || node.attr("hidden").is_some() {
+        return;
+      }
+      if local == "br" {
+        chunks.push(Chunk::Literal("\n".to_string()));
+        return;
+      }
+      // Table cells on the same row are separated by tabs.
+      if matches!(local, "td" | "th")
+        && node.previous_element_sibling().is_some_and(|sibling| matches!(sibling.element_name().map(|name| &*name.local), Some("td" | "th")))
+      {
+        chunks.push(Chunk::Literal("\t".to_string()));
+      }
+
+      let breaks = match local {
+        "p" => 2,
+        _ if BLOCK_ELEMENTS.contains(&local) => 1,
+        _ => 0,
+      };
+      let preformatted = preformatted || PREFORMATTED_ELEMENTS.contains(&local);
+      if breaks > 0 {
+        chunks.push(Chunk::Break(breaks));
+      }
+      for child in node.children() {
+        collect(&child, preformatted, chunks);
+      }
+      if breaks > 0 {
+        chunks.push(Chunk::Break(breaks));
+      }
+    },
+    NodeKind::Document => {
+      for child in node.children() {
+        collect(&child, preformatted, chunks);
+      }
+    },
+    _ => {},
+  }
+}
+
+/// Joins chunks, holding back spaces and line breaks until the next visible character decides whether they are needed.
+#[derive(Default)]
+struct TextWriter {
+  out: String,
+  pending_breaks: usize,
+  pending_space: bool,
+}
+
+impl TextWriter {
+  fn trim_trailing_spaces(&mut self) {
+    while self.out.ends_with(' ') {
+      self.out.pop();
+    }
+  }
+
+  fn flush(&mut self) {
+    if self.pending_breaks > 0 {
+      if !self.out.is_empty() {
+        self.trim_trailing_spaces();
+        let existing = self.out.len() - self.out.trim_end_matches('\n').len();
+        self.out.push_str(&"\n".repeat(self.pending_breaks.saturating_sub(existing)));
+      }
+    } else if self.pending_space && !self.out.is_empty() && !self.out.ends_with(['\n', '\t', ' ']) {
+      self.out.push(' ');
+    }
+    self.pending_breaks = 0;
+    self.pending_space = false;
+  }
+
+  fn write(&mut self, chunk: Chunk) {
+    match chunk {
+      Chunk::Collapsible(text) => {
+        for c in text.chars() {
+          if c.is_ascii_whitespace() {
+            self.pending_space = true;
+          } else {
+            self.flush();
+            self.out.push(c);
+          }
+        }
+      },
+      Chunk::Literal(text) => {
+        self.pending_space = false;
+        self.flush();
+        if text.starts_with('\n') {
+          self.trim_trailing_spaces();
+        }
+        self.out.push_str(&text);
+      },
+      Chunk::Break(count) => {
+        self.pending_breaks = self.pending_breaks.max(count);
+        self.pending_space = false;
+      },
+    }
+  }
+}
+
+/// The text of `node` roughly as a browser renders it, like `HTMLElement.innerText` without a layout engine: whitespace
+/// collapses, block elements start new lines (paragraphs get a blank line), `<br>` breaks lines, `<pre>` keeps its
+/// whitespace, table cells are separated by tabs, and `<head>`, `<script>`, `<style>`, `<template>` and `hidden`
+/// elements are skipped.
+pub fn inner_text<N: DomNode>(node: &N) -> String {
+  let mut chunks = vec![];
+  collect(node, false, &mut chunks);
+
+  let mut writer = TextWriter::default();
+  for chunk in chunks {
+    writer.write(chunk);
+  }
+  writer.out
+}
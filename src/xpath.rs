@@ -0,0 +1,1413 @@
+use html5ever::{namespace_url, ns, Namespace, QualName};
+use std::{collections::HashMap, fmt};
+use crate::node::{DomNode, NodeKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XPathError {
+  Syntax {
+    message: String,
+    position: usize,
+  },
+  Evaluation(String),
+}
+
+impl fmt::Display for XPathError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      XPathError::Syntax { message, position } => write!(f, "{} at position {}", message, position),
+      XPathError::Evaluation(message) => write!(f, "{}", message),
+    }
+  }
+}
+
+impl std::error::Error for XPathError {}
+
+/// A node in the XPath data model. Attributes are not nodes of the tree, so they are addressed through their element.
+#[derive(Debug, Clone)]
+pub enum XPathNode<N> {
+  Node(N),
+  Attribute {
+    element: N,
+    name: QualName,
+    value: String,
+  },
+}
+
+impl<N: DomNode> XPathNode<N> {
+  /// The XPath string-value of the node.
+  pub fn string_value(&self) -> String {
+    match self {
+      XPathNode::Attribute { value, .. } => value.clone(),
+      XPathNode::Node(node) => match node.kind() {
+        NodeKind::Text => node.text().unwrap().to_string(),
+        NodeKind::Comment | NodeKind::ProcessingInstruction => node.comment().unwrap().to_string(),
+        NodeKind::Doctype => String::new(),
+        NodeKind::Document | NodeKind::Element => node
+          .descendants()
+          .iter()
+          .filter_map(|descendant| descendant.text().map(|text| text.to_string()))
+          .collect(),
+      },
+    }
+  }
+
+  pub fn as_node(&self) -> Option<&N> {
+    match self {
+      XPathNode::Node(node) => Some(node),
+      XPathNode::Attribute { .. } => None,
+    }
+  }
+
+  /// Sort key giving document order: the child index path from the root, with attributes ordered
+  /// after their element and before its children.
+  fn order_key(&self) -> OrderKey {
+    let (node, attribute) = match self {
+      XPathNode::Node(node) => (node, None),
+      XPathNode::Attribute { element, name, .. } => {
+        let index = element
+          .attrs()
+          .and_then(|attrs| attrs.iter().position(|attr| attr.name == *name))
+          .unwrap_or(0);
+        (element, Some(index))
+      },
+    };
+
+    let mut key = vec![];
+    let mut current = node.clone();
+    while let Some(parent) = current.parent() {
+      let index = parent
+        .children()
+        .iter()
+        .position(|child| child.same_node(&current))
+        .unwrap_or(0);
+      key.push((1, index));
+      current = parent;
+    }
+    key.reverse();
+    if let Some(index) = attribute {
+      key.push((0, index));
+    }
+    key
+  }
+}
+
+#[derive(Debug, Clone)]
+pub enum Value<N> {
+  NodeSet(Vec<XPathNode<N>>),
+  String(String),
+  Number(f64),
+  Boolean(bool),
+}
+
+impl<N: DomNode> Value<N> {
+  pub fn string(&self) -> String {
+    match self {
+      Value::NodeSet(nodes) => nodes.first().map(XPathNode::string_value).unwrap_or_default(),
+      Value::String(value) => value.clone(),
+      Value::Number(value) => number_to_string(*value),
+      Value::Boolean(value) => value.to_string(),
+    }
+  }
+
+  pub fn number(&self) -> f64 {
+    match self {
+      Value::Number(value) => *value,
+      Value::Boolean(value) => if *value { 1.0 } else { 0.0 },
+      _ => string_to_number(&self.string()),
+    }
+  }
+
+  pub fn boolean(&self) -> bool {
+    match self {
+      Value::NodeSet(nodes) => !nodes.is_empty(),
+      Value::String(value) => !value.is_empty(),
+      Value::Number(value) => *value != 0.0 && !value.is_nan(),
+      Value::Boolean(value) => *value,
+    }
+  }
+
+  /// The tree nodes of a node-set, leaving out attribute nodes.
+  pub fn nodes(&self) -> Vec<N> {
+    match self {
+      Value::NodeSet(nodes) => nodes.iter().filter_map(|node| node.as_node().cloned()).collect(),
+      _ => vec![],
+    }
+  }
+}
+
+fn number_to_string(value: f64) -> String {
+  if value.is_nan() {
+    "NaN".to_string()
+  } else if value.is_infinite() {
+    if value > 0.0 { "Infinity".to_string() } else { "-Infinity".to_string() }
+  } else if value == value.trunc() && value.abs() < 1e18 {
+    format!("{}", value as i64)
+  } else {
+    format!("{}", value)
+  }
+}
+
+fn string_to_number(value: &str) -> f64 {
+  let trimmed = value.trim_matches(|c: char| c.is_ascii_whitespace());
+  let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+  let is_valid = !digits.is_empty()
+    && digits != "."
+    && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+    && digits.matches('.').count() <= 1;
+  if is_valid {
+    trimmed.parse().unwrap_or(f64::NAN)
+  } else {
+    f64::NAN
+  }
+}
+
+/// A compiled XPath 1.0 expression that can be evaluated against any number of documents.
+///
+/// Unprefixed name tests match elements of any namespace (case-insensitively for HTML elements), so that
+/// `//svg` and `//DIV` behave as they read on parsed HTML. The prefixes `html`, `svg`, `math`, `xlink`,
+/// `xml` and `xmlns` are predefined.
+#[derive(Debug, Clone)]
+pub struct XPath {
+  expr: Expr,
+}
+
+impl XPath {
+  pub fn compile(expression: &str) -> Result<XPath, XPathError> {
+    XPath::compile_with_namespaces(expression, &HashMap::new())
+  }
+
+  pub fn compile_with_namespaces(expression: &str, namespaces: &HashMap<String, Namespace>) -> Result<XPath, XPathError> {
+    let mut prefixes: HashMap<String, Namespace> = HashMap::from([
+      ("html".to_string(), ns!(html)),
+      ("svg".to_string(), ns!(svg)),
+      ("math".to_string(), ns!(mathml)),
+      ("xlink".to_string(), ns!(xlink)),
+      ("xml".to_string(), ns!(xml)),
+      ("xmlns".to_string(), ns!(xmlns)),
+    ]);
+    prefixes.extend(namespaces.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    let mut parser = Parser {
+      tokens: tokenize(expression)?,
+      index: 0,
+      namespaces: &prefixes,
+      length: expression.len(),
+    };
+    let expr = parser.parse_expr()?;
+    if parser.index < parser.tokens.len() {
+      return Err(parser.error("unexpected token"));
+    }
+    Ok(XPath { expr })
+  }
+
+  pub fn evaluate<N: DomNode>(&self, context: &N) -> Result<Value<N>, XPathError> {
+    self.evaluate_with_variables(context, &HashMap::new())
+  }
+
+  /// Evaluates with `$name` variable bindings.
+  pub fn evaluate_with_variables<N: DomNode>(&self, context: &N, variables: &HashMap<String, Value<N>>) -> Result<Value<N>, XPathError> {
+    let context = Context {
+      node: XPathNode::Node(context.clone()),
+      position: 1,
+      size: 1,
+      variables,
+    };
+    evaluate(&self.expr, &context)
+  }
+
+  /// Evaluates an expression that must produce a node-set and returns its tree nodes.
+  pub fn select<N: DomNode>(&self, context: &N) -> Result<Vec<N>, XPathError> {
+    match self.evaluate(context)? {
+      value @ Value::NodeSet(_) => Ok(value.nodes()),
+      _ => Err(XPathError::Evaluation("expression does not evaluate to a node-set".to_string())),
+    }
+  }
+}
+
+pub fn xpath<N: DomNode>(context: &N, expression: &str) -> Result<Value<N>, XPathError> {
+  XPath::compile(expression)?.evaluate(context)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Slash,
+  DoubleSlash,
+  Pipe,
+  Plus,
+  Minus,
+  Equal,
+  NotEqual,
+  Less,
+  LessEqual,
+  Greater,
+  GreaterEqual,
+  LeftParen,
+  RightParen,
+  LeftBracket,
+  RightBracket,
+  Dot,
+  DotDot,
+  At,
+  Comma,
+  ColonColon,
+  Star,
+  // An NCName or QName, including `prefix:*`.
+  Name(String),
+  Literal(String),
+  Number(f64),
+  Variable(String),
+}
+
+fn is_name_start(c: char) -> bool {
+  c.is_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+  is_name_start(c) || c.is_ascii_digit() || c == '-' || c == '.'
+}
+
+fn tokenize(expression: &str) -> Result<Vec<(Token, usize)>, XPathError> {
+  let chars: Vec<(usize, char)> = expression.char_indices().collect();
+  let char_at = |i: usize| chars.get(i).map(|&(_, c)| c);
+  let mut tokens = vec![];
+  let mut i = 0;
+
+  let read_ncname = |i: &mut usize| {
+    let mut name = String::new();
+    while let Some(c) = char_at(*i).filter(|&c| is_name_char(c)) {
+      name.push(c);
+      *i += 1;
+    }
+    name
+  };
+
+  while let Some(c) = char_at(i) {
+    let position = chars[i].0;
+    if c.is_ascii_whitespace() {
+      i += 1;
+      continue;
+    }
+
+    let two = (c, char_at(i + 1));
+    let token = match two {
+      ('/', Some('/')) => { i += 2; Token::DoubleSlash },
+      ('.', Some('.')) => { i += 2; Token::DotDot },
+      ('!', Some('=')) => { i += 2; Token::NotEqual },
+      ('<', Some('=')) => { i += 2; Token::LessEqual },
+      ('>', Some('=')) => { i += 2; Token::GreaterEqual },
+      (':', Some(':')) => { i += 2; Token::ColonColon },
+      ('.', Some(d)) if d.is_ascii_digit() => {
+        let start = i;
+        i += 1;
+        while char_at(i).is_some_and(|c| c.is_ascii_digit()) {
+          i += 1;
+        }
+        let text: String = chars[start..i].iter().map(|&(_, c)| c).collect();
+        Token::Number(text.parse().unwrap())
+      },
+      ('/', _) => { i += 1; Token::Slash },
+      ('|', _) => { i += 1; Token::Pipe },
+      ('+', _) => { i += 1; Token::Plus },
+      ('-', _) => { i += 1; Token::Minus },
+      ('=', _) => { i += 1; Token::Equal },
+      ('<', _) => { i += 1; Token::Less },
+      ('>', _) => { i += 1; Token::Greater },
+      ('(', _) => { i += 1; Token::LeftParen },
+      (')', _) => { i += 1; Token::RightParen },
+      ('[', _) => { i += 1; Token::LeftBracket },
+      (']', _) => { i += 1; Token::RightBracket },
+      ('.', _) => { i += 1; Token::Dot },
+      ('@', _) => { i += 1; Token::At },
+      (',', _) => { i += 1; Token::Comma },
+      ('*', _) => { i += 1; Token::Star },
+      ('"', _) | ('\'', _) => {
+        let start = i + 1;
+        let end = (start..chars.len())
+          .find(|&j| chars[j].1 == c)
+          .ok_or_else(|| XPathError::Syntax {
+            message: "unterminated string literal".to_string(),
+            position,
+          })?;
+        i = end + 1;
+        Token::Literal(chars[start..end].iter().map(|&(_, c)| c).collect())
+      },
+      ('$', _) => {
+        i += 1;
+        let name = read_ncname(&mut i);
+        if name.is_empty() {
+          return Err(XPathError::Syntax {
+            message: "expected variable name".to_string(),
+            position,
+          });
+        }
+        Token::Variable(name)
+      },
+      (d, _) if d.is_ascii_digit() => {
+        let start = i;
+        while char_at(i).is_some_and(|c| c.is_ascii_digit()) {
+          i += 1;
+        }
+        if char_at(i) == Some('.') {
+          i += 1;
+          while char_at(i).is_some_and(|c| c.is_ascii_digit()) {
+            i += 1;
+          }
+        }
+        let text: String = chars[start..i].iter().map(|&(_, c)| c).collect();
+        Token::Number(text.parse().unwrap())
+      },
+      (c, _) if is_name_start(c) => {
+        let mut name = read_ncname(&mut i);
+        // `prefix:local` and `prefix:*`, but not the `::` of an axis.
+        if char_at(i) == Some(':') && char_at(i + 1) != Some(':') {
+          if char_at(i + 1) == Some('*') {
+            name.push_str(":*");
+            i += 2;
+          } else if char_at(i + 1).is_some_and(is_name_start) {
+            i += 1;
+            name.push(':');
+            name.push_str(&read_ncname(&mut i));
+          }
+        }
+        Token::Name(name)
+      },
+      _ => {
+        return Err(XPathError::Syntax {
+          message: format!("unexpected character '{}'", c),
+          position,
+        })
+      },
+    };
+    tokens.push((token, position));
+  }
+  Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+  Ancestor,
+  AncestorOrSelf,
+  Attribute,
+  Child,
+  Descendant,
+  DescendantOrSelf,
+  Following,
+  FollowingSibling,
+  Parent,
+  Preceding,
+  PrecedingSibling,
+  Itself,
+}
+
+impl Axis {
+  fn from_name(name: &str) -> Option<Axis> {
+    Some(match name {
+      "ancestor" => Axis::Ancestor,
+      "ancestor-or-self" => Axis::AncestorOrSelf,
+      "attribute" => Axis::Attribute,
+      "child" => Axis::Child,
+      "descendant" => Axis::Descendant,
+      "descendant-or-self" => Axis::DescendantOrSelf,
+      "following" => Axis::Following,
+      "following-sibling" => Axis::FollowingSibling,
+      "parent" => Axis::Parent,
+      "preceding" => Axis::Preceding,
+      "preceding-sibling" => Axis::PrecedingSibling,
+      "self" => Axis::Itself,
+      _ => return None,
+    })
+  }
+}
+
+#[derive(Debug, Clone)]
+enum NodeTest {
+  Name {
+    // `None` matches any namespace.
+    namespace: Option<Namespace>,
+    // `None` is `*`.
+    local_name: Option<String>,
+  },
+  Node,
+  Text,
+  Comment,
+  ProcessingInstruction(Option<String>),
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+  axis: Axis,
+  test: NodeTest,
+  predicates: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOperator {
+  Or,
+  And,
+  Equal,
+  NotEqual,
+  Less,
+  LessEqual,
+  Greater,
+  GreaterEqual,
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Modulo,
+  Union,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+  Binary(BinaryOperator, Box<Expr>, Box<Expr>),
+  Negate(Box<Expr>),
+  Path {
+    absolute: bool,
+    steps: Vec<Step>,
+  },
+  // A primary expression with predicates, optionally followed by a relative location path.
+  Filter {
+    primary: Box<Expr>,
+    predicates: Vec<Expr>,
+    steps: Vec<Step>,
+  },
+  Literal(String),
+  Number(f64),
+  Variable(String),
+  Function(String, Vec<Expr>),
+}
+
+const FUNCTIONS: &[(&str, usize, Option<usize>)] = &[
+  ("last", 0, Some(0)),
+  ("position", 0, Some(0)),
+  ("count", 1, Some(1)),
+  ("id", 1, Some(1)),
+  ("local-name", 0, Some(1)),
+  ("namespace-uri", 0, Some(1)),
+  ("name", 0, Some(1)),
+  ("string", 0, Some(1)),
+  ("concat", 2, None),
+  ("starts-with", 2, Some(2)),
+  ("contains", 2, Some(2)),
+  ("substring-before", 2, Some(2)),
+  ("substring-after", 2, Some(2)),
+  ("substring", 2, Some(3)),
+  ("string-length", 0, Some(1)),
+  ("normalize-space", 0, Some(1)),
+  ("translate", 3, Some(3)),
+  ("boolean", 1, Some(1)),
+  ("not", 1, Some(1)),
+  ("true", 0, Some(0)),
+  ("false", 0, Some(0)),
+  ("lang", 1, Some(1)),
+  ("number", 0, Some(1)),
+  ("sum", 1, Some(1)),
+  ("floor", 1, Some(1)),
+  ("ceiling", 1, Some(1)),
+  ("round", 1, Some(1)),
+];
+
+struct Parser<'a> {
+  tokens: Vec<(Token, usize)>,
+  index: usize,
+  namespaces: &'a HashMap<String, Namespace>,
+  length: usize,
+}
+
+impl Parser<'_> {
+  fn error(&self, message: &str) -> XPathError {
+    XPathError::Syntax {
+      message: message.to_string(),
+      position: self.tokens.get(self.index).map_or(self.length, |&(_, position)| position),
+    }
+  }
+
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.index).map(|(token, _)| token)
+  }
+
+  fn peek_at(&self, offset: usize) -> Option<&Token> {
+    self.tokens.get(self.index + offset).map(|(token, _)| token)
+  }
+
+  fn eat(&mut self, token: &Token) -> bool {
+    if self.peek() == Some(token) {
+      self.index += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, token: &Token, description: &str) -> Result<(), XPathError> {
+    if self.eat(token) {
+      Ok(())
+    } else {
+      Err(self.error(&format!("expected {}", description)))
+    }
+  }
+
+  fn is_operator_name(&self, name: &str) -> bool {
+    matches!(self.peek(), Some(Token::Name(n)) if n == name)
+  }
+
+  fn parse_expr(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_and()?;
+    while self.is_operator_name("or") {
+      self.index += 1;
+      left = Expr::Binary(BinaryOperator::Or, Box::new(left), Box::new(self.parse_and()?));
+    }
+    Ok(left)
+  }
+
+  fn parse_and(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_equality()?;
+    while self.is_operator_name("and") {
+      self.index += 1;
+      left = Expr::Binary(BinaryOperator::And, Box::new(left), Box::new(self.parse_equality()?));
+    }
+    Ok(left)
+  }
+
+  fn parse_equality(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_relational()?;
+    loop {
+      let operator = match self.peek() {
+        Some(Token::Equal) => BinaryOperator::Equal,
+        Some(Token::NotEqual) => BinaryOperator::NotEqual,
+        _ => return Ok(left),
+      };
+      self.index += 1;
+      left = Expr::Binary(operator, Box::new(left), Box::new(self.parse_relational()?));
+    }
+  }
+
+  fn parse_relational(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_additive()?;
+    loop {
+      let operator = match self.peek() {
+        Some(Token::Less) => BinaryOperator::Less,
+        Some(Token::LessEqual) => BinaryOperator::LessEqual,
+        Some(Token::Greater) => BinaryOperator::Greater,
+        Some(Token::GreaterEqual) => BinaryOperator::GreaterEqual,
+        _ => return Ok(left),
+      };
+      self.index += 1;
+      left = Expr::Binary(operator, Box::new(left), Box::new(self.parse_additive()?));
+    }
+  }
+
+  fn parse_additive(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_multiplicative()?;
+    loop {
+      let operator = match self.peek() {
+        Some(Token::Plus) => BinaryOperator::Add,
+        Some(Token::Minus) => BinaryOperator::Subtract,
+        _ => return Ok(left),
+      };
+      self.index += 1;
+      left = Expr::Binary(operator, Box::new(left), Box::new(self.parse_multiplicative()?));
+    }
+  }
+
+  fn parse_multiplicative(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_unary()?;
+    loop {
+      // In operator position `*`, `div` and `mod` are operators rather than name tests.
+      let operator = match self.peek() {
+        Some(Token::Star) => BinaryOperator::Multiply,
+        Some(Token::Name(name)) if name == "div" => BinaryOperator::Divide,
+        Some(Token::Name(name)) if name == "mod" => BinaryOperator::Modulo,
+        _ => return Ok(left),
+      };
+      self.index += 1;
+      left = Expr::Binary(operator, Box::new(left), Box::new(self.parse_unary()?));
+    }
+  }
+
+  fn parse_unary(&mut self) -> Result<Expr, XPathError> {
+    if self.eat(&Token::Minus) {
+      return Ok(Expr::Negate(Box::new(self.parse_unary()?)));
+    }
+    self.parse_union()
+  }
+
+  fn parse_union(&mut self) -> Result<Expr, XPathError> {
+    let mut left = self.parse_path()?;
+    while self.eat(&Token::Pipe) {
+      left = Expr::Binary(BinaryOperator::Union, Box::new(left), Box::new(self.parse_path()?));
+    }
+    Ok(left)
+  }
+
+  fn is_node_type(name: &str) -> bool {
+    matches!(name, "node" | "text" | "comment" | "processing-instruction")
+  }
+
+  fn starts_filter_expr(&self) -> bool {
+    match self.peek() {
+      Some(Token::Variable(_)) | Some(Token::LeftParen) | Some(Token::Literal(_)) | Some(Token::Number(_)) => true,
+      Some(Token::Name(name)) => self.peek_at(1) == Some(&Token::LeftParen) && !Parser::is_node_type(name),
+      _ => false,
+    }
+  }
+
+  fn starts_step(&self) -> bool {
+    matches!(self.peek(), Some(Token::Name(_)) | Some(Token::Star) | Some(Token::Dot) | Some(Token::DotDot) | Some(Token::At))
+  }
+
+  fn parse_path(&mut self) -> Result<Expr, XPathError> {
+    if self.starts_filter_expr() {
+      let primary = self.parse_primary()?;
+      let predicates = self.parse_predicates()?;
+      let steps = match self.peek() {
+        Some(Token::Slash) | Some(Token::DoubleSlash) => self.parse_relative_steps(false)?,
+        _ => vec![],
+      };
+      if predicates.is_empty() && steps.is_empty() {
+        return Ok(primary);
+      }
+      return Ok(Expr::Filter {
+        primary: Box::new(primary),
+        predicates,
+        steps,
+      });
+    }
+
+    match self.peek() {
+      Some(Token::Slash) => {
+        self.index += 1;
+        let steps = if self.starts_step() {
+          self.parse_relative_steps(true)?
+        } else {
+          vec![]
+        };
+        Ok(Expr::Path { absolute: true, steps })
+      },
+      Some(Token::DoubleSlash) => Ok(Expr::Path {
+        absolute: true,
+        steps: self.parse_relative_steps(false)?,
+      }),
+      _ => Ok(Expr::Path {
+        absolute: false,
+        steps: self.parse_relative_steps(true)?,
+      }),
+    }
+  }
+
+  fn descendant_or_self_step() -> Step {
+    Step {
+      axis: Axis::DescendantOrSelf,
+      test: NodeTest::Node,
+      predicates: vec![],
+    }
+  }
+
+  /// Parses `step ('/' step | '//' step)*`. With `leading_step` false the path starts at a `/` or `//` separator.
+  fn parse_relative_steps(&mut self, leading_step: bool) -> Result<Vec<Step>, XPathError> {
+    let mut steps = vec![];
+    if leading_step {
+      steps.push(self.parse_step()?);
+    }
+    loop {
+      match self.peek() {
+        Some(Token::Slash) => {
+          self.index += 1;
+        },
+        Some(Token::DoubleSlash) => {
+          self.index += 1;
+          steps.push(Parser::descendant_or_self_step());
+        },
+        _ => return Ok(steps),
+      }
+      steps.push(self.parse_step()?);
+    }
+  }
+
+  fn parse_step(&mut self) -> Result<Step, XPathError> {
+    if self.eat(&Token::Dot) {
+      return Ok(Step { axis: Axis::Itself, test: NodeTest::Node, predicates: vec![] });
+    }
+    if self.eat(&Token::DotDot) {
+      return Ok(Step { axis: Axis::Parent, test: NodeTest::Node, predicates: vec![] });
+    }
+
+    let axis = if self.eat(&Token::At) {
+      Axis::Attribute
+    } else if let (Some(Token::Name(name)), Some(Token::ColonColon)) = (self.peek(), self.peek_at(1)) {
+      let axis = Axis::from_name(name).ok_or_else(|| self.error(&format!("unsupported axis '{}'", name)))?;
+      self.index += 2;
+      axis
+    } else {
+      Axis::Child
+    };
+
+    let test = self.parse_node_test()?;
+    let predicates = self.parse_predicates()?;
+    Ok(Step { axis, test, predicates })
+  }
+
+  fn parse_node_test(&mut self) -> Result<NodeTest, XPathError> {
+    let name = match self.peek() {
+      Some(Token::Star) => {
+        self.index += 1;
+        return Ok(NodeTest::Name { namespace: None, local_name: None });
+      },
+      Some(Token::Name(name)) => name.clone(),
+      _ => return Err(self.error("expected node test")),
+    };
+    self.index += 1;
+
+    if Parser::is_node_type(&name) && self.eat(&Token::LeftParen) {
+      let test = match name.as_str() {
+        "node" => NodeTest::Node,
+        "text" => NodeTest::Text,
+        "comment" => NodeTest::Comment,
+        _ => match self.peek() {
+          Some(Token::Literal(target)) => {
+            let target = target.clone();
+            self.index += 1;
+            NodeTest::ProcessingInstruction(Some(target))
+          },
+          _ => NodeTest::ProcessingInstruction(None),
+        },
+      };
+      self.expect(&Token::RightParen, "')'")?;
+      return Ok(test);
+    }
+
+    match name.split_once(':') {
+      Some((prefix, local_name)) => {
+        let namespace = self
+          .namespaces
+          .get(prefix)
+          .cloned()
+          .ok_or_else(|| self.error(&format!("unknown namespace prefix '{}'", prefix)))?;
+        Ok(NodeTest::Name {
+          namespace: Some(namespace),
+          local_name: if local_name == "*" { None } else { Some(local_name.to_string()) },
+        })
+      },
+      None => Ok(NodeTest::Name {
+        namespace: None,
+        local_name: Some(name),
+      }),
+    }
+  }
+
+  fn parse_predicates(&mut self) -> Result<Vec<Expr>, XPathError> {
+    let mut predicates = vec![];
+    while self.eat(&Token::LeftBracket) {
+      predicates.push(self.parse_expr()?);
+      self.expect(&Token::RightBracket, "']'")?;
+    }
+    Ok(predicates)
+  }
+
+  fn parse_primary(&mut self) -> Result<Expr, XPathError> {
+    let token = self.peek().cloned();
+    match token {
+      Some(Token::Variable(name)) => {
+        self.index += 1;
+        Ok(Expr::Variable(name))
+      },
+      Some(Token::Literal(value)) => {
+        self.index += 1;
+        Ok(Expr::Literal(value))
+      },
+      Some(Token::Number(value)) => {
+        self.index += 1;
+        Ok(Expr::Number(value))
+      },
+      Some(Token::LeftParen) => {
+        self.index += 1;
+        let expr = self.parse_expr()?;
+        self.expect(&Token::RightParen, "')'")?;
+        Ok(expr)
+      },
+      Some(Token::Name(name)) => {
+        let &(_, min, max) = FUNCTIONS
+          .iter()
+          .find(|(function, _, _)| *function == name)
+          .ok_or_else(|| self.error(&format!("unknown function '{}'", name)))?;
+        self.index += 2;
+        let mut args = vec![];
+        if !self.eat(&Token::RightParen) {
+          loop {
+            args.push(self.parse_expr()?);
+            if self.eat(&Token::RightParen) {
+              break;
+            }
+            self.expect(&Token::Comma, "',' or ')'")?;
+          }
+        }
+        if args.len() < min || max.is_some_and(|max| args.len() > max) {
+          return Err(self.error(&format!("wrong number of arguments for '{}'", name)));
+        }
+        Ok(Expr::Function(name, args))
+      },
+      _ => Err(self.error("expected expression")),
+    }
+  }
+}
+
+struct Context<'a, N> {
+  node: XPathNode<N>,
+  position: usize,
+  size: usize,
+  variables: &'a HashMap<String, Value<N>>,
+}
+
+impl<'a, N: DomNode> Context<'a, N> {
+  fn with_node(&self, node: XPathNode<N>, position: usize, size: usize) -> Context<'a, N> {
+    Context {
+      node,
+      position,
+      size,
+      variables: self.variables,
+    }
+  }
+}
+
+type OrderKey = Vec<(u8, usize)>;
+
+fn sort_document_order<N: DomNode>(nodes: Vec<XPathNode<N>>) -> Vec<XPathNode<N>> {
+  let mut keyed: Vec<(OrderKey, XPathNode<N>)> = nodes.into_iter().map(|node| (node.order_key(), node)).collect();
+  keyed.sort_by(|a, b| a.0.cmp(&b.0));
+  keyed.dedup_by(|a, b| a.0 == b.0);
+  keyed.into_iter().map(|(_, node)| node).collect()
+}
+
+fn root_of<N: DomNode>(node: &XPathNode<N>) -> N {
+  let mut current = match node {
+    XPathNode::Node(node) => node.clone(),
+    XPathNode::Attribute { element, .. } => element.clone(),
+  };
+  while let Some(parent) = current.parent() {
+    current = parent;
+  }
+  current
+}
+
+fn evaluate<N: DomNode>(expr: &Expr, context: &Context<N>) -> Result<Value<N>, XPathError> {
+  match expr {
+    Expr::Literal(value) => Ok(Value::String(value.clone())),
+    Expr::Number(value) => Ok(Value::Number(*value)),
+    Expr::Variable(name) => context
+      .variables
+      .get(name)
+      .cloned()
+      .ok_or_else(|| XPathError::Evaluation(format!("unbound variable '${}'", name))),
+    Expr::Negate(operand) => Ok(Value::Number(-evaluate(operand, context)?.number())),
+    Expr::Binary(operator, left, right) => evaluate_binary(*operator, left, right, context),
+    Expr::Function(name, args) => evaluate_function(name, args, context),
+    Expr::Path { absolute, steps } => {
+      let start = if *absolute {
+        XPathNode::Node(root_of(&context.node))
+      } else {
+        context.node.clone()
+      };
+      Ok(Value::NodeSet(evaluate_steps(vec![start], steps, context)?))
+    },
+    Expr::Filter { primary, predicates, steps } => {
+      let mut nodes = match evaluate(primary, context)? {
+        Value::NodeSet(nodes) => nodes,
+        value if predicates.is_empty() && steps.is_empty() => return Ok(value),
+        _ => return Err(XPathError::Evaluation("predicates and paths can only be applied to node-sets".to_string())),
+      };
+      for predicate in predicates {
+        nodes = filter_predicate(nodes, predicate, context)?;
+      }
+      Ok(Value::NodeSet(evaluate_steps(nodes, steps, context)?))
+    },
+  }
+}
+
+fn evaluate_steps<N: DomNode>(mut nodes: Vec<XPathNode<N>>, steps: &[Step], context: &Context<N>) -> Result<Vec<XPathNode<N>>, XPathError> {
+  for step in steps {
+    let mut next = vec![];
+    for node in &nodes {
+      let mut candidates: Vec<XPathNode<N>> = axis_nodes(node, step.axis)
+        .into_iter()
+        .filter(|candidate| matches_node_test(candidate, &step.test, step.axis))
+        .collect();
+      for predicate in &step.predicates {
+        candidates = filter_predicate(candidates, predicate, context)?;
+      }
+      next.extend(candidates);
+    }
+    nodes = sort_document_order(next);
+  }
+  Ok(nodes)
+}
+
+fn filter_predicate<N: DomNode>(nodes: Vec<XPathNode<N>>, predicate: &Expr, context: &Context<N>) -> Result<Vec<XPathNode<N>>, XPathError> {
+  let size = nodes.len();
+  let mut result = vec![];
+  for (index, node) in nodes.into_iter().enumerate() {
+    let inner = context.with_node(node.clone(), index + 1, size);
+    let keep = match evaluate(predicate, &inner)? {
+      Value::Number(number) => number == (index + 1) as f64,
+      value => value.boolean(),
+    };
+    if keep {
+      result.push(node);
+    }
+  }
+  Ok(result)
+}
+
+fn is_in_data_model<N: DomNode>(node: &N) -> bool {
+  node.kind() != NodeKind::Doctype
+}
+
+fn descendants_of<N: DomNode>(node: &N) -> Vec<XPathNode<N>> {
+  node
+    .descendants()
+    .into_iter()
+    .skip(1)
+    .filter(is_in_data_model)
+    .map(XPathNode::Node)
+    .collect()
+}
+
+fn following_siblings<N: DomNode>(node: &N) -> Vec<N> {
+  let mut result = vec![];
+  let mut current = node.next_sibling();
+  while let Some(sibling) = current {
+    current = sibling.next_sibling();
+    result.push(sibling);
+  }
+  result
+}
+
+fn preceding_siblings<N: DomNode>(node: &N) -> Vec<N> {
+  let mut result = vec![];
+  let mut current = node.previous_sibling();
+  while let Some(sibling) = current {
+    current = sibling.previous_sibling();
+    result.push(sibling);
+  }
+  result
+}
+
+/// Nodes on `axis` from `node`, in axis order (reverse document order for the reverse axes).
+fn axis_nodes<N: DomNode>(node: &XPathNode<N>, axis: Axis) -> Vec<XPathNode<N>> {
+  let tree_node = match node {
+    XPathNode::Node(tree_node) => tree_node,
+    XPathNode::Attribute { element, .. } => {
+      return match axis {
+        Axis::Itself => vec![node.clone()],
+        Axis::Parent => vec![XPathNode::Node(element.clone())],
+        Axis::Ancestor | Axis::AncestorOrSelf => {
+          let mut result = if axis == Axis::AncestorOrSelf { vec![node.clone()] } else { vec![] };
+          result.push(XPathNode::Node(element.clone()));
+          result.extend(axis_nodes(&XPathNode::Node(element.clone()), Axis::Ancestor));
+          result
+        },
+        Axis::DescendantOrSelf => vec![node.clone()],
+        Axis::Following => {
+          let mut result = descendants_of(element);
+          result.extend(axis_nodes(&XPathNode::Node(element.clone()), Axis::Following));
+          result
+        },
+        Axis::Preceding => axis_nodes(&XPathNode::Node(element.clone()), Axis::Preceding),
+        _ => vec![],
+      };
+    },
+  };
+
+  match axis {
+    Axis::Itself => vec![node.clone()],
+    Axis::Child => tree_node
+      .children()
+      .into_iter()
+      .filter(is_in_data_model)
+      .map(XPathNode::Node)
+      .collect(),
+    Axis::Descendant => descendants_of(tree_node),
+    Axis::DescendantOrSelf => {
+      let mut result = vec![node.clone()];
+      result.extend(descendants_of(tree_node));
+      result
+    },
+    Axis::Parent => tree_node.parent().map(XPathNode::Node).into_iter().collect(),
+    Axis::Ancestor | Axis::AncestorOrSelf => {
+      let mut result = if axis == Axis::AncestorOrSelf { vec![node.clone()] } else { vec![] };
+      let mut current = tree_node.parent();
+      while let Some(ancestor) = current {
+        current = ancestor.parent();
+        result.push(XPathNode::Node(ancestor));
+      }
+      result
+    },
+    Axis::FollowingSibling => following_siblings(tree_node)
+      .into_iter()
+      .filter(is_in_data_model)
+      .map(XPathNode::Node)
+      .collect(),
+    Axis::PrecedingSibling => preceding_siblings(tree_node)
+      .into_iter()
+      .filter(is_in_data_model)
+      .map(XPathNode::Node)
+      .collect(),
+    Axis::Following => {
+      let mut result = vec![];
+      let mut current = Some(tree_node.clone());
+      while let Some(ancestor) = current {
+        for sibling in following_siblings(&ancestor) {
+          result.extend(sibling.descendants().into_iter().filter(is_in_data_model).map(XPathNode::Node));
+        }
+        current = ancestor.parent();
+      }
+      result
+    },
+    Axis::Preceding => {
+      let mut result = vec![];
+      let mut current = Some(tree_node.clone());
+      while let Some(ancestor) = current {
+        for sibling in preceding_siblings(&ancestor) {
+          result.extend(sibling.descendants().into_iter().rev().filter(is_in_data_model).map(XPathNode::Node));
+        }
+        current = ancestor.parent();
+      }
+      result
+    },
+    Axis::Attribute => {
+      let element = tree_node.clone();
+      match tree_node.attrs() {
+        Some(attrs) => attrs
+          .iter()
+          .filter(|attr| attr.name.ns != ns!(xmlns) && &*attr.name.local != "xmlns")
+          .map(|attr| XPathNode::Attribute {
+            element: element.clone(),
+            name: attr.name.clone(),
+            value: attr.value.to_string(),
+          })
+          .collect(),
+        None => vec![],
+      }
+    },
+  }
+}
+
+fn matches_node_test<N: DomNode>(node: &XPathNode<N>, test: &NodeTest, axis: Axis) -> bool {
+  match test {
+    NodeTest::Node => true,
+    NodeTest::Text => node.as_node().is_some_and(|node| node.kind() == NodeKind::Text),
+    NodeTest::Comment => node.as_node().is_some_and(|node| node.kind() == NodeKind::Comment),
+    NodeTest::ProcessingInstruction(target) => node.as_node().is_some_and(|node| {
+      node.kind() == NodeKind::ProcessingInstruction
+        && target.as_ref().is_none_or(|target| node.processing_instruction_target().is_some_and(|t| **t == **target))
+    }),
+    NodeTest::Name { namespace, local_name } => {
+      let (name, is_html) = match (node, axis) {
+        (XPathNode::Attribute { name, element, .. }, Axis::Attribute) => {
+          let is_html = element.element_name().is_some_and(|name| name.ns == ns!(html));
+          // Unprefixed attribute names only match attributes without a namespace.
+          if namespace.is_none() && name.ns != ns!() {
+            return false;
+          }
+          (name.clone(), is_html)
+        },
+        (XPathNode::Node(node), axis) if axis != Axis::Attribute => match node.element_name() {
+          Some(name) => (name.clone(), name.ns == ns!(html)),
+          None => return false,
+        },
+        _ => return false,
+      };
+      if namespace.as_ref().is_some_and(|namespace| *namespace != name.ns) {
+        return false;
+      }
+      match local_name {
+        None => true,
+        Some(local_name) if is_html => local_name.eq_ignore_ascii_case(&name.local),
+        Some(local_name) => **local_name == *name.local,
+      }
+    },
+  }
+}
+
+fn compare_values<N: DomNode>(operator: BinaryOperator, left: &Value<N>, right: &Value<N>) -> bool {
+  let compare_numbers = |a: f64, b: f64| match operator {
+    BinaryOperator::Equal => a == b,
+    BinaryOperator::NotEqual => a != b,
+    BinaryOperator::Less => a < b,
+    BinaryOperator::LessEqual => a <= b,
+    BinaryOperator::Greater => a > b,
+    BinaryOperator::GreaterEqual => a >= b,
+    _ => unreachable!(),
+  };
+  let is_equality = matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual);
+  let compare_strings = |a: &str, b: &str| {
+    if is_equality {
+      (a == b) == (operator == BinaryOperator::Equal)
+    } else {
+      compare_numbers(string_to_number(a), string_to_number(b))
+    }
+  };
+
+  match (left, right) {
+    (Value::NodeSet(left), Value::NodeSet(right)) => {
+      let right_strings: Vec<String> = right.iter().map(XPathNode::string_value).collect();
+      left.iter().any(|l| {
+        let l = l.string_value();
+        right_strings.iter().any(|r| compare_strings(&l, r))
+      })
+    },
+    (Value::NodeSet(nodes), other) | (other, Value::NodeSet(nodes)) => {
+      let node_on_left = matches!(left, Value::NodeSet(_));
+      match other {
+        Value::Boolean(b) => {
+          let n = !nodes.is_empty();
+          if node_on_left { compare_numbers(n as u8 as f64, *b as u8 as f64) } else { compare_numbers(*b as u8 as f64, n as u8 as f64) }
+        },
+        Value::Number(number) => nodes.iter().any(|node| {
+          let n = string_to_number(&node.string_value());
+          if node_on_left { compare_numbers(n, *number) } else { compare_numbers(*number, n) }
+        }),
+        _ => {
+          let s = other.string();
+          nodes.iter().any(|node| {
+            let n = node.string_value();
+            if node_on_left { compare_strings(&n, &s) } else { compare_strings(&s, &n) }
+          })
+        },
+      }
+    },
+    _ if is_equality => {
+      if matches!(left, Value::Boolean(_)) || matches!(right, Value::Boolean(_)) {
+        compare_numbers(left.boolean() as u8 as f64, right.boolean() as u8 as f64)
+      } else if matches!(left, Value::Number(_)) || matches!(right, Value::Number(_)) {
+        compare_numbers(left.number(), right.number())
+      } else {
+        compare_strings(&left.string(), &right.string())
+      }
+    },
+    _ => compare_numbers(left.number(), right.number()),
+  }
+}
+
+fn evaluate_binary<N: DomNode>(operator: BinaryOperator, left: &Expr, right: &Expr, context: &Context<N>) -> Result<Value<N>, XPathError> {
+  match operator {
+    BinaryOperator::Or => {
+      Ok(Value::Boolean(evaluate(left, context)?.boolean() || evaluate(right, context)?.boolean()))
+    },
+    BinaryOperator::And => {
+      Ok(Value::Boolean(evaluate(left, context)?.boolean() && evaluate(right, context)?.boolean()))
+    },
+    BinaryOperator::Union => match (evaluate(left, context)?, evaluate(right, context)?) {
+      (Value::NodeSet(mut left), Value::NodeSet(right)) => {
+        left.extend(right);
+        Ok(Value::NodeSet(sort_document_order(left)))
+      },
+      _ => Err(XPathError::Evaluation("'|' requires node-sets on both sides".to_string())),
+    },
+    BinaryOperator::Equal
+    | BinaryOperator::NotEqual
+    | BinaryOperator::Less
+    | BinaryOperator::LessEqual
+    | BinaryOperator::Greater
+    | BinaryOperator::GreaterEqual => {
+      let left = evaluate(left, context)?;
+      let right = evaluate(right, context)?;
+      Ok(Value::Boolean(compare_values(operator, &left, &right)))
+    },
+    BinaryOperator::Add
+    | BinaryOperator::Subtract
+    | BinaryOperator::Multiply
+    | BinaryOperator::Divide
+    | BinaryOperator::Modulo => {
+      let left = evaluate(left, context)?.number();
+      let right = evaluate(right, context)?.number();
+      Ok(Value::Number(match operator {
+        BinaryOperator::Add => left + right,
+        BinaryOperator::Subtract => left - right,
+        BinaryOperator::Multiply => left * right,
+        BinaryOperator::Divide => left / right,
+        _ => left % right,
+      }))
+    },
+  }
+}
+
+fn node_set_arg<N: DomNode>(arg: &Expr, context: &Context<N>, function: &str) -> Result<Vec<XPathNode<N>>, XPathError> {
+  match evaluate(arg, context)? {
+    Value::NodeSet(nodes) => Ok(nodes),
+    _ => Err(XPathError::Evaluation(format!("'{}' requires a node-set argument", function))),
+  }
+}
+
+/// The first node of the optional node-set argument, or the context node when there is none.
+fn optional_node_arg<N: DomNode>(args: &[Expr], context: &Context<N>, function: &str) -> Result<Option<XPathNode<N>>, XPathError> {
+  match args.first() {
+    Some(arg) => Ok(node_set_arg(arg, context, function)?.into_iter().next()),
+    None => Ok(Some(context.node.clone())),
+  }
+}
+
+fn qualified_name_of<N: DomNode>(node: &XPathNode<N>) -> Option<QualName> {
+  match node {
+    XPathNode::Attribute { name, .. } => Some(name.clone()),
+    XPathNode::Node(node) => node.element_name().cloned(),
+  }
+}
+
+fn string_arg_or_context<N: DomNode>(args: &[Expr], context: &Context<N>) -> Result<String, XPathError> {
+  match args.first() {
+    Some(arg) => Ok(evaluate(arg, context)?.string()),
+    None => Ok(context.node.string_value()),
+  }
+}
+
+fn xpath_round(value: f64) -> f64 {
+  if value.is_nan() || value.is_infinite() {
+    value
+  } else if (-0.5..0.0).contains(&value) {
+    -0.0
+  } else {
+    (value + 0.5).floor()
+  }
+}
+
+fn evaluate_function<N: DomNode>(name: &str, args: &[Expr], context: &Context<N>) -> Result<Value<N>, XPathError> {
+  let string = |index: usize| -> Result<String, XPathError> { Ok(evaluate(&args[index], context)?.string()) };
+  let number = |index: usize| -> Result<f64, XPathError> { Ok(evaluate(&args[index], context)?.number()) };
+
+  Ok(match name {
+    "last" => Value::Number(context.size as f64),
+    "position" => Value::Number(context.position as f64),
+    "count" => Value::Number(node_set_arg(&args[0], context, name)?.len() as f64),
+    "id" => {
+      let ids: Vec<String> = match evaluate(&args[0], context)? {
+        Value::NodeSet(nodes) => nodes
+          .iter()
+          .flat_map(|node| node.string_value().split_ascii_whitespace().map(str::to_string).collect::<Vec<_>>())
+          .collect(),
+        value => value.string().split_ascii_whitespace().map(str::to_string).collect(),
+      };
+      let root = root_of(&context.node);
+      let nodes = root
+        .descendants()
+        .into_iter()
+        .filter(|node| node.attr("id").is_some_and(|id| ids.contains(&id)))
+        .map(XPathNode::Node)
+        .collect();
+      Value::NodeSet(nodes)
+    },
+    "local-name" => Value::String(
+      optional_node_arg(args, context, name)?
+        .as_ref()
+        .and_then(qualified_name_of)
+        .map(|name| name.local.to_string())
+        .unwrap_or_default(),
+    ),
+    "namespace-uri" => Value::String(
+      optional_node_arg(args, context, name)?
+        .as_ref()
+        .and_then(qualified_name_of)
+        .map(|name| name.ns.to_string())
+        .unwrap_or_default(),
+    ),
+    "name" => Value::String(
+      optional_node_arg(args, context, name)?
+        .as_ref()
+        .and_then(qualified_name_of)
+        .map(|name| match name.prefix {
+          Some(prefix) => format!("{}:{}", prefix, name.local),
+          None => name.local.to_string(),
+        })
+        .unwrap_or_default(),
+    ),
+    "string" => Value::String(string_arg_or_context(args, context)?),
+    "concat" => {
+      let mut result = String::new();
+      for index in 0..args.len() {
+        result.push_str(&string(index)?);
+      }
+      Value::String(result)
+    },
+    "starts-with" => Value::Boolean(string(0)?.starts_with(&string(1)?)),
+    "contains" => Value::Boolean(string(0)?.contains(&string(1)?)),
+    "substring-before" => {
+      let value = string(0)?;
+      Value::String(value.split_once(&string(1)?).map(|(before, _)| before.to_string()).unwrap_or_default())
+    },
+    "substring-after" => {
+      let value = string(0)?;
+      Value::String(value.split_once(&string(1)?).map(|(_, after)| after.to_string()).unwrap_or_default())
+    },
+    "substring" => {
+      let chars: Vec<char> = string(0)?.chars().collect();
+      let start = xpath_round(number(1)?);
+      let end = if args.len() > 2 {
+        start + xpath_round(number(2)?)
+      } else {
+        f64::INFINITY
+      };
+      // Character positions are 1-based; NaN comparisons drop every character.
+      let result = chars
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| {
+          let position = (index + 1) as f64;
+          position >= start && position < end
+        })
+        .map(|(_, c)| *c)
+        .collect();
+      Value::String(result)
+    },
+    "string-length" => Value::Number(string_arg_or_context(args, context)?.chars().count() as f64),
+    "normalize-space" => Value::String(
+      string_arg_or_context(args, context)?
+        .split_ascii_whitespace()
+        .collect::<Vec<_>>()
+        .join(" "),
+    ),
+    "translate" => {
+      let from: Vec<char> = string(1)?.chars().collect();
+      let to: Vec<char> = string(2)?.chars().collect();
+      let result = string(0)?
+        .chars()
+        .filter_map(|c| match from.iter().position(|&f| f == c) {
+          Some(index) => to.get(index).copied(),
+          None => Some(c),
+        })
+        .collect();
+      Value::String(result)
+    },
+    "boolean" => Value::Boolean(evaluate(&args[0], context)?.boolean()),
+    "not" => Value::Boolean(!evaluate(&args[0], context)?.boolean()),
+    "true" => Value::Boolean(true),
+    "false" => Value::Boolean(false),
+    "lang" => {
+      let expected = string(0)?.to_ascii_lowercase();
+      let lang = axis_nodes(&context.node, Axis::AncestorOrSelf)
+        .iter()
+        .filter_map(|node| node.as_node().cloned())
+        .find_map(|node| {
+          let attrs = node.attrs()?;
+          attrs
+            .iter()
+            .find(|attr| &*attr.name.local == "lang" && (attr.name.ns == ns!(xml) || attr.name.ns == ns!()))
+            .map(|attr| attr.value.to_ascii_lowercase())
+        });
+      Value::Boolean(lang.is_some_and(|lang| lang == expected || lang.starts_with(&format!("{}-", expected))))
+    },
+    "number" => Value::Number(match args.first() {
+      Some(_) => number(0)?,
+      None => string_to_number(&context.node.string_value()),
+    }),
+    "sum" => Value::Number(
+      node_set_arg(&args[0], context, name)?
+        .iter()
+        .map(|node| string_to_number(&node.string_value()))
+        .sum(),
+    ),
+    "floor" => Value::Number(number(0)?.floor()),
+    "ceiling" => Value::Number(number(0)?.ceil()),
+    "round" => Value::Number(xpath_round(number(0)?)),
+    _ => return Err(XPathError::Evaluation(format!("unknown function '{}'", name))),
+  })
+}
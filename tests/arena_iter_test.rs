@@ -0,0 +1,66 @@
+use rust_html5ever_sample::arena::{ArenaDom, NodeData, NodeEdge, Ref};
+
+fn tag_name(node: Ref) -> String {
+  match node.data {
+    NodeData::Element { ref name, .. } => name.local.to_string(),
+    NodeData::Text { .. } => "#text".to_string(),
+    NodeData::Document => "#document".to_string(),
+    _ => "#other".to_string(),
+  }
+}
+
+fn find<'arena>(root: Ref<'arena>, name: &str) -> Ref<'arena> {
+  root.descendants().find(|node| tag_name(node) == name).unwrap()
+}
+
+#[test]
+fn arena_children_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<ul><li>a</li><li>b</li><li>c</li></ul>");
+  let ul = find(document, "ul");
+
+  assert_eq!(ul.children().count(), 3);
+  assert_eq!(ul.reverse_children().count(), 3);
+  assert_eq!(ul.children().rev().count(), 3);
+
+  let mut children = ul.children();
+  assert!(std::ptr::eq(children.next().unwrap(), ul.first_child.get().unwrap()));
+  assert!(std::ptr::eq(children.next_back().unwrap(), ul.last_child.get().unwrap()));
+  assert_eq!(children.count(), 1);
+
+  // 여러 번 읽어도 트리가 유지되어야 한다.
+  assert_eq!(ul.children().count(), 3);
+}
+
+#[test]
+fn arena_siblings_and_ancestors_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<ul><li>a</li><li id=\"b\">b</li><li>c</li></ul>");
+  let ul = find(document, "ul");
+  let middle = ul.children().nth(1).unwrap();
+
+  assert_eq!(middle.preceding_siblings().count(), 1);
+  assert_eq!(middle.following_siblings().count(), 1);
+
+  let ancestors: Vec<String> = middle.ancestors().map(tag_name).collect();
+  assert_eq!(ancestors, vec!["ul", "body", "html", "#document"]);
+}
+
+#[test]
+fn arena_traverse_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<p><b>x</b></p>");
+  let p = find(document, "p");
+
+  let edges: Vec<String> = p
+    .traverse()
+    .map(|edge| match edge {
+      NodeEdge::Start(node) => format!("<{}>", tag_name(node)),
+      NodeEdge::End(node) => format!("</{}>", tag_name(node)),
+    })
+    .collect();
+  assert_eq!(edges, vec!["<p>", "<b>", "<#text>", "</#text>", "</b>", "</p>"]);
+
+  let descendants: Vec<String> = document.descendants().map(tag_name).collect();
+  assert_eq!(descendants, vec!["#document", "html", "head", "body", "p", "b", "#text"]);
+}
@@ -0,0 +1,92 @@
+use html5ever::{parse_document, serialize::{serialize, Serialize, SerializeOpts, TraversalScope}, tendril::TendrilSink};
+use markup5ever_rcdom::{RcDom, SerializableHandle};
+use rust_html5ever_sample::arena::{ArenaDom, HtmlNode, NodeData, Ref};
+
+const HTML: &str = r#"
+  <!DOCTYPE html>
+  <html>
+    <head>
+      <title>테스트></title>
+    </head>
+    <body id="[##_id_##]">
+      <!-- 주석 -->
+      <s3>
+        테스트 !!! &amp; <b class="x">굵게</b>
+      </s3>
+      <my-element>
+        안녕하세요~ ^^
+      </my-element>
+      <svg><circle r="1"/></svg>
+    <body>
+  </html>
+"#;
+
+fn arena_to_string(node: &impl Serialize, traversal_scope: TraversalScope) -> String {
+  let mut bytes = vec![];
+  let opts = SerializeOpts { traversal_scope, ..Default::default() };
+  serialize(&mut bytes, node, opts).unwrap();
+  String::from_utf8(bytes).unwrap()
+}
+
+fn rcdom_to_string(html: &str) -> String {
+  let dom = parse_document(RcDom::default(), Default::default()).one(html);
+  let handle: SerializableHandle = dom.document.clone().into();
+  let mut bytes = vec![];
+  serialize(&mut bytes, &handle, SerializeOpts::default()).unwrap();
+  String::from_utf8(bytes).unwrap()
+}
+
+fn find<'arena>(root: Ref<'arena>, tag: &str) -> Ref<'arena> {
+  root
+    .descendants()
+    .find(|node| matches!(node.data, NodeData::Element { ref name, .. } if &*name.local == tag))
+    .unwrap()
+}
+
+#[test]
+fn arena_serialize_matches_rcdom_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+
+  let result = arena_to_string(document, TraversalScope::ChildrenOnly(None));
+  assert_eq!(result, rcdom_to_string(HTML));
+
+  // 직렬화가 트리를 망가뜨리지 않으므로 두 번째 결과도 같아야 한다.
+  assert_eq!(arena_to_string(document, TraversalScope::ChildrenOnly(None)), result);
+}
+
+#[test]
+fn arena_serialize_traversal_scope_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<ul id=\"list\"><li>a</li><li>b</li></ul>");
+  let ul = find(document, "ul");
+
+  assert_eq!(arena_to_string(ul, TraversalScope::IncludeNode), "<ul id=\"list\"><li>a</li><li>b</li></ul>");
+  assert_eq!(arena_to_string(ul, TraversalScope::ChildrenOnly(None)), "<li>a</li><li>b</li>");
+}
+
+#[test]
+fn arena_serialize_template_contents_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<template><p>안녕</p></template>");
+  let template = find(document, "template");
+
+  // `SerializableHandle`처럼 템플릿 내용은 빠진다.
+  let html = "<template><p>안녕</p></template>";
+  assert_eq!(arena_to_string(document, TraversalScope::ChildrenOnly(None)), rcdom_to_string(html));
+  assert_eq!(arena_to_string(template, TraversalScope::IncludeNode), "<template></template>");
+  assert_eq!(arena_to_string(template, TraversalScope::ChildrenOnly(None)), "");
+
+  // HtmlNode로 감싸면 브라우저의 outerHTML, innerHTML처럼 내용까지 쓴다.
+  assert_eq!(arena_to_string(&HtmlNode(template), TraversalScope::IncludeNode), "<template><p>안녕</p></template>");
+  assert_eq!(arena_to_string(&HtmlNode(template), TraversalScope::ChildrenOnly(None)), "<p>안녕</p>");
+}
+
+#[test]
+fn arena_serialize_document_include_node_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+
+  // `SerializableHandle`은 문서 자체를 직렬화하면 패닉하지만 여기서는 자식들을 쓴다.
+  assert_eq!(arena_to_string(document, TraversalScope::IncludeNode), rcdom_to_string(HTML));
+}
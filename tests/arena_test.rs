@@ -0,0 +1,31 @@
+use html5ever::{local_name, namespace_url, ns, QualName};
+use rust_html5ever_sample::arena::{ArenaDom, NodeData};
+
+#[test]
+fn arena_parse_document_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<!DOCTYPE html><p id=\"a\">안녕</p>");
+
+  assert!(matches!(document.data, NodeData::Document));
+  assert!(matches!(document.first_child.get().unwrap().data, NodeData::Doctype { .. }));
+
+  let html = document.last_child.get().unwrap();
+  match html.data {
+    NodeData::Element { ref name, .. } => assert_eq!(name.local, local_name!("html")),
+    _ => panic!("expected <html>"),
+  }
+}
+
+#[test]
+fn arena_parse_fragment_test() {
+  let arena = ArenaDom::new();
+  let context = QualName::new(None, ns!(html), local_name!("tbody"));
+  let document = arena.parse_fragment(context, vec![], "<tr><td>1</td></tr>");
+
+  let html = document.first_child.get().unwrap();
+  let tr = html.first_child.get().unwrap();
+  match tr.data {
+    NodeData::Element { ref name, .. } => assert_eq!(name.local, local_name!("tr")),
+    _ => panic!("expected <tr>"),
+  }
+}
@@ -0,0 +1,119 @@
+use html5ever::{namespace_url, ns, parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, attributes::Attributes, rcdom::{add_attr, modify_attr, outer_html}, select::select_one};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html lang="ko">
+  <body id="[##_id_##]">
+    <div id="card" class="card  big card" data-user-id="7" data-x="1">안녕하세요</div>
+    <svg><a xlink:href="/home" xml:lang="ko"></a></svg>
+  </body>
+</html>
+"#;
+
+#[test]
+fn attributes_get_set_remove_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let div = select_one(&dom.document, "#card").unwrap().unwrap();
+
+  assert_eq!(div.get_attr("ID").as_deref(), Some("card"));
+  assert!(div.has_attr("data-x"));
+  assert!(!div.has_attr("title"));
+
+  div.set_attr("Title", "제목");
+  div.set_attr("id", "card2");
+  assert_eq!(div.get_attr("title").as_deref(), Some("제목"));
+  assert!(outer_html(&div).starts_with("<div id=\"card2\" class=\"card  big card\" data-user-id=\"7\" data-x=\"1\" title=\"제목\">"));
+
+  assert!(div.remove_attr("title"));
+  assert!(!div.remove_attr("title"));
+
+  assert!(div.toggle_attr("hidden", None));
+  assert_eq!(div.get_attr("hidden").as_deref(), Some(""));
+  assert!(div.toggle_attr("hidden", Some(true)));
+  assert!(!div.toggle_attr("hidden", None));
+  assert!(!div.toggle_attr("hidden", Some(false)));
+
+  let text = div.children.borrow()[0].clone();
+  assert_eq!(text.get_attr("id"), None);
+  text.set_attr("id", "x");
+  assert!(!text.toggle_attr("id", None));
+}
+
+#[test]
+fn attributes_legacy_helpers_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let div = select_one(&dom.document, "#card").unwrap().unwrap();
+
+  add_attr(&div, "id", "ignored");
+  add_attr(&div, "role", "region");
+  modify_attr(&div, "data-x", "2", false);
+  modify_attr(&div, "lang", "en", false);
+  modify_attr(&div, "dir", "ltr", true);
+
+  assert_eq!(div.get_attr("id").as_deref(), Some("card"));
+  assert_eq!(div.get_attr("role").as_deref(), Some("region"));
+  assert_eq!(div.get_attr("data-x").as_deref(), Some("2"));
+  assert_eq!(div.get_attr("lang"), None);
+  assert_eq!(div.get_attr("dir").as_deref(), Some("ltr"));
+}
+
+#[test]
+fn attributes_namespaced_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let a = select_one(&dom.document, "svg a").unwrap().unwrap();
+
+  assert_eq!(a.get_attr("xlink:href").as_deref(), Some("/home"));
+  assert_eq!(a.get_attr("href"), None);
+  assert_eq!(a.get_attr_ns(&ns!(xlink), "href").as_deref(), Some("/home"));
+  assert_eq!(a.get_attr_ns(&ns!(xml), "lang").as_deref(), Some("ko"));
+  assert!(!a.has_attr_ns(&ns!(), "href"));
+
+  a.set_attr_ns(&ns!(xlink), "xlink:href", "/about");
+  a.set_attr_ns(&ns!(xlink), "xlink:title", "소개");
+  assert_eq!(a.get_attr("xlink:href").as_deref(), Some("/about"));
+  assert_eq!(a.get_attr("xlink:title").as_deref(), Some("소개"));
+  assert_eq!(outer_html(&a), "<a xlink:href=\"/about\" xml:lang=\"ko\" xlink:title=\"소개\"></a>");
+
+  assert!(a.remove_attr_ns(&ns!(xml), "lang"));
+  assert!(!a.has_attr("xml:lang"));
+}
+
+#[test]
+fn attributes_class_list_and_dataset_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let div = select_one(&dom.document, "#card").unwrap().unwrap();
+
+  let classes = div.class_list();
+  assert_eq!(classes.values(), ["card", "big"]);
+  assert!(classes.contains("big"));
+  classes.add("new");
+  classes.remove("card");
+  assert!(!classes.toggle("big"));
+  assert!(classes.replace("new", "fresh"));
+  assert_eq!(div.get_attr("class").as_deref(), Some("fresh"));
+  assert_eq!(classes.len(), 1);
+
+  let dataset = div.dataset();
+  assert_eq!(dataset.get("userId").as_deref(), Some("7"));
+  dataset.set("createdAt", "2024");
+  assert_eq!(div.get_attr("data-created-at").as_deref(), Some("2024"));
+  assert!(dataset.remove("x"));
+  assert_eq!(dataset.entries(), [("userId".to_string(), "7".to_string()), ("createdAt".to_string(), "2024".to_string())]);
+}
+
+#[test]
+fn attributes_arena_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+  let div = select_one(&document, "#card").unwrap().unwrap();
+
+  div.set_attr("title", "제목");
+  assert_eq!(div.get_attr("TITLE").as_deref(), Some("제목"));
+  div.class_list().add("arena");
+  assert!(div.class_list().contains("arena"));
+  assert_eq!(div.dataset().get("userId").as_deref(), Some("7"));
+
+  let a = select_one(&document, "svg a").unwrap().unwrap();
+  assert_eq!(a.get_attr_ns(&ns!(xlink), "href").as_deref(), Some("/home"));
+}
@@ -0,0 +1,160 @@
+use std::{fs, io::Write, path::PathBuf, process::{Command, Output, Stdio}};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>테스트></title>
+  </head>
+  <body id="[##_id_##]">
+    <!-- 주석 -->
+    <s_list><p class="item">[##_name_##]</p></s_list>
+    <my-element>
+      안녕하세요~ ^^
+    </my-element>
+  </body>
+</html>
+"#;
+
+fn run(args: &[&str], stdin: &str) -> Output {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_html5ever-sample"))
+    .args(args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  // Commands that fail early never read stdin, so a broken pipe here is expected.
+  let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+  child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+  let path = std::env::temp_dir().join(format!("html5ever-sample-{}-{}", std::process::id(), name));
+  fs::write(&path, contents).unwrap();
+  path
+}
+
+#[test]
+fn cli_parse_test() {
+  let output = run(&["parse"], HTML);
+  assert!(output.status.success());
+  let tree = stdout(&output);
+  assert!(tree.starts_with("#Document\n  <!DOCTYPE html \"\" \"\">\n  <html>\n"));
+  assert!(tree.contains("    <body id=\"[##_id_##]\">\n"));
+}
+
+#[test]
+fn cli_select_test() {
+  let path = temp_file("select.html", HTML);
+  let output = run(&["select", "body > my-element", path.to_str().unwrap()], "");
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), "<my-element>\n      안녕하세요~ ^^\n    </my-element>\n");
+
+  let output = run(&["select", "div["], HTML);
+  assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn cli_text_format_minify_test() {
+  assert!(stdout(&run(&["text"], HTML)).contains("안녕하세요~ ^^"));
+  assert!(!stdout(&run(&["text"], HTML)).contains("테스트>"));
+  assert!(stdout(&run(&["text", "--raw"], HTML)).contains("테스트>"));
+
+  let minified = stdout(&run(&["minify"], HTML));
+  assert!(!minified.contains("주석"));
+  assert!(minified.starts_with("<!DOCTYPE html><title>테스트&gt;</title><body id=[##_id_##]>"));
+
+  let output = run(&["minify", "--report"], HTML);
+  assert!(String::from_utf8(output.stderr).unwrap().contains("bytes, saved"));
+}
+
+#[test]
+fn cli_format_test() {
+  assert!(stdout(&run(&["format", "-"], HTML)).starts_with("<!DOCTYPE html>\n<html>\n  <head>\n    <title>테스트&gt;</title>\n"));
+
+  let pretty = stdout(&run(&["pretty", "--indent", "4"], HTML));
+  assert_eq!(pretty, stdout(&run(&["format", "--indent", "4"], HTML)));
+  assert!(pretty.starts_with("<!DOCTYPE html>\n<html>\n    <head>\n        <title>테스트&gt;</title>\n"));
+  assert!(pretty.contains("\n        <my-element>안녕하세요~ ^^</my-element>\n"));
+
+  let output = run(&["format", "--indent", "x"], HTML);
+  assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn cli_json_test() {
+  let output = run(&["json"], HTML);
+  assert!(output.status.success());
+  let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+  assert_eq!(json["type"], "document");
+  assert_eq!(json["children"][0]["name"], "html");
+  assert_eq!(json["children"][1]["attributes"], serde_json::json!([]));
+}
+
+#[test]
+fn cli_diff_test() {
+  let old = temp_file("old.html", HTML);
+  let new = temp_file("new.html", &HTML.replace("class=\"item\"", "class=\"entry\""));
+  let output = run(&["diff", old.to_str().unwrap(), new.to_str().unwrap()], "");
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), "@ /html/body[@id=\"[##_id_##]\"]/s_list/p class: \"item\" -> \"entry\"\n1 attributes changed\n");
+
+  let output = run(&["diff", "--unified", old.to_str().unwrap(), new.to_str().unwrap()], "");
+  assert!(stdout(&output).contains("\n-      <p class=\"item\">[##_name_##]</p>\n+      <p class=\"entry\">[##_name_##]</p>\n"));
+  assert_eq!(run(&["diff", old.to_str().unwrap()], "").status.code(), Some(2));
+}
+
+#[test]
+fn cli_render_template_test() {
+  let vars = temp_file("vars.json", r#"{ "id": "main", "s_list": [{ "name": "하나" }, { "name": "둘" }], "extra": 1 }"#);
+  let output = run(&["render-template", "--vars", vars.to_str().unwrap()], HTML);
+  assert!(output.status.success());
+
+  let html = stdout(&output);
+  assert!(html.contains("<body id=\"main\">"));
+  assert!(html.contains("<p class=\"item\">하나</p><p class=\"item\">둘</p>"));
+  assert!(String::from_utf8(output.stderr).unwrap().contains("unused variable extra"));
+}
+
+#[test]
+fn cli_usage_test() {
+  assert_eq!(run(&[], "").status.code(), Some(2));
+  assert_eq!(run(&["bogus"], "").status.code(), Some(2));
+  assert_eq!(run(&["render-template"], HTML).status.code(), Some(2));
+  assert!(run(&["--help"], "").status.success());
+}
+
+#[test]
+fn cli_lint_test() {
+  let output = run(&["lint"], "<!DOCTYPE html>\n<body>\n<body class=\"x\">\n");
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stdout(&output).starts_with("3:16: "));
+
+  let output = run(&["lint", "--max-errors", "1"], "<!DOCTYPE html>\n<body>\n<body class=\"x\">\n");
+  assert!(output.status.success());
+
+  let output = run(&["lint", "--max-errors", "many"], HTML);
+  assert_eq!(output.status.code(), Some(2));
+
+  // A <meta> past the prescan switches the fallback EUC-KR to windows-1252, so "\xC7\xD1" is two characters.
+  let mut bytes = format!("<html><head><script>{}</script><meta charset=\"windows-1252\"></head><p>", "// comment\n".repeat(300)).into_bytes();
+  bytes.extend_from_slice(b"\xC7\xD1</i>\n");
+  let path = std::env::temp_dir().join(format!("html5ever-sample-{}-late-meta.html", std::process::id()));
+  fs::write(&path, bytes).unwrap();
+  let output = run(&["lint", "--max-errors", "10", path.to_str().unwrap()], "");
+  assert!(stdout(&output).ends_with("\n301:54: Found special tag while closing generic tag\n"));
+}
+
+#[test]
+fn cli_charset_test() {
+  // "<p>한글</p>" in EUC-KR.
+  let bytes = b"<p>\xC7\xD1\xB1\xDB</p>";
+  let path = std::env::temp_dir().join(format!("html5ever-sample-{}-euc-kr.html", std::process::id()));
+  fs::write(&path, bytes).unwrap();
+  assert_eq!(stdout(&run(&["text", path.to_str().unwrap()], "")), "한글\n");
+  assert_eq!(stdout(&run(&["text", "--charset", "windows-1252", path.to_str().unwrap()], "")), "ÇÑ±Û\n");
+}
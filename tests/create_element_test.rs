@@ -0,0 +1,70 @@
+use html5ever::{namespace_url, ns, parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{NodeData, RcDom};
+use rust_html5ever_sample::{mutation::append, node::DomNode, rcdom::{create_element, create_element_for, create_element_ns, inner_html, is_valid_name, namespace_for_child, node_create, outer_html}, select::select_one};
+
+#[test]
+fn create_html_element_test() {
+  let br = node_create("br", &None);
+  assert_eq!(br.element_name().unwrap().ns, ns!(html));
+  assert_eq!(outer_html(&br), "<br>");
+
+  let div = create_element("DIV", &[("class", "card")]).unwrap();
+  assert_eq!(outer_html(&div), "<div class=\"card\"></div>");
+
+  let template = create_element("template", &[]).unwrap();
+  assert!(template.template_contents().is_some());
+  assert!(div.template_contents().is_none());
+  assert!(node_create("template", &None).template_contents().is_some());
+}
+
+#[test]
+fn create_element_name_validation_test() {
+  assert!(is_valid_name("my-element"));
+  assert!(is_valid_name("xlink:href"));
+  assert!(is_valid_name("한글"));
+  assert!(!is_valid_name(""));
+  assert!(!is_valid_name("1div"));
+  assert!(!is_valid_name("a b"));
+  assert!(!is_valid_name("a:b:c"));
+  assert!(!is_valid_name("<p>"));
+
+  assert_eq!(create_element("my element", &[]).unwrap_err().name, "my element");
+  assert_eq!(create_element("p", &[("on click", "x")]).unwrap_err().name, "on click");
+}
+
+#[test]
+fn create_foreign_element_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one("<body><svg><foreignObject></foreignObject></svg><math><annotation-xml encoding=\"text/html\"></annotation-xml></math></body>");
+  let body = select_one(&dom.document, "body").unwrap().unwrap();
+  let svg = select_one(&dom.document, "svg").unwrap().unwrap();
+  let foreign_object = select_one(&dom.document, "foreignObject").unwrap().unwrap();
+  let math = select_one(&dom.document, "math").unwrap().unwrap();
+  let annotation = select_one(&dom.document, "annotation-xml").unwrap().unwrap();
+
+  assert_eq!(namespace_for_child(&body, "svg"), ns!(svg));
+  assert_eq!(namespace_for_child(&body, "circle"), ns!(html));
+  assert_eq!(namespace_for_child(&svg, "circle"), ns!(svg));
+  assert_eq!(namespace_for_child(&foreign_object, "p"), ns!(html));
+  assert_eq!(namespace_for_child(&math, "mi"), ns!(mathml));
+  assert_eq!(namespace_for_child(&annotation, "p"), ns!(html));
+  assert_eq!(namespace_for_child(&svg, "math"), ns!(svg));
+
+  // MathML text integration points take HTML children, apart from these two.
+  let mi = create_element_ns(ns!(mathml), "mi", &[]).unwrap();
+  assert_eq!(create_element_for(&mi, "b", &[]).unwrap().element_name().unwrap().ns, ns!(html));
+  assert_eq!(namespace_for_child(&mi, "mglyph"), ns!(mathml));
+  let plain_annotation = create_element_ns(ns!(mathml), "annotation-xml", &[]).unwrap();
+  assert_eq!(namespace_for_child(&plain_annotation, "p"), ns!(mathml));
+  assert_eq!(namespace_for_child(&plain_annotation, "svg"), ns!(svg));
+
+  let gradient = create_element_for(&svg, "linearGradient", &[("id", "g")]).unwrap();
+  assert_eq!(gradient.element_name().unwrap().ns, ns!(svg));
+  append(&svg, &gradient).unwrap();
+  assert!(inner_html(&svg).ends_with("<linearGradient id=\"g\"></linearGradient>"));
+
+  let created = create_element_ns(ns!(mathml), "annotation-xml", &[("encoding", "application/xhtml+xml")]).unwrap();
+  match created.data {
+    NodeData::Element { mathml_annotation_xml_integration_point, .. } => assert!(mathml_annotation_xml_integration_point),
+    _ => panic!("expected an element"),
+  }
+}
@@ -0,0 +1,65 @@
+use html5ever::{local_name, namespace_url, ns, QualName};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, diagnostics::{parse_document_with_diagnostics, parse_fragment_with_diagnostics}};
+
+const HTML: &str = "<!DOCTYPE html>
+<html>
+  <body>
+    <p>안녕하세요</p>
+  <body class=\"second\">
+</html>
+";
+
+#[test]
+fn diagnostics_position_test() {
+  let result = parse_document_with_diagnostics(RcDom::default(), HTML);
+  assert_eq!(result.diagnostics.len(), 1);
+
+  let diagnostic = &result.diagnostics[0];
+  assert_eq!((diagnostic.line, diagnostic.column), (5, 23));
+  assert!(diagnostic.message.contains("body"));
+  assert!(diagnostic.to_string().starts_with("5:23: "));
+
+  // The wrapped sink still receives every error.
+  assert_eq!(result.output.errors.len(), 1);
+}
+
+#[test]
+fn diagnostics_clean_document_test() {
+  let result = parse_document_with_diagnostics(RcDom::default(), "<!DOCTYPE html>\n<p>안녕</p>\n");
+  assert!(result.diagnostics.is_empty());
+  assert!(result.lint(0).is_ok());
+}
+
+#[test]
+fn diagnostics_lint_threshold_test() {
+  let result = parse_document_with_diagnostics(RcDom::default(), "<p>a</b>\n<div>\n</span>");
+  assert_eq!(result.diagnostics.len(), 4);
+  assert!(result.lint(4).is_ok());
+  assert_eq!(result.lint(3).unwrap_err().len(), 4);
+}
+
+#[test]
+fn diagnostics_arena_test() {
+  let arena = ArenaDom::new();
+  let result = arena.parse_document_with_diagnostics(HTML);
+  assert_eq!(result.diagnostics.len(), 1);
+  assert_eq!(result.diagnostics[0].line, 5);
+  assert!(result.output.last_child.get().is_some());
+}
+
+#[test]
+fn diagnostics_fragment_test() {
+  let context = QualName::new(None, ns!(html), local_name!("div"));
+  let result = parse_fragment_with_diagnostics(RcDom::default(), context, vec![], "<span>a</div>");
+  assert!(!result.diagnostics.is_empty());
+  assert_eq!((result.diagnostics[0].line, result.diagnostics[0].column), (1, 13));
+}
+
+#[test]
+fn diagnostics_positions_within_line_test() {
+  let html = "<p a=\"1\"b>텍스트 &nosuch; 끝 &#0;</p>\n<table>글자<tr><td>a</td></tr></table>";
+  let result = parse_document_with_diagnostics(RcDom::default(), html);
+  let positions: Vec<(usize, usize)> = result.diagnostics.iter().map(|diagnostic| (diagnostic.line, diagnostic.column)).collect();
+  assert_eq!(positions, vec![(1, 9), (1, 10), (1, 22), (1, 29), (2, 13)]);
+}
@@ -0,0 +1,167 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{arena::ArenaDom, diff::{diff, diff_with_options, report, unified_diff, DiffOptions, Edit}, xpath::xpath};
+
+fn parse(html: &str) -> Handle {
+  parse_document(RcDom::default(), Default::default()).one(html).document
+}
+
+const OLD: &str = r#"<html><head><title>스킨</title></head><body>
+  <div id="header"><h1>블로그</h1></div>
+  <ul id="menu">
+    <li id="home">홈</li>
+    <li id="tags">태그</li>
+    <li id="guestbook">방명록</li>
+  </ul>
+  <div id="content">
+    <p class="post">첫 글</p>
+    <p class="post">둘째 글</p>
+  </div>
+  <div id="sidebar"><div id="search">검색</div></div>
+</body></html>"#;
+
+#[test]
+fn diff_identical_and_reindented_test() {
+  assert_eq!(diff(&parse(OLD), &parse(OLD)), vec![]);
+
+  let reindented = OLD.replace("\n  ", "\n        ").replace("첫 글", "첫    글\n");
+  assert_eq!(diff(&parse(OLD), &parse(&reindented)), vec![]);
+  assert!(!diff_with_options(&parse(OLD), &parse(&reindented), &DiffOptions { ignore_whitespace: false }).is_empty());
+}
+
+#[test]
+fn diff_attribute_and_text_test() {
+  let new = OLD.replace(r#"<p class="post">첫 글</p>"#, r#"<p class="post first" data-n="1">첫 번째 글</p>"#).replace(r#"id="menu""#, r#"id="menu" hidden"#);
+  assert_eq!(
+    diff(&parse(OLD), &parse(&new)),
+    vec![
+      Edit::Attribute { path: r#"/html/body/ul[@id="menu"]"#.to_string(), name: "hidden".to_string(), old: None, new: Some("".to_string()) },
+      Edit::Attribute {
+        path: r#"/html/body/div[@id="content"]/p[1]"#.to_string(),
+        name: "class".to_string(),
+        old: Some("post".to_string()),
+        new: Some("post first".to_string()),
+      },
+      Edit::Attribute { path: r#"/html/body/div[@id="content"]/p[1]"#.to_string(), name: "data-n".to_string(), old: None, new: Some("1".to_string()) },
+      Edit::Text { path: r#"/html/body/div[@id="content"]/p[1]/text()"#.to_string(), old: "첫 글".to_string(), new: "첫 번째 글".to_string() },
+    ]
+  );
+}
+
+#[test]
+fn diff_insert_delete_test() {
+  let new = OLD.replace(r#"<p class="post">둘째 글</p>"#, r#"<p class="post">둘째 글</p><p class="post">셋째 글</p>"#).replace(r#"<div id="header"><h1>블로그</h1></div>"#, "");
+  let edits = diff(&parse(OLD), &parse(&new));
+  assert_eq!(
+    edits,
+    vec![
+      Edit::Delete { path: r#"/html/body/div[@id="header"]"#.to_string(), html: "<div id=\"header\">\n  <h1>블로그</h1>\n</div>".to_string() },
+      Edit::Insert { path: r#"/html/body/div[@id="content"]/p[3]"#.to_string(), html: "<p class=\"post\">셋째 글</p>".to_string() },
+    ]
+  );
+
+  // The paths resolve with the XPath module, in the old tree for deletes and the new one for the rest.
+  let new_document = parse(&new);
+  let inserted = xpath(&new_document, r#"/html/body/div[@id="content"]/p[3]"#).unwrap().nodes();
+  assert_eq!(inserted.len(), 1);
+}
+
+#[test]
+fn diff_moves_keyed_on_id_test() {
+  let new = OLD
+    .replace(r#"<li id="tags">태그</li>"#, "")
+    .replace(r#"<li id="home">홈</li>"#, r#"<li id="home">홈</li><li id="tags">태그 목록</li>"#)
+    .replace(r#"<li id="guestbook">방명록</li>"#, "")
+    .replace(r#"<ul id="menu">"#, r#"<ul id="menu"><li id="guestbook">방명록</li>"#)
+    .replace(r#"<div id="sidebar"><div id="search">검색</div></div>"#, r#"<div id="sidebar"></div>"#)
+    .replace(r#"<div id="header"><h1>블로그</h1></div>"#, r#"<div id="header"><h1>블로그</h1><div id="search">검색</div></div>"#);
+  let edits = diff(&parse(OLD), &parse(&new));
+  assert_eq!(
+    edits,
+    vec![
+      Edit::Move { from: r#"/html/body/div[@id="sidebar"]/div[@id="search"]"#.to_string(), to: r#"/html/body/div[@id="header"]/div[@id="search"]"#.to_string() },
+      Edit::Move { from: r#"/html/body/ul[@id="menu"]/li[@id="guestbook"]"#.to_string(), to: r#"/html/body/ul[@id="menu"]/li[@id="guestbook"]"#.to_string() },
+      Edit::Text { path: r#"/html/body/ul[@id="menu"]/li[@id="tags"]/text()"#.to_string(), old: "태그".to_string(), new: "태그 목록".to_string() },
+    ]
+  );
+}
+
+#[test]
+fn diff_id_kept_by_another_element_test() {
+  let old = parse(r#"<div id="x">내용</div><p>끝</p>"#);
+  let new = parse(r#"<section id="x">내용</section><p>끝</p>"#);
+  assert_eq!(
+    diff(&old, &new),
+    vec![
+      Edit::Delete { path: r#"/html/body/div[@id="x"]"#.to_string(), html: "<div id=\"x\">내용</div>".to_string() },
+      Edit::Insert { path: r#"/html/body/section[@id="x"]"#.to_string(), html: "<section id=\"x\">내용</section>".to_string() },
+    ]
+  );
+}
+
+#[test]
+fn diff_move_into_inserted_element_test() {
+  let old = parse(r#"<p id="a">문단</p><p>끝</p>"#);
+  let new = parse(r#"<section><h2>제목</h2><p id="a">고친 문단</p></section><p>끝</p>"#);
+  assert_eq!(
+    diff(&old, &new),
+    vec![
+      Edit::Insert { path: "/html/body/section".to_string(), html: "<section>\n  <h2>제목</h2>\n  <p id=\"a\">고친 문단</p>\n</section>".to_string() },
+      Edit::Move { from: r#"/html/body/p[@id="a"]"#.to_string(), to: r#"/html/body/section/p[@id="a"]"#.to_string() },
+      Edit::Text { path: r#"/html/body/section/p[@id="a"]/text()"#.to_string(), old: "문단".to_string(), new: "고친 문단".to_string() },
+    ]
+  );
+}
+
+#[test]
+fn diff_report_test() {
+  let new = OLD.replace("둘째 글", "두 번째 글").replace(r#"<div id="header"><h1>블로그</h1></div>"#, "").replace("<title>스킨</title>", r#"<title>스킨</title><meta charset="utf-8">"#);
+  let text = report(&diff(&parse(OLD), &parse(&new)));
+  assert_eq!(
+    text,
+    r#"+ /html/head/meta <meta charset="utf-8">
+- /html/body/div[@id="header"] <div id="header">…
+~ /html/body/div[@id="content"]/p[2]/text() "둘째 글" -> "두 번째 글"
+1 inserted, 1 deleted, 1 texts changed
+"#
+  );
+  assert_eq!(report(&[]), "no differences\n");
+}
+
+#[test]
+fn diff_unified_test() {
+  let new = OLD.replace("둘째 글", "두 번째 글").replace(r#"<li id="tags">태그</li>"#, "");
+  let unified = unified_diff(&parse(OLD), &parse(&new), "old.html", "new.html");
+  assert_eq!(
+    unified,
+    r#"--- old.html
++++ new.html
+@@ -8,12 +8,11 @@
+     </div>
+     <ul id="menu">
+       <li id="home">홈</li>
+-      <li id="tags">태그</li>
+       <li id="guestbook">방명록</li>
+     </ul>
+     <div id="content">
+       <p class="post">첫 글</p>
+-      <p class="post">둘째 글</p>
++      <p class="post">두 번째 글</p>
+     </div>
+     <div id="sidebar">
+       <div id="search">검색</div>
+"#
+  );
+  assert_eq!(unified_diff(&parse(OLD), &parse(OLD), "a", "b"), "");
+}
+
+#[test]
+fn diff_arena_test() {
+  let dom = ArenaDom::new();
+  let old = dom.parse_document("<ul><li>하나</li><li>둘</li></ul>");
+  let new = dom.parse_document("<ul><li>하나</li><li class=\"x\">둘</li><li>셋</li></ul>");
+  assert_eq!(
+    report(&diff(&old, &new)),
+    "@ /html/body/ul/li[2] class=\"x\" added\n+ /html/body/ul/li[3] <li>셋</li>\n1 inserted, 1 attributes changed\n"
+  );
+}
@@ -0,0 +1,135 @@
+use encoding_rs::{EUC_KR, UTF_16LE, UTF_8, WINDOWS_1252};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, encoding::{extract_charset, parse_bytes, prescan, sniff, Confidence, DecodeOptions, EncodingSource}, select::select_one, text::text_content};
+
+fn euc_kr(text: &str) -> Vec<u8> {
+  EUC_KR.encode(text).0.into_owned()
+}
+
+fn title(bytes: &[u8], options: &DecodeOptions) -> String {
+  let result = parse_bytes(RcDom::default, bytes, options);
+  text_content(&select_one(&result.output.document, "title").unwrap().unwrap())
+}
+
+#[test]
+fn encoding_byte_order_mark_test() {
+  let mut bytes = b"\xEF\xBB\xBF".to_vec();
+  bytes.extend_from_slice("<meta charset=euc-kr><title>제목</title>".as_bytes());
+  let result = parse_bytes(RcDom::default, &bytes, &DecodeOptions::default());
+  assert_eq!(result.encoding, UTF_8);
+  assert_eq!(result.confidence, Confidence::Certain);
+  assert_eq!(result.source, EncodingSource::ByteOrderMark);
+  assert_eq!(text_content(&select_one(&result.output.document, "title").unwrap().unwrap()), "제목");
+
+  let mut bytes = b"\xFF\xFE".to_vec();
+  for unit in "<title>제목</title>".encode_utf16() {
+    bytes.extend_from_slice(&unit.to_le_bytes());
+  }
+  let sniffed = sniff(&bytes, &DecodeOptions::default());
+  assert_eq!((sniffed.encoding, sniffed.bom_length), (UTF_16LE, 2));
+  assert_eq!(title(&bytes, &DecodeOptions::default()), "제목");
+}
+
+#[test]
+fn encoding_transport_hint_test() {
+  let mut bytes = b"<meta charset=utf-8>".to_vec();
+  bytes.extend(euc_kr("<title>한글 페이지</title>"));
+  let options = DecodeOptions {
+    transport_hint: Some("text/html; charset=EUC-KR".to_string()),
+    ..Default::default()
+  };
+  let result = parse_bytes(RcDom::default, &bytes, &options);
+  assert_eq!((result.encoding, result.confidence, result.source), (EUC_KR, Confidence::Certain, EncodingSource::TransportHint));
+  assert_eq!(title(&bytes, &options), "한글 페이지");
+
+  let options = DecodeOptions {
+    transport_hint: Some("cp949".to_string()),
+    ..Default::default()
+  };
+  assert_eq!(sniff(&bytes, &options).encoding, EUC_KR);
+}
+
+#[test]
+fn encoding_meta_prescan_test() {
+  let bytes = euc_kr("<!DOCTYPE html><html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=euc-kr\"><title>한글</title>");
+  let options = DecodeOptions {
+    fallback: WINDOWS_1252,
+    ..Default::default()
+  };
+  let result = parse_bytes(RcDom::default, &bytes, &options);
+  assert_eq!((result.encoding, result.source), (EUC_KR, EncodingSource::MetaPrescan));
+  // The tree builder saw the same `<meta>`, which makes the guess certain.
+  assert_eq!(result.confidence, Confidence::Certain);
+  assert!(!result.had_errors);
+  assert_eq!(title(&bytes, &options), "한글");
+}
+
+#[test]
+fn encoding_meta_reparse_test() {
+  let mut html = format!("<html><head><script>{}</script>", "// 주석\n".repeat(200));
+  html.push_str("<meta charset=\"EUC-KR\"><title>늦은 선언</title>");
+  let bytes = euc_kr(&html);
+  assert_eq!(prescan(&bytes), None);
+
+  let options = DecodeOptions {
+    fallback: WINDOWS_1252,
+    ..Default::default()
+  };
+  let result = parse_bytes(RcDom::default, &bytes, &options);
+  assert_eq!((result.encoding, result.confidence, result.source), (EUC_KR, Confidence::Certain, EncodingSource::MetaReparse));
+  assert_eq!(title(&bytes, &options), "늦은 선언");
+}
+
+#[test]
+fn encoding_detection_and_fallback_test() {
+  let bytes = "<title>유니코드</title>".as_bytes();
+  let result = parse_bytes(RcDom::default, bytes, &DecodeOptions::default());
+  assert_eq!((result.encoding, result.confidence, result.source), (UTF_8, Confidence::Tentative, EncodingSource::Utf8Detected));
+
+  let cut_off = &"<p>한글".as_bytes()[..7];
+  assert_eq!(sniff(cut_off, &DecodeOptions::default()).source, EncodingSource::Utf8Detected);
+
+  let bytes = euc_kr("<title>완성형</title>");
+  let result = parse_bytes(RcDom::default, &bytes, &DecodeOptions::default());
+  assert_eq!((result.encoding, result.source), (EUC_KR, EncodingSource::Fallback));
+  assert_eq!(title(&bytes, &DecodeOptions::default()), "완성형");
+  // CP949 extensions beyond KS X 1001.
+  assert_eq!(title(&euc_kr("<title>똠방각하</title>"), &DecodeOptions::default()), "똠방각하");
+}
+
+#[test]
+fn encoding_prescan_test() {
+  assert_eq!(prescan(b"<meta charset='shift_jis'>"), Some(encoding_rs::SHIFT_JIS));
+  assert_eq!(prescan(b"<META CHARSET=EUC-KR>"), Some(EUC_KR));
+  assert_eq!(prescan(b"<!-- <meta charset=euc-kr> --><meta charset=utf-8>"), Some(UTF_8));
+  assert_eq!(prescan(b"<!--><meta charset=euc-kr>"), Some(EUC_KR));
+  assert_eq!(prescan(b"<div title='<meta charset=euc-kr>'><meta charset=windows-1252>"), Some(WINDOWS_1252));
+  assert_eq!(prescan(b"<meta content=\"text/html; charset=euc-kr\">"), None);
+  assert_eq!(prescan(b"<meta charset=utf-16le>"), Some(UTF_8));
+  assert_eq!(prescan(b"<meta charset=x-user-defined>"), Some(WINDOWS_1252));
+  assert_eq!(prescan(b"<meta charset=nonsense><meta charset=euc-kr>"), Some(EUC_KR));
+  assert_eq!(prescan(b"<metacharset=euc-kr>"), None);
+
+  let mut late = vec![b' '; 1024];
+  late.extend_from_slice(b"<meta charset=euc-kr>");
+  assert_eq!(prescan(&late), None);
+}
+
+#[test]
+fn encoding_extract_charset_test() {
+  assert_eq!(extract_charset("text/html; charset=euc-kr"), Some(EUC_KR));
+  assert_eq!(extract_charset("text/html;CHARSET = \"ks_c_5601-1987\""), Some(EUC_KR));
+  assert_eq!(extract_charset("text/html; charset='utf-8"), None);
+  assert_eq!(extract_charset("text/html; charsetx; charset=utf-8"), Some(UTF_8));
+  assert_eq!(extract_charset("text/html"), None);
+  assert_eq!(extract_charset("text/html; charset=CP949"), Some(EUC_KR));
+}
+
+#[test]
+fn encoding_arena_test() {
+  let dom = ArenaDom::new();
+  let bytes = euc_kr("<meta charset=euc-kr><p>아레나</p>");
+  let result = dom.parse_bytes(&bytes, &DecodeOptions::default());
+  assert_eq!(result.encoding, EUC_KR);
+  assert_eq!(text_content(&select_one(&result.output, "p").unwrap().unwrap()), "아레나");
+}
@@ -0,0 +1,127 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, format::{format, FormatOptions}, rcdom::convert_node_to_html_string, select::select_one, text::text_content};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>테스트></title>
+  </head>
+  <body id="[##_id_##]">
+    <!-- 주석 -->
+    <p>첫   번째
+       문단 <b>굵게</b> &amp; 끝</p>
+    <pre>
+
+  코드
+    들여쓰기</pre>
+    <my-element>
+      안녕하세요~ ^^
+    </my-element>
+  </body>
+</html>
+"#;
+
+const EXPECTED: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>테스트&gt;</title>
+  </head>
+  <body id="[##_id_##]">
+    <!-- 주석 -->
+    <p>첫 번째 문단 <b>굵게</b> &amp; 끝</p>
+    <pre>
+
+  코드
+    들여쓰기</pre>
+    <my-element>안녕하세요~ ^^</my-element>
+  </body>
+</html>
+"#;
+
+#[test]
+fn format_document_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  assert_eq!(format(&dom.document, &FormatOptions::default()), EXPECTED);
+}
+
+#[test]
+fn format_preserves_pre_contents_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let formatted = format(&dom.document, &FormatOptions::default());
+
+  // Parsing the formatted output again gives the same <pre> text.
+  let reparsed = parse_document(RcDom::default(), Default::default()).one(formatted);
+  let pre = select_one(&dom.document, "pre").unwrap().unwrap();
+  let reparsed_pre = select_one(&reparsed.document, "pre").unwrap().unwrap();
+  assert_eq!(convert_node_to_html_string(&pre), convert_node_to_html_string(&reparsed_pre));
+}
+
+#[test]
+fn format_options_test() {
+  let html = r#"<div id="card" class="card big" data-user-id="7"><span>안녕</span> <em>하세요</em><section><p>x</p></section></div>"#;
+  let dom = parse_document(RcDom::default(), Default::default()).one(html);
+  let div = select_one(&dom.document, "div").unwrap().unwrap();
+
+  let options = FormatOptions {
+    indent_width: 4,
+    wrap_attributes_at: Some(30),
+    ..Default::default()
+  };
+  assert_eq!(
+    format(&div, &options),
+    "<div\n    id=\"card\"\n    class=\"card big\"\n    data-user-id=\"7\">\n    <span>안녕</span> <em>하세요</em>\n    <section>\n        <p>x</p>\n    </section>\n</div>\n"
+  );
+
+  // The list replaces the defaults, so <span> and <em> become blocks.
+  let options = FormatOptions {
+    inline_elements: vec!["section".to_string(), "p".to_string()],
+    wrap_attributes_at: None,
+    ..Default::default()
+  };
+  assert_eq!(
+    format(&div, &options),
+    "<div id=\"card\" class=\"card big\" data-user-id=\"7\">\n  <span>안녕</span>\n  <em>하세요</em>\n  <section><p>x</p></section>\n</div>\n"
+  );
+}
+
+#[test]
+fn format_arena_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+  assert_eq!(format(&document, &FormatOptions::default()), EXPECTED);
+}
+
+#[test]
+fn format_raw_text_round_trip_test() {
+  let options = FormatOptions {
+    preserved_elements: vec![],
+    ..Default::default()
+  };
+  for name in ["style", "script", "xmp", "iframe", "noembed", "noframes", "noscript", "plaintext"] {
+    let html = format!("<body><div><{0}><p>x</p>  a &amp;\n  b</{0}></div></body>", name);
+    let dom = parse_document(RcDom::default(), Default::default()).one(html);
+    let formatted = format(&dom.document, &options);
+
+    let reparsed = parse_document(RcDom::default(), Default::default()).one(formatted.as_str());
+    let element = select_one(&dom.document, name).unwrap().unwrap();
+    let reparsed_element = select_one(&reparsed.document, name).unwrap().unwrap();
+    // Nothing ends <plaintext>, so the tags written after its text are read back as text too.
+    if name == "plaintext" {
+      assert!(text_content(&reparsed_element).starts_with(&*text_content(&element)));
+    } else {
+      assert_eq!(text_content(&reparsed_element), text_content(&element), "{}", name);
+    }
+  }
+}
+
+#[test]
+fn format_legacy_doctype_test() {
+  let html = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN"><p>테스트</p>"#;
+  let dom = parse_document(RcDom::default(), Default::default()).one(html);
+  let formatted = format(&dom.document, &FormatOptions::default());
+  assert!(formatted.starts_with("<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\">\n"));
+
+  let reparsed = parse_document(RcDom::default(), Default::default()).one(formatted);
+  assert_eq!(reparsed.quirks_mode, dom.quirks_mode);
+}
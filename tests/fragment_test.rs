@@ -0,0 +1,50 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, node::DomNode, rcdom::{convert_node_to_html_string, node_append, node_create, node_select_one, parse_fragment}};
+
+#[test]
+fn rcdom_parse_fragment_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one("<ul id=\"list\"><li>하나</li></ul>");
+  let ul = node_select_one(&dom.document, "ul", &None).unwrap();
+
+  let nodes = parse_fragment(&ul, "<li>둘</li><li>셋</li>");
+  assert_eq!(nodes.len(), 2);
+  assert!(nodes.iter().all(|node| node.parent().is_none()));
+  for node in nodes.iter() {
+    node_append(&ul, node);
+  }
+  assert_eq!(convert_node_to_html_string(&ul), "<li>하나</li><li>둘</li><li>셋</li>");
+}
+
+#[test]
+fn rcdom_parse_fragment_context_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one("<table><tbody></tbody></table>");
+  let tbody = node_select_one(&dom.document, "tbody", &None).unwrap();
+
+  // Outside of a table context the parser drops the `<tr>` and `<td>` tags.
+  let div = node_create("div", &None);
+  assert_eq!(parse_fragment(&div, "<tr><td>1</td></tr>").len(), 1);
+
+  let nodes = parse_fragment(&tbody, "<tr><td>1</td></tr>");
+  assert_eq!(nodes.len(), 1);
+  node_append(&tbody, &nodes[0]);
+  assert_eq!(convert_node_to_html_string(&tbody), "<tr><td>1</td></tr>");
+  assert!(nodes[0].parent().unwrap().same_node(&tbody));
+}
+
+#[test]
+fn arena_parse_fragment_children_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document("<table><tbody></tbody></table>");
+  let tbody = document.descendants().into_iter().find(|node| node.element_name().map(|name| &*name.local) == Some("tbody")).unwrap();
+
+  let nodes = arena.parse_fragment_children(tbody, "<tr><td>1</td></tr>");
+  assert_eq!(nodes.len(), 1);
+  assert!(nodes[0].parent.get().is_none());
+  tbody.append(nodes[0]);
+  assert_eq!(tbody.children().count(), 1);
+
+  let nodes = arena.parse_fragment_children(document, "<p>안녕</p>텍스트");
+  assert_eq!(nodes.len(), 2);
+  assert!(nodes[1].text().is_some());
+}
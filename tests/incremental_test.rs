@@ -0,0 +1,156 @@
+use encoding_rs::{EUC_KR, UTF_8};
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, encoding::{DecodeOptions, EncodingSource}, incremental::IncrementalParser, rcdom::outer_html, select::select_one, text::text_content};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <meta charset="utf-8">
+    <title>테스트</title>
+  </head>
+  <body id="[##_id_##]">
+    <p>안녕하세요~ ^^</p>
+    <my-element>한글 문서</my-element>
+  </body>
+</html>
+"#;
+
+#[test]
+fn incremental_matches_one_shot_parse_test() {
+  let expected = outer_html(&parse_document(RcDom::default(), Default::default()).one(HTML).document);
+  // Chunks of 5 bytes split most of the three-byte Korean characters.
+  for size in [1, 5, 64, 4096] {
+    let mut parser = IncrementalParser::new(RcDom::default());
+    for chunk in HTML.as_bytes().chunks(size) {
+      parser.feed(chunk);
+    }
+    let result = parser.finish();
+    assert_eq!(outer_html(&result.output.document), expected);
+    assert_eq!((result.encoding, result.source), (UTF_8, EncodingSource::MetaPrescan));
+    assert!(!result.had_errors);
+  }
+}
+
+#[test]
+fn incremental_stop_after_head_test() {
+  let mut html = String::from("<!DOCTYPE html><html><head><title>긴 문서</title></head><body>");
+  html.push_str(&"<p>본문</p>\n".repeat(10000));
+  html.push_str("</body></html>");
+
+  let mut parser = IncrementalParser::new(RcDom::default());
+  let mut title = None;
+  for chunk in html.as_bytes().chunks(256) {
+    parser.feed(chunk);
+    let document = parser.document();
+    if select_one(&document, "body").unwrap().is_some() {
+      title = select_one(&document, "title").unwrap().map(|title| text_content(&title));
+      break;
+    }
+  }
+  assert_eq!(title.as_deref(), Some("긴 문서"));
+  // Nothing is parsed before the first 1024 bytes are in, and the body starts right after them.
+  assert_eq!(parser.bytes_fed(), 1024);
+}
+
+#[test]
+fn incremental_partial_tree_test() {
+  let options = DecodeOptions {
+    transport_hint: Some("utf-8".to_string()),
+    ..Default::default()
+  };
+  let mut parser = IncrementalParser::with_options(RcDom::default(), &options);
+  parser.feed("<ul><li>하나</li><li>둘".as_bytes());
+  assert_eq!(parser.encoding(), Some(UTF_8));
+  let document = parser.document();
+  let list = select_one(&document, "ul").unwrap().unwrap();
+  assert_eq!(outer_html(&list), "<ul><li>하나</li><li>둘</li></ul>");
+
+  parser.feed("</li><li>셋".as_bytes());
+  assert_eq!(outer_html(&list), "<ul><li>하나</li><li>둘</li><li>셋</li></ul>");
+  let result = parser.finish();
+  assert_eq!(text_content(&select_one(&result.output.document, "ul").unwrap().unwrap()), "하나둘셋");
+}
+
+#[test]
+fn incremental_buffers_until_encoding_is_known_test() {
+  let mut parser = IncrementalParser::new(RcDom::default());
+  parser.feed(b"<p>short");
+  assert_eq!(parser.encoding(), None);
+  let document = parser.document();
+  assert!(select_one(&document, "p").unwrap().is_none());
+  let result = parser.finish();
+  assert_eq!(text_content(&select_one(&result.output.document, "p").unwrap().unwrap()), "short");
+}
+
+#[test]
+fn incremental_legacy_encoding_test() {
+  let bytes = EUC_KR.encode("<meta charset=euc-kr><title>완성형 한글</title>").0.into_owned();
+  let mut parser = IncrementalParser::new(RcDom::default());
+  for chunk in bytes.chunks(3) {
+    parser.feed(chunk);
+  }
+  let result = parser.finish();
+  assert_eq!(result.encoding, EUC_KR);
+  assert!(!result.had_errors);
+  assert_eq!(text_content(&select_one(&result.output.document, "title").unwrap().unwrap()), "완성형 한글");
+}
+
+#[test]
+fn incremental_arena_test() {
+  let dom = ArenaDom::new();
+  let mut parser = dom.incremental_parser(&DecodeOptions::default());
+  for chunk in HTML.as_bytes().chunks(100) {
+    parser.feed(chunk);
+  }
+  let result = parser.finish();
+  assert_eq!(text_content(&select_one(&result.output, "my-element").unwrap().unwrap()), "한글 문서");
+}
+
+#[test]
+fn incremental_late_utf8_test() {
+  let mut html = String::from("<!DOCTYPE html><html><head><title>late</title></head><body>");
+  html.push_str(&"<p>ascii only</p>\n".repeat(100));
+  html.push_str("<p id=\"last\">늦게 나온 한글</p></body></html>");
+  assert!(html.as_bytes()[..1024].is_ascii());
+
+  for size in [1, 7, 1024, 4096] {
+    let mut parser = IncrementalParser::new(RcDom::default());
+    for chunk in html.as_bytes().chunks(size) {
+      parser.feed(chunk);
+    }
+    let result = parser.finish();
+    assert_eq!((result.encoding, result.source), (UTF_8, EncodingSource::Utf8Detected));
+    assert!(!result.had_errors);
+    assert_eq!(text_content(&select_one(&result.output.document, "#last").unwrap().unwrap()), "늦게 나온 한글");
+  }
+
+  // Legacy bytes after an ASCII start still get the fallback.
+  let mut bytes = "<p>ascii only</p>\n".repeat(100).into_bytes();
+  bytes.extend(EUC_KR.encode("<p id=\"last\">완성형</p>").0.into_owned());
+  let mut parser = IncrementalParser::new(RcDom::default());
+  for chunk in bytes.chunks(5) {
+    parser.feed(chunk);
+  }
+  let result = parser.finish();
+  assert_eq!((result.encoding, result.source), (EUC_KR, EncodingSource::Fallback));
+  assert_eq!(text_content(&select_one(&result.output.document, "#last").unwrap().unwrap()), "완성형");
+}
+
+#[test]
+fn incremental_hint_without_charset_test() {
+  let bytes = EUC_KR.encode("<html><head><meta charset=euc-kr><title>힌트 없음</title>").0.into_owned();
+  let options = DecodeOptions {
+    transport_hint: Some("text/html".to_string()),
+    ..Default::default()
+  };
+  let mut parser = IncrementalParser::with_options(RcDom::default(), &options);
+  for chunk in bytes.chunks(3) {
+    parser.feed(chunk);
+  }
+  // The hint names no charset, so the parser waits for the prescan instead of deciding after 3 bytes.
+  assert_eq!(parser.encoding(), None);
+  let result = parser.finish();
+  assert_eq!((result.encoding, result.source), (EUC_KR, EncodingSource::MetaPrescan));
+  assert_eq!(text_content(&select_one(&result.output.document, "title").unwrap().unwrap()), "힌트 없음");
+}
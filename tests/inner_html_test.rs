@@ -0,0 +1,75 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{mutation::MutationError, node::DomNode, rcdom::{inner_html, node_create, node_select_one, outer_html, set_inner_html, set_outer_html}};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>테스트></title>
+  </head>
+  <body id="[##_id_##]"><my-element class="a">안녕하세요~ <b>^^</b></my-element><template><p>템플릿</p></template></body>
+</html>
+"#;
+
+#[test]
+fn inner_outer_html_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let element = node_select_one(&dom.document, "my-element", &None).unwrap();
+
+  assert_eq!(inner_html(&element), "안녕하세요~ <b>^^</b>");
+  assert_eq!(outer_html(&element), "<my-element class=\"a\">안녕하세요~ <b>^^</b></my-element>");
+
+  let template = node_select_one(&dom.document, "template", &None).unwrap();
+  assert_eq!(inner_html(&template), "<p>템플릿</p>");
+  assert_eq!(outer_html(&template), "<template><p>템플릿</p></template>");
+
+  assert!(outer_html(&dom.document).starts_with("<!DOCTYPE html><html>"));
+}
+
+#[test]
+fn set_inner_html_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let element = node_select_one(&dom.document, "my-element", &None).unwrap();
+  let old_child = element.children.borrow()[0].clone();
+
+  set_inner_html(&element, "<i>새 내용</i> & more").unwrap();
+  assert_eq!(inner_html(&element), "<i>새 내용</i> &amp; more");
+  assert!(old_child.parent().is_none());
+  assert!(element.children.borrow()[0].parent().unwrap().same_node(&element));
+
+  let template = node_select_one(&dom.document, "template", &None).unwrap();
+  set_inner_html(&template, "<td>1</td>").unwrap();
+  assert_eq!(inner_html(&template), "<td>1</td>");
+  assert!(template.children.borrow().is_empty());
+
+  // The node is the parsing context, so table parts survive inside a table.
+  let tbody = node_create("tbody", &None);
+  set_inner_html(&tbody, "<tr><td>1</td></tr>").unwrap();
+  assert_eq!(inner_html(&tbody), "<tr><td>1</td></tr>");
+
+  // Text, comments and doctypes cannot have children.
+  let text = element.children.borrow()[1].clone();
+  assert_eq!(set_inner_html(&text, "<b>x</b>"), Err(MutationError::NotAContainer));
+  assert_eq!(outer_html(&text), " &amp; more");
+  let doctype = dom.document.children.borrow()[0].clone();
+  assert_eq!(set_inner_html(&doctype, "<b>x</b>"), Err(MutationError::NotAContainer));
+}
+
+#[test]
+fn set_outer_html_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let element = node_select_one(&dom.document, "my-element", &None).unwrap();
+  let body = element.parent().unwrap();
+
+  set_outer_html(&element, "<p>하나</p>둘").unwrap();
+  assert!(element.parent().is_none());
+  assert!(inner_html(&body).starts_with("<p>하나</p>둘<template><p>템플릿</p></template>"));
+  assert!(body.children.borrow()[1].parent().unwrap().same_node(&body));
+
+  // Detached nodes and children of the document are left alone.
+  assert_eq!(set_outer_html(&element, "<p>무시</p>"), Err(MutationError::NoParent));
+  assert_eq!(outer_html(&element), "<my-element class=\"a\">안녕하세요~ <b>^^</b></my-element>");
+  let html = node_select_one(&dom.document, "html", &None).unwrap();
+  assert_eq!(set_outer_html(&html, "<p>무시</p>"), Err(MutationError::NoParent));
+  assert!(html.parent().is_some());
+}
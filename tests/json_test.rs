@@ -0,0 +1,124 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, json::{from_json, to_json}, rcdom::{convert_node_to_html_string, outer_html}, select::select_one};
+use serde_json::json;
+
+const HTML: &str = r##"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN" "http://www.w3.org/TR/html4/strict.dtd">
+<html lang="ko">
+  <head><title>테스트</title></head>
+  <body id="[##_id_##]">
+    <!-- 주석 -->
+    <p class="item">안녕하세요 &amp; <b>반갑습니다</b></p>
+    <template><li>템플릿 안</li></template>
+    <svg viewBox="0 0 10 10"><use xlink:href="#icon"/></svg>
+    <math><annotation-xml encoding="text/html"><div>수식</div></annotation-xml></math>
+    <script>if (a < b) {}</script>
+  </body>
+</html>
+"##;
+
+#[test]
+fn json_round_trip_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let value = to_json(&dom.document);
+  let rebuilt = from_json(&value).unwrap();
+  assert_eq!(convert_node_to_html_string(&rebuilt), convert_node_to_html_string(&dom.document));
+  assert_eq!(to_json(&rebuilt), value);
+
+  // Through a string, as another process would get it.
+  let text = serde_json::to_string(&value).unwrap();
+  let rebuilt = from_json(&serde_json::from_str(&text).unwrap()).unwrap();
+  assert_eq!(convert_node_to_html_string(&rebuilt), convert_node_to_html_string(&dom.document));
+}
+
+#[test]
+fn json_schema_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let value = to_json(&dom.document);
+  assert_eq!(
+    value["children"][0],
+    json!({ "type": "doctype", "name": "html", "publicId": "-//W3C//DTD HTML 4.01//EN", "systemId": "http://www.w3.org/TR/html4/strict.dtd" })
+  );
+
+  let paragraph = to_json(&select_one(&dom.document, "p").unwrap().unwrap());
+  assert_eq!(
+    paragraph,
+    json!({
+      "type": "element",
+      "name": "p",
+      "namespace": "http://www.w3.org/1999/xhtml",
+      "attributes": [{ "name": "class", "value": "item" }],
+      "children": [
+        { "type": "text", "data": "안녕하세요 & " },
+        {
+          "type": "element",
+          "name": "b",
+          "namespace": "http://www.w3.org/1999/xhtml",
+          "attributes": [],
+          "children": [{ "type": "text", "data": "반갑습니다" }],
+        },
+      ],
+    })
+  );
+
+  let template = to_json(&select_one(&dom.document, "template").unwrap().unwrap());
+  assert_eq!(template["children"], json!([]));
+  assert_eq!(template["content"][0]["name"], "li");
+
+  let icon = to_json(&select_one(&dom.document, "use").unwrap().unwrap());
+  assert_eq!(icon["namespace"], "http://www.w3.org/2000/svg");
+  assert_eq!(icon["attributes"], json!([{ "name": "href", "value": "#icon", "namespace": "http://www.w3.org/1999/xlink", "prefix": "xlink" }]));
+
+  let comment = &value["children"][1]["children"][2]["children"][1];
+  assert_eq!(comment, &json!({ "type": "comment", "data": " 주석 " }));
+}
+
+#[test]
+fn json_subtree_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let paragraph = select_one(&dom.document, "p").unwrap().unwrap();
+  let rebuilt = from_json(&to_json(&paragraph)).unwrap();
+  assert!(rebuilt.parent.take().is_none());
+  assert_eq!(outer_html(&rebuilt), outer_html(&paragraph));
+
+  let template = select_one(&dom.document, "template").unwrap().unwrap();
+  assert_eq!(outer_html(&from_json(&to_json(&template)).unwrap()), "<template><li>템플릿 안</li></template>");
+}
+
+#[test]
+fn json_errors_test() {
+  let error = from_json(&json!({ "type": "document", "children": [{ "type": "element", "name": "p" }] })).unwrap_err();
+  assert_eq!(error.to_string(), "/children/0: missing \"namespace\"");
+
+  let error = from_json(&json!({ "type": "document", "children": [{ "type": "blink" }] })).unwrap_err();
+  assert_eq!(error.path, "/children/0/type");
+
+  let error = from_json(&json!({
+    "type": "element",
+    "name": "p",
+    "namespace": "http://www.w3.org/1999/xhtml",
+    "attributes": [{ "name": "class", "value": 1 }],
+  }))
+  .unwrap_err();
+  assert_eq!(error.to_string(), "/attributes/0/value: expected a string");
+
+  assert!(from_json(&json!({ "type": "text", "data": "고아" })).is_err());
+  assert!(from_json(&json!({ "type": "element", "name": "p", "namespace": "http://www.w3.org/1999/xhtml", "children": [{ "type": "doctype", "name": "html" }] })).is_err());
+  assert!(from_json(&json!("문서")).is_err());
+}
+
+#[test]
+fn json_arena_test() {
+  let dom = ArenaDom::new();
+  let document = dom.parse_document(HTML);
+  let value = to_json(&document);
+
+  let rebuilt_dom = ArenaDom::new();
+  let rebuilt = rebuilt_dom.from_json(&value).unwrap();
+  assert_eq!(to_json(&rebuilt), value);
+
+  // The two trees export the same JSON, so either can rebuild the other.
+  let rcdom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  assert_eq!(to_json(&rcdom.document), value);
+  assert_eq!(convert_node_to_html_string(&from_json(&value).unwrap()), convert_node_to_html_string(&rcdom.document));
+}
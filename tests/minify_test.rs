@@ -0,0 +1,120 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{minify::{minify, MinifyOptions}, rcdom::outer_html, select::select_one, text::inner_text};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>테스트></title>
+    <!--[if IE]><link rel="stylesheet" href="ie.css"><![endif]-->
+  </head>
+  <body id="[##_id_##]">
+    <!-- 주석 -->
+    <ul class="list">
+      <li>하나</li>
+      <li>둘 <b>굵게</b> <i>기울임</i></li>
+    </ul>
+    <p>첫   번째</p>
+    <p>두 번째</p>
+    <pre>
+  코드  그대로</pre>
+    <input type="checkbox" checked="checked" disabled="" value="a b">
+    <my-element>
+      안녕하세요~ ^^
+    </my-element>
+  </body>
+</html>
+"#;
+
+fn parse(html: &str) -> Handle {
+  parse_document(RcDom::default(), Default::default()).one(html).document
+}
+
+#[test]
+fn minify_default_test() {
+  let document = parse(HTML);
+  let (html, report) = minify(&document, &MinifyOptions::default());
+  assert_eq!(
+    html,
+    "<!DOCTYPE html><title>테스트&gt;</title><!--[if IE]><link rel=\"stylesheet\" href=\"ie.css\"><![endif]--><body id=[##_id_##]>\
+<ul class=list><li>하나<li>둘 <b>굵게</b> <i>기울임</i></ul><p>첫 번째<p>두 번째<pre>  코드  그대로</pre>\
+<input type=checkbox checked disabled value=\"a b\"> <my-element> 안녕하세요~ ^^ </my-element>"
+  );
+  assert_eq!(report.minified_bytes, html.len());
+  assert!(report.saved_bytes() > 100);
+  assert!(report.saved_percent() > 20.0);
+}
+
+#[test]
+fn minify_keeps_rendering_test() {
+  let document = parse(HTML);
+  let before = inner_text(&document);
+  let (html, _) = minify(&document, &MinifyOptions::default());
+
+  // The minified markup parses back into a tree that renders the same text.
+  let reparsed = parse(&html);
+  assert_eq!(inner_text(&reparsed), before);
+  let pre = select_one(&reparsed, "pre").unwrap().unwrap();
+  assert_eq!(outer_html(&pre), "<pre>  코드  그대로</pre>");
+}
+
+#[test]
+fn minify_options_test() {
+  let options = MinifyOptions {
+    keep_conditional_comments: false,
+    omit_optional_tags: false,
+    remove_attribute_quotes: false,
+    collapse_boolean_attributes: false,
+    ..Default::default()
+  };
+  let document = parse(HTML);
+  let (html, _) = minify(&document, &options);
+  assert!(html.starts_with("<!DOCTYPE html><html><head><title>테스트&gt;</title></head><body id=\"[##_id_##]\"><ul class=\"list\"><li>하나</li>"));
+  assert!(html.contains("<input type=\"checkbox\" checked=\"checked\" disabled=\"\" value=\"a b\">"));
+  assert!(html.ends_with("</my-element></body></html>"));
+
+  let options = MinifyOptions {
+    collapse_whitespace: false,
+    remove_comments: false,
+    ..Default::default()
+  };
+  let document = parse(HTML);
+  let (html, _) = minify(&document, &options);
+  assert!(html.contains("<!-- 주석 -->"));
+  assert!(html.contains("\n    <ul class=list>\n      <li>하나</li>\n"));
+}
+
+#[test]
+fn minify_element_test() {
+  let document = parse("<div><p>하나</p><p>둘</p></div><p>셋</p>");
+  let div = select_one(&document, "div").unwrap().unwrap();
+  let (html, _) = minify(&div, &MinifyOptions::default());
+  // The last <p> is closed by the end of the <div>.
+  assert_eq!(html, "<div><p>하나<p>둘</div>");
+}
+
+#[test]
+fn minify_invisible_inline_elements_test() {
+  let document = parse("<p>a <script>x()</script> b <template></template> c <!-- 주석 --> d</p>");
+  let (html, _) = minify(&document, &MinifyOptions::default());
+  assert_eq!(html, "<p>a <script>x()</script>b <template></template>c d");
+
+  let reparsed = parse(&html);
+  assert_eq!(inner_text(&reparsed), inner_text(&document));
+  assert_eq!(inner_text(&reparsed), "a b c d");
+}
+
+#[test]
+fn minify_leaves_tree_test() {
+  let document = parse(HTML);
+  let before = outer_html(&document);
+  minify(&document, &MinifyOptions::default());
+  assert_eq!(outer_html(&document), before);
+}
+
+#[test]
+fn minify_legacy_doctype_test() {
+  let document = parse(r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01//EN"><p>테스트</p>"#);
+  let (html, _) = minify(&document, &MinifyOptions::default());
+  assert_eq!(html, "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"><p>테스트");
+}
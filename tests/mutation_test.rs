@@ -0,0 +1,104 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{mutation::{append, clone_node, detach, insert_after, insert_before, prepend, replace_with, unwrap, wrap, MutationError}, node::DomNode, rcdom::{inner_html, node_create, node_select_one, outer_html}};
+
+fn parse(html: &str) -> Handle {
+  parse_document(RcDom::default(), Default::default()).one(html).document
+}
+
+fn body(document: &Handle) -> Handle {
+  node_select_one(document, "body", &None).unwrap()
+}
+
+/// Every child's weak parent link points back at the node holding it.
+fn assert_consistent(handle: &Handle) {
+  for child in handle.children.borrow().iter() {
+    assert!(child.parent().unwrap().same_node(handle));
+    assert_consistent(child);
+  }
+}
+
+#[test]
+fn mutation_insert_test() {
+  let document = parse("<ul><li>둘</li></ul>");
+  let ul = node_select_one(&document, "ul", &None).unwrap();
+  let two = ul.children.borrow()[0].clone();
+
+  let one = node_create("li", &None);
+  let three = node_create("li", &None);
+  prepend(&ul, &one).unwrap();
+  append(&ul, &three).unwrap();
+  insert_before(&two, &node_create("i", &None)).unwrap();
+  insert_after(&two, &node_create("span", &None)).unwrap();
+
+  assert_eq!(inner_html(&ul), "<li></li><i></i><li>둘</li><span></span><li></li>");
+  assert_consistent(&document);
+
+  // Inserting a node that already has a parent moves it.
+  insert_after(&three, &one).unwrap();
+  assert_eq!(inner_html(&ul), "<i></i><li>둘</li><span></span><li></li><li></li>");
+  assert!(ul.children.borrow()[4].same_node(&one));
+  assert_consistent(&document);
+}
+
+#[test]
+fn mutation_errors_test() {
+  let document = parse("<div><p>안녕</p></div>");
+  let div = node_select_one(&document, "div", &None).unwrap();
+  let p = node_select_one(&document, "p", &None).unwrap();
+  let text = p.children.borrow()[0].clone();
+
+  assert_eq!(append(&p, &div), Err(MutationError::Cycle));
+  assert_eq!(append(&div, &div), Err(MutationError::Cycle));
+  assert_eq!(append(&text, &node_create("b", &None)), Err(MutationError::NotAContainer));
+  assert_eq!(insert_before(&node_create("b", &None), &p), Err(MutationError::NoParent));
+  assert_eq!(unwrap(&node_create("b", &None)), Err(MutationError::NoParent));
+  assert_eq!(inner_html(&div), "<p>안녕</p>");
+}
+
+#[test]
+fn mutation_replace_wrap_unwrap_test() {
+  let document = parse("<p>하나 <b>둘</b> 셋</p>");
+  let p = node_select_one(&document, "p", &None).unwrap();
+  let b = node_select_one(&document, "b", &None).unwrap();
+
+  let strong = node_create("strong", &None);
+  replace_with(&b, &strong).unwrap();
+  assert!(b.parent().is_none());
+  assert_eq!(inner_html(&p), "하나 <strong></strong> 셋");
+
+  let em = node_create("em", &None);
+  wrap(&p, &em).unwrap();
+  assert_eq!(inner_html(&body(&document)), "<em><p>하나 <strong></strong> 셋</p></em>");
+
+  unwrap(&p).unwrap();
+  assert!(p.parent().is_none());
+  assert_eq!(inner_html(&body(&document)), "<em>하나 <strong></strong> 셋</em>");
+  assert_consistent(&document);
+}
+
+#[test]
+fn mutation_clone_and_move_between_documents_test() {
+  let source = parse("<div class=\"card\"><p>안녕</p><template><i>t</i></template></div>");
+  let target = parse("<section></section>");
+  let div = node_select_one(&source, "div", &None).unwrap();
+  let section = node_select_one(&target, "section", &None).unwrap();
+
+  let shallow = clone_node(&div, false);
+  assert_eq!(outer_html(&shallow), "<div class=\"card\"></div>");
+
+  let deep = clone_node(&div, true);
+  assert!(deep.parent().is_none());
+  assert_eq!(outer_html(&deep), outer_html(&div));
+  let template = node_select_one(&deep, "template", &None).unwrap();
+  assert_eq!(inner_html(&template), "<i>t</i>");
+
+  append(&section, &div).unwrap();
+  assert!(node_select_one(&source, "div", &None).is_none());
+  assert_eq!(inner_html(&section), "<div class=\"card\"><p>안녕</p><template><i>t</i></template></div>");
+  assert_consistent(&target);
+
+  detach(&div);
+  assert!(div.parent().is_none());
+  assert!(section.children.borrow().is_empty());
+}
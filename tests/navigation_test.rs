@@ -0,0 +1,59 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, node::DomNode, rcdom::{node_parent, node_select_one}, select::{closest, select_one}};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <body id="[##_id_##]">
+    <div class="card"><p>하나</p> 텍스트 <!-- 주석 --><p id="two">둘</p><span>셋</span></div>
+  </body>
+</html>
+"#;
+
+fn first_text<N: DomNode>(node: &N) -> String {
+  node.children()[0].text().unwrap().to_string()
+}
+
+#[test]
+fn navigation_parent_is_not_destructive_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let two = node_select_one(&dom.document, "p", &Some(&vec![("id", "two")])).unwrap();
+
+  let div = node_parent(&two).unwrap();
+  assert!(node_parent(&two).unwrap().same_node(&div));
+  assert!(two.parent().unwrap().same_node(&div));
+}
+
+#[test]
+fn navigation_rcdom_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let two = select_one(&dom.document, "#two").unwrap().unwrap();
+
+  let names: Vec<String> = two.ancestors().iter().filter_map(|node| node.element_name().map(|name| name.local.to_string())).collect();
+  assert_eq!(names, ["div", "body", "html"]);
+  assert!(two.root().same_node(&dom.document));
+  assert_eq!(two.index_in_parent(), Some(3));
+
+  assert_eq!(first_text(&two.previous_element_sibling().unwrap()), "하나");
+  assert_eq!(first_text(&two.next_element_sibling().unwrap()), "셋");
+  assert!(two.next_element_sibling().unwrap().next_element_sibling().is_none());
+
+  assert!(closest(&two, "p").unwrap().unwrap().same_node(&two));
+  assert_eq!(closest(&two, ".card").unwrap().unwrap().attr("class").as_deref(), Some("card"));
+  assert!(closest(&two, "section").unwrap().is_none());
+  assert!(closest(&two, "p[").is_err());
+  assert!(dom.document.index_in_parent().is_none());
+}
+
+#[test]
+fn navigation_arena_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+  let two = select_one(&document, "#two").unwrap().unwrap();
+
+  assert_eq!(DomNode::ancestors(&two).len(), 4);
+  assert!(two.root().same_node(&document));
+  assert_eq!(two.index_in_parent(), Some(3));
+  assert_eq!(two.previous_element_sibling().unwrap().element_name().unwrap().local.as_ref(), "p");
+  assert!(closest(&two, "body").unwrap().is_some());
+}
@@ -0,0 +1,80 @@
+use html5ever::{parse_document, tendril::TendrilSink, tree_builder::QuirksMode};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, diagnostics::parse_document_with_diagnostics, quirks::{audit, classify, QuirksReason}};
+
+#[test]
+fn quirks_standard_doctype_test() {
+  let result = parse_document_with_diagnostics(RcDom::default(), "<!DOCTYPE html><p>안녕</p>");
+  assert_eq!(result.quirks_mode, QuirksMode::NoQuirks);
+
+  let report = audit(&result.output.document, result.quirks_mode);
+  assert!(!report.is_legacy());
+  assert_eq!(report.reason, QuirksReason::Standard);
+  assert_eq!(report.doctype.unwrap().name, "html");
+}
+
+#[test]
+fn quirks_missing_doctype_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one("<p>안녕</p>");
+  assert_eq!(dom.quirks_mode, QuirksMode::Quirks);
+
+  let report = audit(&dom.document, dom.quirks_mode);
+  assert!(report.is_legacy());
+  assert_eq!(report.doctype, None);
+  assert_eq!(report.reason, QuirksReason::MissingDoctype);
+}
+
+#[test]
+fn quirks_legacy_doctype_test() {
+  let html = r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN"><p>a</p>"#;
+  let arena = ArenaDom::new();
+  let result = arena.parse_document_with_diagnostics(html);
+  assert_eq!(result.quirks_mode, QuirksMode::Quirks);
+
+  let report = audit(&result.output, result.quirks_mode);
+  let doctype = report.doctype.clone().unwrap();
+  assert_eq!(doctype.public_id, "-//W3C//DTD HTML 4.01 Transitional//EN");
+  assert_eq!(doctype.system_id, "");
+  assert_eq!(report.reason, QuirksReason::Html4WithoutSystemId("-//w3c//dtd html 4.01 transitional//"));
+  assert!(report.reason.to_string().contains("no system identifier"));
+}
+
+#[test]
+fn quirks_limited_doctype_test() {
+  let html = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Transitional//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd">"#;
+  let result = parse_document_with_diagnostics(RcDom::default(), html);
+  assert_eq!(result.quirks_mode, QuirksMode::LimitedQuirks);
+  assert_eq!(result.output.quirks_mode, QuirksMode::LimitedQuirks);
+
+  let report = audit(&result.output.document, result.quirks_mode);
+  assert_eq!(report.reason, QuirksReason::LimitedQuirkyPublicIdPrefix("-//w3c//dtd xhtml 1.0 transitional//"));
+}
+
+#[test]
+fn quirks_malformed_doctype_test() {
+  let result = parse_document_with_diagnostics(RcDom::default(), "<!DOCTYPE html SYSTEM><p>a</p>");
+  assert_eq!(result.quirks_mode, QuirksMode::Quirks);
+  assert_eq!(audit(&result.output.document, result.quirks_mode).reason, QuirksReason::Malformed);
+}
+
+#[test]
+fn quirks_classify_test() {
+  assert_eq!(classify("html", "", ""), (QuirksMode::NoQuirks, QuirksReason::Standard));
+  assert_eq!(classify("svg", "", ""), (QuirksMode::Quirks, QuirksReason::NotHtml));
+  assert_eq!(classify("html", "HTML", ""), (QuirksMode::Quirks, QuirksReason::QuirkyPublicId("html")));
+  assert_eq!(
+    classify("html", "-//W3C//DTD HTML 4.01 Frameset//EN", "http://www.w3.org/TR/html4/frameset.dtd"),
+    (QuirksMode::LimitedQuirks, QuirksReason::Html4WithSystemId("-//w3c//dtd html 4.01 frameset//"))
+  );
+}
+
+#[test]
+fn quirks_empty_system_id_test() {
+  let html = r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN" ""><p>a</p>"#;
+  let result = parse_document_with_diagnostics(RcDom::default(), html);
+  assert_eq!(result.quirks_mode, QuirksMode::LimitedQuirks);
+
+  let report = audit(&result.output.document, result.quirks_mode);
+  assert_eq!(report.doctype.unwrap().system_id, "");
+  assert_eq!(report.reason, QuirksReason::Html4WithSystemId("-//w3c//dtd html 4.01 transitional//"));
+}
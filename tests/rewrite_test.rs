@@ -0,0 +1,168 @@
+use rust_html5ever_sample::{attributes::Attributes, rewrite::{rewrite_str, Rewriter}, template::TemplateContext};
+
+#[test]
+fn rewrite_element_handlers_test() {
+  let html = rewrite_str(r#"<div id="main"><p class="note">안녕하세요</p><p>삭제<b>될</b> 문단</p><span>풀기</span></div>"#, |rewriter| {
+    rewriter
+      .on_element("p.note", |element| {
+        element.set_attr("class", "note important");
+        element.before("<hr>");
+        element.append("!");
+      })?
+      .on_element("p:not(.note)", |element| element.remove())?
+      .on_element("#main > span", |element| element.unwrap())?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, r#"<div id="main"><hr><p class="note important">안녕하세요!</p>풀기</div>"#);
+}
+
+#[test]
+fn rewrite_replace_and_inner_html_test() {
+  let html = rewrite_str("<ul><li>하나<li>둘</ul><section><h1>제목</h1><p>본문</section>", |rewriter| {
+    rewriter
+      .on_element("li", |element| element.prepend("- "))?
+      .on_element("section", |element| element.set_inner_html("<p>새 본문</p>"))?
+      .on_element("ul > li:first-child, h1", |element| element.replace("<em>교체</em>"))
+      .map(|_| ())
+  });
+  // Sibling selectors need the tree the stream never keeps.
+  assert!(html.is_err());
+
+  let html = rewrite_str("<ul><li>하나<li>둘</ul><section><h1>제목</h1><p>본문</section>", |rewriter| {
+    rewriter
+      .on_element("ul > li", |element| element.prepend("- "))?
+      .on_element("section", |element| element.set_inner_html("<p>새 본문</p>"))?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, "<ul><li>- 하나<li>- 둘</ul><section><p>새 본문</p></section>");
+}
+
+#[test]
+fn rewrite_implied_end_tags_test() {
+  let mut closed = vec![];
+  let html = rewrite_str("<table><tr><td>가<td>나<tr><td>다</table><p>문단<div>블록</div>", |rewriter| {
+    rewriter
+      .on_end_tag("td, p", |end| closed.push(format!("{}{}", end.name(), if end.is_implied() { "?" } else { "" })))?
+      .on_text("tr > td", |text| text.set_text(&text.text().repeat(2)))?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, "<table><tr><td>가가<td>나나<tr><td>다다</table><p>문단<div>블록</div>");
+  assert_eq!(closed, vec!["td?", "td?", "td?", "p?"]);
+}
+
+#[test]
+fn rewrite_attribute_and_comment_handlers_test() {
+  let html = rewrite_str(r#"<a href="/문서?a=1&amp;b=2">링크</a><img src="a.png"><div><!-- 메모 --></div><p><!--지우기--></p>"#, |rewriter| {
+    rewriter
+      .on_attribute("a", "href", |value| value.insert_str(0, "https://example.com"))?
+      .on_attribute("img", "src", |value| *value = value.replace(".png", ".webp"))?
+      .on_comment("p", |comment| comment.remove())?
+      .on_comment("*", |comment| {
+        let text = comment.text().trim().to_string();
+        comment.set_text(&text);
+      })?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, r#"<a href="https://example.com/문서?a=1&amp;b=2">링크</a><img src="a.webp"><div><!--메모--></div><p></p>"#);
+}
+
+#[test]
+fn rewrite_raw_text_and_foreign_content_test() {
+  let mut texts = vec![];
+  let html = rewrite_str("<script>if (a < b) { x = '<p>'; }</script><svg><title>그림</title><rect/></svg><textarea><b>굵게</b></textarea>", |rewriter| {
+    rewriter.on_text("*", |text| texts.push(format!("{}:{}", text.parent().name(), text.text())))?;
+    rewriter.on_element("svg > title", |element| element.set_attr("lang", "ko"))?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, r#"<script>if (a < b) { x = '<p>'; }</script><svg><title lang="ko">그림</title><rect /></svg><textarea>&lt;b&gt;굵게&lt;/b&gt;</textarea>"#);
+  assert_eq!(texts, vec!["script:if (a < b) { x = '<p>'; }", "title:그림", "textarea:<b>굵게</b>"]);
+}
+
+#[test]
+fn rewrite_placeholders_test() {
+  let context: TemplateContext = [
+    ("title", "<b>A & B</b></script>"),
+    ("id", "main\" onload=\"x"),
+    ("name", "홍길동"),
+    ("extra", "unused"),
+  ]
+  .into_iter()
+  .collect();
+  let html = r#"<title>[##_title_##]</title><body id="[##_id_##]"><p>[##_name_##]님 [##_missing_##]</p><script>var t = "[##_title_##]";</script>"#;
+
+  let mut rewriter = Rewriter::new(vec![]);
+  rewriter.replace_placeholders(&context);
+  // One byte at a time splits both the Korean characters and the markers.
+  for byte in html.as_bytes().chunks(1) {
+    rewriter.feed(byte).unwrap();
+  }
+  let (output, report) = rewriter.finish().unwrap();
+  assert_eq!(
+    String::from_utf8(output).unwrap(),
+    r#"<title>&lt;b&gt;A &amp; B&lt;/b&gt;&lt;/script&gt;</title><body id="main&quot; onload=&quot;x"><p>홍길동님 [##_missing_##]</p><script>var t = "<b>A & B</b><\/script>";</script>"#
+  );
+  assert_eq!(report.unknown, vec!["missing"]);
+  assert_eq!(report.unused, vec!["extra"]);
+}
+
+#[test]
+fn rewrite_long_text_in_chunks_test() {
+  let marker = "[##_name_##]";
+  let mut html = String::from("<pre>");
+  for _ in 0..4000 {
+    html.push_str("한글 ");
+    html.push_str(marker);
+  }
+  html.push_str("</pre>");
+  let context: TemplateContext = [("name", "값")].into_iter().collect();
+
+  let mut chunks = 0;
+  let mut last = 0;
+  let mut rewriter = Rewriter::new(vec![]);
+  rewriter.replace_placeholders(&context).on_text("pre", |text| {
+    chunks += 1;
+    assert!(text.text().len() <= 32 * 1024);
+    last += text.is_last_in_run() as usize;
+  }).unwrap();
+  for chunk in html.as_bytes().chunks(1000) {
+    rewriter.feed(chunk).unwrap();
+  }
+  let (output, report) = rewriter.finish().unwrap();
+  let output = String::from_utf8(output).unwrap();
+  assert!(!output.contains("[##_"));
+  assert_eq!(output.matches("값").count(), 4000);
+  assert!(report.unknown.is_empty());
+  assert!(chunks > 1);
+  assert_eq!(last, 1);
+}
+
+#[test]
+fn rewrite_end_tag_scope_test() {
+  // The parser ignores a </div> inside a table cell, so the cell, row and table stay open.
+  let mut texts = vec![];
+  let html = rewrite_str("<div><table><tr><td></div>가</td></tr></table>나</div>", |rewriter| {
+    rewriter.on_text("td", |text| texts.push(text.text().to_string()))?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, "<div><table><tr><td></div>가</td></tr></table>나</div>");
+  assert_eq!(texts, vec!["가"]);
+}
+
+#[test]
+fn rewrite_end_tag_namespace_test() {
+  // Inside <desc> the </a> is an HTML end tag, which does not close the SVG <a>.
+  let mut closed = vec![];
+  let html = rewrite_str("<svg><a><desc><p>가</a>나</p></desc></a></svg>", |rewriter| {
+    rewriter.on_end_tag("*", |end| closed.push(end.name().to_string()))?;
+    Ok(())
+  })
+  .unwrap();
+  assert_eq!(html, "<svg><a><desc><p>가</a>나</p></desc></a></svg>");
+  assert_eq!(closed, vec!["p", "desc", "a", "svg"]);
+}
@@ -0,0 +1,88 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{rcdom::inner_html, sanitize::{sanitize, Policy}, select::select_one};
+
+/// The document and its `<body>`. The document has to outlive the body: dropping it empties the whole tree.
+fn parse(html: &str) -> (Handle, Handle) {
+  let document = parse_document(RcDom::default(), Default::default()).one(html).document;
+  let body = select_one(&document, "body").unwrap().unwrap();
+  (document, body)
+}
+
+fn clean(html: &str, policy: &Policy) -> String {
+  let (_document, body) = parse(html);
+  sanitize(&body, policy);
+  inner_html(&body)
+}
+
+#[test]
+fn sanitize_default_policy_test() {
+  let (_document, body) = parse(r#"<p class="intro" style="color: red">안녕 <b onclick="alert(1)">하세요</b><script>alert(1)</script></p><!-- 주석 --><custom-box>상자</custom-box>"#);
+  let report = sanitize(&body, &Policy::default());
+  assert_eq!(inner_html(&body), r#"<p class="intro">안녕 <b>하세요</b></p>상자"#);
+  assert_eq!(report.removed_elements, vec!["script", "custom-box"]);
+  assert_eq!(report.removed_attributes, vec!["style", "onclick"]);
+}
+
+#[test]
+fn sanitize_keeps_or_drops_children_test() {
+  let policy = Policy::default();
+  assert_eq!(clean("<section><p>본문</p></section>", &policy), "<p>본문</p>");
+  assert_eq!(clean("<div><style>p { color: red }</style><iframe src=\"x\"></iframe>남음</div>", &policy), "<div>남음</div>");
+  assert_eq!(clean("<font><font><i>중첩</i></font></font>", &policy), "<i>중첩</i>");
+}
+
+#[test]
+fn sanitize_url_schemes_test() {
+  let policy = Policy::default();
+  assert_eq!(clean(r#"<a href="https://example.com/">링크</a>"#, &policy), r#"<a href="https://example.com/">링크</a>"#);
+  assert_eq!(clean(r#"<a href="/posts/1?a=b:c#top">상대</a>"#, &policy), r#"<a href="/posts/1?a=b:c#top">상대</a>"#);
+  assert_eq!(clean(r#"<a href="javascript:alert(1)">x</a>"#, &policy), "<a>x</a>");
+  assert_eq!(clean(r#"<a href=" JaVaScRiPt:alert(1)">x</a>"#, &policy), "<a>x</a>");
+  assert_eq!(clean("<a href=\"java&#9;script:alert(1)\">x</a>", &policy), "<a>x</a>");
+  assert_eq!(clean("<a href=\"&#1;javascript:alert(1)\">x</a>", &policy), "<a>x</a>");
+  assert_eq!(clean(r#"<img src="data:image/svg+xml;base64,PHN2Zz4=" alt="그림">"#, &policy), r#"<img alt="그림">"#);
+
+  let mut policy = Policy::default();
+  policy.allow_url_schemes(&["data:"]).allow_attributes("img", &["srcset"]);
+  assert_eq!(clean(r#"<img src="data:image/png;base64,AAAA">"#, &policy), r#"<img src="data:image/png;base64,AAAA">"#);
+  assert_eq!(clean(r#"<img srcset="a.png 1x, javascript:alert(1) 2x">"#, &policy), "<img>");
+  assert_eq!(clean(r#"<img srcset="a.png 1x, /b.png 2x">"#, &policy), r#"<img srcset="a.png 1x, /b.png 2x">"#);
+}
+
+#[test]
+fn sanitize_event_handlers_are_never_allowed_test() {
+  let mut policy = Policy::default();
+  policy.allow_attributes("*", &["onclick", "id"]);
+  assert_eq!(clean(r#"<p id="a" onclick="alert(1)" ONMOUSEOVER="alert(2)">x</p>"#, &policy), r#"<p id="a">x</p>"#);
+}
+
+#[test]
+fn sanitize_foreign_content_test() {
+  let mut policy = Policy::default();
+  policy.allow_elements(&["svg", "math", "style", "a"]);
+  let (_document, body) = parse(r#"<p>앞</p><svg><style>&lt;img src=x onerror=alert(1)&gt;</style><a xlink:href="javascript:alert(1)">x</a></svg><math><mi><style></style></mi></math><p>뒤</p>"#);
+  let report = sanitize(&body, &policy);
+  assert_eq!(inner_html(&body), "<p>앞</p><p>뒤</p>");
+  assert_eq!(report.removed_elements, vec!["svg", "math"]);
+}
+
+#[test]
+fn sanitize_template_contents_test() {
+  assert_eq!(clean("<p>밖</p><template><p>안</p></template>", &Policy::default()), "<p>밖</p>");
+
+  let mut policy = Policy::default();
+  policy.allow_elements(&["template"]);
+  assert_eq!(
+    clean(r#"<p>밖</p><template><p onclick="alert(1)">안</p><script>alert(1)</script><a href="javascript:x">링크</a></template>"#, &policy),
+    "<p>밖</p><template><p>안</p><a>링크</a></template>"
+  );
+}
+
+#[test]
+fn sanitize_empty_policy_keeps_text_test() {
+  let (_document, body) = parse("<h1>제목</h1><p>본문 <a href=\"/\">링크</a></p><script>alert(1)</script>");
+  sanitize(&body, &Policy::new());
+  assert_eq!(inner_html(&body), "제목본문 링크");
+  assert_eq!(body.children.borrow().len(), 3);
+}
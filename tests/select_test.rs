@@ -0,0 +1,100 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{arena::ArenaDom, node::DomNode, select::{select, select_one, Selector}};
+
+const HTML: &str = r#"
+  <!DOCTYPE html>
+  <html>
+    <body id="[##_id_##]">
+      <div class="card first" data-id="x1"><p>하나</p></div>
+      <div class="card" data-id="y2"><p>둘</p><img src="a.png"></div>
+      <div class="card" data-id="x3" lang="ko-KR"><span>셋</span></div>
+      <div class="other" data-id="x4"></div>
+      <svg><foreignObject></foreignObject><circle r="1"/></svg>
+    </body>
+  </html>
+"#;
+
+fn parse(html: &str) -> Handle {
+  parse_document(RcDom::default(), Default::default()).one(html).document
+}
+
+fn ids<N: DomNode>(nodes: &[N]) -> Vec<String> {
+  nodes.iter().map(|node| node.attr("data-id").unwrap_or_default()).collect()
+}
+
+#[test]
+fn select_basic_test() {
+  let document = parse(HTML);
+
+  assert_eq!(ids(&select(&document, "div").unwrap()), vec!["x1", "y2", "x3", "x4"]);
+  assert_eq!(ids(&select(&document, ".card").unwrap()), vec!["x1", "y2", "x3"]);
+  assert_eq!(ids(&select(&document, "div.card.first").unwrap()), vec!["x1"]);
+  assert_eq!(select(&document, "#\\[\\#\\#_id_\\#\\#\\]").unwrap().len(), 1);
+  assert_eq!(ids(&select(&document, "[data-id^=x]").unwrap()), vec!["x1", "x3", "x4"]);
+  assert_eq!(ids(&select(&document, "[data-id$='2']").unwrap()), vec!["y2"]);
+  assert_eq!(ids(&select(&document, "[data-id*=\"3\"]").unwrap()), vec!["x3"]);
+  assert_eq!(ids(&select(&document, "[class~=first]").unwrap()), vec!["x1"]);
+  assert_eq!(ids(&select(&document, "[lang|=ko]").unwrap()), vec!["x3"]);
+  assert_eq!(ids(&select(&document, "[DATA-ID=X1 i]").unwrap()), vec!["x1"]);
+  assert_eq!(ids(&select(&document, "[data-id=X1]").unwrap()), Vec::<String>::new());
+}
+
+#[test]
+fn select_combinator_test() {
+  let document = parse(HTML);
+
+  assert_eq!(select(&document, "body > div.card[data-id^=x]:nth-child(2n+1)").unwrap().len(), 2);
+  assert_eq!(select(&document, "body p").unwrap().len(), 2);
+  assert_eq!(select(&document, "html > p").unwrap().len(), 0);
+  assert_eq!(ids(&select(&document, ".first + div").unwrap()), vec!["y2"]);
+  assert_eq!(ids(&select(&document, ".first ~ div").unwrap()), vec!["y2", "x3", "x4"]);
+}
+
+#[test]
+fn select_pseudo_class_test() {
+  let document = parse(HTML);
+
+  assert_eq!(ids(&select(&document, "div:not(.card)").unwrap()), vec!["x4"]);
+  assert_eq!(ids(&select(&document, "div:is(.first, .other)").unwrap()), vec!["x1", "x4"]);
+  assert_eq!(ids(&select(&document, "div:has(> img)").unwrap()), vec!["y2"]);
+  assert_eq!(ids(&select(&document, "div:has(+ .other)").unwrap()), vec!["x3"]);
+  assert_eq!(ids(&select(&document, "div:first-child").unwrap()), vec!["x1"]);
+  assert_eq!(ids(&select(&document, "div:last-of-type").unwrap()), vec!["x4"]);
+  assert_eq!(ids(&select(&document, "div:nth-last-child(2)").unwrap()), vec!["x4"]);
+  assert_eq!(ids(&select(&document, "div:nth-child(-n+2)").unwrap()), vec!["x1", "y2"]);
+  assert_eq!(ids(&select(&document, "div:nth-child(odd of .card)").unwrap()), vec!["x1", "x3"]);
+  assert_eq!(ids(&select(&document, "div:empty").unwrap()), vec!["x4"]);
+  assert_eq!(select(&document, "p:only-child").unwrap().len(), 1);
+  assert_eq!(select(&document, ":root").unwrap().len(), 1);
+}
+
+#[test]
+fn select_namespace_test() {
+  let document = parse(HTML);
+
+  assert_eq!(select(&document, "svg|circle").unwrap().len(), 1);
+  assert_eq!(select(&document, "html|circle").unwrap().len(), 0);
+  assert_eq!(select(&document, "*|foreignObject").unwrap().len(), 1);
+  assert_eq!(select(&document, "foreignobject").unwrap().len(), 0);
+}
+
+#[test]
+fn select_arena_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+
+  let selector = Selector::parse("body > div.card[data-id^=x]:nth-child(2n+1)").unwrap();
+  assert_eq!(ids(&selector.select(&document)), vec!["x1", "x3"]);
+  assert_eq!(ids(&select(&document, "div:has(> img)").unwrap()), vec!["y2"]);
+  assert!(select_one(&document, "table").unwrap().is_none());
+}
+
+#[test]
+fn select_error_test() {
+  assert!(Selector::parse("div >").is_err());
+  assert!(Selector::parse("div[").is_err());
+  assert!(Selector::parse("p::before").is_err());
+  assert!(Selector::parse("foo|p").is_err());
+  assert_eq!(Selector::parse("div )").unwrap_err().position, 4);
+}
@@ -0,0 +1,154 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{rcdom::{convert_node_to_html_string, node_parent}, select::select_one, text::text_content, template::{find_placeholders, render_to_string, render_with_options, replace_placeholders, RenderReport, TemplateContext, TemplateOptions}};
+
+fn parse(html: &str) -> Handle {
+  parse_document(RcDom::default(), Default::default()).one(html).document
+}
+
+#[test]
+fn template_replace_placeholders_test() {
+  let result = replace_placeholders("a [##_x_##] b [##_y_z_##] [##_ bad_##] [##_", |name| {
+    if name == "x" { Some("X".to_string()) } else { None }
+  });
+  assert_eq!(result, "a X b [##_y_z_##] [##_ bad_##] [##_");
+  assert_eq!(find_placeholders("[##_a_##][##_b_c_##]"), vec!["a", "b_c"]);
+}
+
+#[test]
+fn template_render_test() {
+  let document = parse(r#"
+    <html>
+      <head><title>[##_title_##]</title></head>
+      <body id="[##_id_##]">
+        <s3>[##_greeting_##], [##_name_##]!</s3>
+        <script>var title = "[##_title_##]";</script>
+        <template><p>[##_title_##]</p></template>
+      </body>
+    </html>
+  "#);
+
+  let context: TemplateContext = [
+    ("title", "<b>A & B</b></script>"),
+    ("id", "main\" onload=\"x"),
+    ("greeting", "안녕하세요"),
+    ("extra", "unused"),
+  ]
+  .into_iter()
+  .collect();
+  let (html, report) = render_to_string(&document, &context);
+
+  assert!(html.contains("<title>&lt;b&gt;A &amp; B&lt;/b&gt;&lt;/script&gt;</title>"));
+  assert!(html.contains("<body id=\"main&quot; onload=&quot;x\">"));
+  assert!(html.contains("<s3>안녕하세요, [##_name_##]!</s3>"));
+  assert!(html.contains("var title = \"<b>A & B</b><\\/script>\";"));
+  assert_eq!(report.unknown, vec!["name"]);
+  assert_eq!(report.unused, vec!["extra"]);
+}
+
+#[test]
+fn template_section_test() {
+  let document = parse(r#"
+    <ul><s_item><li class="[##_class_##]">[##_title_##] ([##_site_##])</li></s_item></ul>
+    <s_notice><p>공지</p></s_notice>
+    <s_hidden><p>숨김</p></s_hidden>
+    <s_missing><p>없음</p></s_missing>
+  "#);
+
+  let items = ["하나", "둘"]
+    .iter()
+    .enumerate()
+    .map(|(i, title)| {
+      let mut item = TemplateContext::new();
+      item.set("title", title).set("class", &format!("item-{}", i));
+      item
+    })
+    .collect();
+  let mut context = TemplateContext::new();
+  context
+    .set("site", "블로그")
+    .set_list("s_item", items)
+    .set_flag("s_notice", true)
+    .set_flag("s_hidden", false);
+
+  let (html, report) = render_to_string(&document, &context);
+  assert!(html.contains("<ul><li class=\"item-0\">하나 (블로그)</li><li class=\"item-1\">둘 (블로그)</li></ul>"));
+  assert!(html.contains("<p>공지</p>"));
+  assert!(!html.contains("숨김"));
+  assert!(!html.contains("없음"));
+  assert!(!html.contains("s_"));
+  assert_eq!(report.missing_sections, vec!["s_missing"]);
+  assert!(report.unknown.is_empty());
+  assert!(report.unused.is_empty());
+}
+
+#[test]
+fn template_section_tags_option_test() {
+  let document = parse("<body><s3><p>[##_name_##]</p></s3><my-element>그대로</my-element></body>");
+  let options = TemplateOptions {
+    section_prefix: None,
+    section_tags: vec!["s3".to_string()],
+  };
+
+  let mut item = TemplateContext::new();
+  item.set("name", "테스트");
+  let mut context = TemplateContext::new();
+  context.set_list("s3", vec![item.clone(), item]);
+
+  let report = render_with_options(&document, &context, &options);
+  let html = convert_node_to_html_string(&document);
+  assert_eq!(html, "<html><head></head><body><p>테스트</p><p>테스트</p><my-element>그대로</my-element></body></html>");
+  assert_eq!(report, RenderReport::default());
+}
+
+#[test]
+fn template_nested_section_test() {
+  let document = parse("<div id=\"list\"><s_post><h2>[##_title_##]</h2><s_tag><span>[##_tag_##]</span></s_tag></s_post></div>");
+  let post = |title: &str, tags: &[&str]| {
+    let mut post = TemplateContext::new();
+    post.set("title", title).set_list(
+      "s_tag",
+      tags
+        .iter()
+        .map(|tag| {
+          let mut item = TemplateContext::new();
+          item.set("tag", tag);
+          item
+        })
+        .collect(),
+    );
+    post
+  };
+  let mut context = TemplateContext::new();
+  context.set_list("s_post", vec![post("첫 글", &["일기", "여행"]), post("둘째 글", &[])]);
+
+  let report = render_with_options(&document, &context, &TemplateOptions::default());
+  assert_eq!(report, RenderReport::default());
+  let list = select_one(&document, "#list").unwrap().unwrap();
+  assert_eq!(
+    convert_node_to_html_string(&list),
+    "<h2>첫 글</h2><span>일기</span><span>여행</span><h2>둘째 글</h2>"
+  );
+  // The expanded nodes are attached to the section's parent.
+  for child in list.children.borrow().iter() {
+    assert!(std::rc::Rc::ptr_eq(&node_parent(child).unwrap(), &list));
+  }
+}
+
+#[test]
+fn template_raw_text_escaping_test() {
+  let document = parse(r#"<style>.a::after { content: "[##_css_##]"; }</style><script>var s = "[##_js_##]";</script>"#);
+  let context: TemplateContext = [
+    ("css", "a</b </STYLE><p>"),
+    ("js", "<!-- <SCRIPT> </b> </Script >"),
+  ]
+  .into_iter()
+  .collect();
+  let (html, _) = render_to_string(&document, &context);
+  // Only the sequences that could end or derail the element being filled are broken up.
+  assert!(html.contains(r#"<style>.a::after { content: "a</b <\/STYLE><p>"; }</style>"#));
+  assert!(html.contains(r#"<script>var s = "<\!-- <\SCRIPT> </b> <\/Script >";</script>"#));
+
+  let reparsed = parse(&html);
+  assert_eq!(text_content(&select_one(&reparsed, "script").unwrap().unwrap()), r#"var s = "<\!-- <\SCRIPT> </b> <\/Script >";"#);
+}
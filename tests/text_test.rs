@@ -0,0 +1,76 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::RcDom;
+use rust_html5ever_sample::{arena::ArenaDom, select::select_one, text::{inner_text, text_content}};
+
+const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>테스트></title>
+    <style>p { color: red }</style>
+  </head>
+  <body id="[##_id_##]">
+    <h1>  제목   입니다 </h1>
+    <p>첫   번째
+       문단 <b>굵게</b>와 <i>기울임</i>.</p>
+    <p>둘째<br>줄바꿈</p>
+    <script>var x = "<p>no</p>";</script>
+    <pre>  코드
+    들여쓰기</pre>
+    <template><p>템플릿</p></template>
+    <table><tr><td>가</td><td>나</td></tr><tr><td>다</td><td>라</td></tr></table>
+    <ul><li>하나</li><li>둘</li></ul>
+    <my-element>
+      안녕하세요~ ^^
+    </my-element>
+    <div hidden>숨김</div>
+  </body>
+</html>
+"#;
+
+const EXPECTED: &str = "제목 입니다
+
+첫 번째 문단 굵게와 기울임.
+
+둘째
+줄바꿈
+
+  코드
+    들여쓰기
+가\t나
+다\t라
+하나
+둘
+안녕하세요~ ^^";
+
+#[test]
+fn text_content_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  let p = select_one(&dom.document, "p").unwrap().unwrap();
+  assert_eq!(text_content(&p), "첫   번째\n       문단 굵게와 기울임.");
+
+  let text = text_content(&dom.document);
+  assert!(text.contains("테스트>"));
+  assert!(text.contains("var x"));
+  assert!(!text.contains("템플릿"));
+}
+
+#[test]
+fn inner_text_test() {
+  let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+  assert_eq!(inner_text(&dom.document), EXPECTED);
+
+  let my_element = select_one(&dom.document, "my-element").unwrap().unwrap();
+  assert_eq!(inner_text(&my_element), "안녕하세요~ ^^");
+  assert_eq!(inner_text(&select_one(&dom.document, "p").unwrap().unwrap()), "첫 번째 문단 굵게와 기울임.");
+}
+
+#[test]
+fn inner_text_arena_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+  assert_eq!(inner_text(&document), EXPECTED);
+  assert_eq!(text_content(&document), {
+    let dom = parse_document(RcDom::default(), Default::default()).one(HTML);
+    text_content(&dom.document)
+  });
+}
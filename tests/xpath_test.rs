@@ -0,0 +1,106 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, RcDom};
+use rust_html5ever_sample::{arena::ArenaDom, node::DomNode, rcdom::convert_node_to_html_string, xpath::{xpath, Value, XPath, XPathError}};
+use std::collections::HashMap;
+
+const HTML: &str = r#"
+  <!DOCTYPE html>
+  <html>
+    <head>
+      <title>테스트></title>
+    </head>
+    <body id="[##_id_##]">
+      <s3>
+        테스트 !!!
+      </s3>
+      <my-element>
+        안녕하세요~ ^^
+      </my-element>
+      <ul>
+        <li class="a">1</li>
+        <li class="b">2</li>
+        <li class="a">3</li>
+      </ul>
+    </body>
+  </html>
+"#;
+
+fn parse(html: &str) -> Handle {
+  parse_document(RcDom::default(), Default::default()).one(html).document
+}
+
+fn names<N: DomNode>(nodes: &[N]) -> Vec<String> {
+  nodes.iter().map(|node| node.element_name().unwrap().local.to_string()).collect()
+}
+
+#[test]
+fn xpath_node_set_test() {
+  let document = parse(HTML);
+
+  let nodes = xpath(&document, "//body/*[contains(text(),'테스트')]").unwrap().nodes();
+  assert_eq!(names(&nodes), vec!["s3"]);
+  assert!(convert_node_to_html_string(&nodes[0]).contains("테스트 !!!"));
+
+  assert_eq!(xpath(&document, "//li[2]").unwrap().string(), "2");
+  assert_eq!(xpath(&document, "//li[last()]").unwrap().string(), "3");
+  assert_eq!(xpath(&document, "//li[@class='a']").unwrap().nodes().len(), 2);
+  assert_eq!(names(&xpath(&document, "//li[1]/ancestor::*").unwrap().nodes()), vec!["html", "body", "ul"]);
+  assert_eq!(xpath(&document, "//li[3]/preceding-sibling::li[1]").unwrap().string(), "2");
+  assert_eq!(names(&xpath(&document, "//s3 | //title").unwrap().nodes()), vec!["title", "s3"]);
+  assert_eq!(xpath(&document, "(//li)[position() > 1]").unwrap().nodes().len(), 2);
+  assert_eq!(xpath(&document, "/html/body/@id").unwrap().string(), "[##_id_##]");
+  assert_eq!(xpath(&document, "//ul/following::*").unwrap().nodes().len(), 0);
+  assert_eq!(names(&xpath(&document, "//ul/preceding::*[1]").unwrap().nodes()), vec!["my-element"]);
+}
+
+#[test]
+fn xpath_scalar_test() {
+  let document = parse(HTML);
+
+  assert_eq!(xpath(&document, "count(//li)").unwrap().number(), 3.0);
+  assert_eq!(xpath(&document, "sum(//li)").unwrap().number(), 6.0);
+  assert_eq!(xpath(&document, "normalize-space(//s3)").unwrap().string(), "테스트 !!!");
+  assert_eq!(xpath(&document, "string-length(normalize-space(//s3))").unwrap().number(), 7.0);
+  assert_eq!(xpath(&document, "substring('12345', 1.5, 2.6)").unwrap().string(), "234");
+  assert_eq!(xpath(&document, "translate('bar', 'abc', 'ABC')").unwrap().string(), "BAr");
+  assert_eq!(xpath(&document, "concat('a', 1 + 2, true())").unwrap().string(), "a3true");
+  assert_eq!(xpath(&document, "7 mod 3 * 2 div 4").unwrap().number(), 0.5);
+  assert_eq!(xpath(&document, "round(-2.5)").unwrap().number(), -2.0);
+  assert!(xpath(&document, "//li = 2").unwrap().boolean());
+  assert!(xpath(&document, "//li > 2 and not(//li > 3)").unwrap().boolean());
+  assert!(!xpath(&document, "//table").unwrap().boolean());
+  assert_eq!(xpath(&document, "name(//li[1]/@class)").unwrap().string(), "class");
+  assert_eq!(xpath(&document, "1 div 0").unwrap().string(), "Infinity");
+  assert!(matches!(xpath(&document, "'x'").unwrap(), Value::String(_)));
+}
+
+#[test]
+fn xpath_compiled_reuse_test() {
+  let query = XPath::compile("//li[@class = $class]").unwrap();
+  let first = parse("<ul><li class=\"x\">1</li></ul>");
+  let second = parse("<ul><li class=\"x\">1</li><li class=\"x\">2</li></ul>");
+
+  let variables = HashMap::from([("class".to_string(), Value::String("x".to_string()))]);
+  assert_eq!(query.evaluate_with_variables(&first, &variables).unwrap().nodes().len(), 1);
+  assert_eq!(query.evaluate_with_variables(&second, &variables).unwrap().nodes().len(), 2);
+  assert!(matches!(query.evaluate(&first), Err(XPathError::Evaluation(_))));
+}
+
+#[test]
+fn xpath_arena_test() {
+  let arena = ArenaDom::new();
+  let document = arena.parse_document(HTML);
+
+  let query = XPath::compile("//body/*[contains(text(),'테스트')]").unwrap();
+  assert_eq!(names(&query.select(&document).unwrap()), vec!["s3"]);
+  assert_eq!(xpath(&document, "count(//li[@class='a'])").unwrap().number(), 2.0);
+}
+
+#[test]
+fn xpath_syntax_error_test() {
+  assert!(matches!(XPath::compile("//li["), Err(XPathError::Syntax { .. })));
+  assert!(matches!(XPath::compile("foo(1)"), Err(XPathError::Syntax { .. })));
+  assert!(matches!(XPath::compile("count()"), Err(XPathError::Syntax { .. })));
+  assert!(matches!(XPath::compile("bogus::li"), Err(XPathError::Syntax { .. })));
+  assert!(matches!(XPath::compile("'abc"), Err(XPathError::Syntax { position: 0, .. })));
+}